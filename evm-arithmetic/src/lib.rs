@@ -0,0 +1,312 @@
+//! 按补码解释 U256 的有符号运算（SDIV、SMOD、SAR）以及其他需要特殊处理的算术指令
+//!
+//! stage1 的练习机器和 stage2 的解释器共用这些辅助函数：
+//! stage2 通过 `models::arithmetic` 重新导出，stage1 把 u64 字符号扩展到 U256 后调用。
+
+use primitive_types::{U256, U512};
+
+/// 有符号 256 位整数的最小值 (-2^255)，即 0x8000...0000
+pub const I256_MIN: U256 = U256([0, 0, 0, 0x8000_0000_0000_0000]);
+
+/// 判断一个 U256 在补码解释下是否为负数（最高位为 1）
+pub fn is_negative(value: U256) -> bool {
+    value.bit(255)
+}
+
+/// 补码取反：-x = !x + 1
+pub fn twos_complement(value: U256) -> U256 {
+    (!value).overflowing_add(U256::one()).0
+}
+
+/// 补码解释下的绝对值
+///
+/// 注意 I256_MIN 的绝对值无法用有符号数表示，
+/// 这里按无符号返回 2^255，恰好是除法需要的数值。
+fn abs(value: U256) -> U256 {
+    if is_negative(value) {
+        twos_complement(value)
+    } else {
+        value
+    }
+}
+
+/// SDIV: 有符号除法
+///
+/// - 除数为 0 时结果为 0
+/// - INT_MIN / -1 溢出，结果仍为 INT_MIN
+/// - 结果向 0 取整
+pub fn sdiv(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::zero();
+    }
+    if a == I256_MIN && b == U256::MAX {
+        return I256_MIN;
+    }
+
+    let quotient = abs(a) / abs(b);
+    if is_negative(a) != is_negative(b) {
+        twos_complement(quotient)
+    } else {
+        quotient
+    }
+}
+
+/// SMOD: 有符号取模
+///
+/// - 模数为 0 时结果为 0
+/// - 结果的符号与被除数 a 相同
+pub fn smod(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::zero();
+    }
+
+    let remainder = abs(a) % abs(b);
+    if is_negative(a) {
+        twos_complement(remainder)
+    } else {
+        remainder
+    }
+}
+
+/// SAR: 算术右移（高位补符号位）
+///
+/// 位移量 >= 256 时，负数结果为 -1（全 1），非负数结果为 0。
+pub fn sar(shift: U256, value: U256) -> U256 {
+    let negative = is_negative(value);
+
+    if shift >= U256::from(256) {
+        return if negative { U256::MAX } else { U256::zero() };
+    }
+
+    let shift = shift.as_usize();
+    if negative {
+        // 对取反后的值做逻辑右移再取反，等价于高位补 1
+        !((!value) >> shift)
+    } else {
+        value >> shift
+    }
+}
+
+/// SLT: 按有符号整数比较 a < b
+pub fn slt(a: U256, b: U256) -> bool {
+    match (is_negative(a), is_negative(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        // 同号时补码的大小关系与无符号比较一致
+        _ => a < b,
+    }
+}
+
+/// SGT: 按有符号整数比较 a > b
+pub fn sgt(a: U256, b: U256) -> bool {
+    slt(b, a)
+}
+
+/// BYTE: 取 value 的第 index 个字节（从最高位字节开始计数）
+///
+/// 下标 >= 32 时结果为 0。
+pub fn byte(index: U256, value: U256) -> U256 {
+    if index >= U256::from(32) {
+        return U256::zero();
+    }
+    U256::from(value.byte(31 - index.as_usize()))
+}
+
+/// SHL: 逻辑左移，位移量 >= 256 时结果为 0
+pub fn shl(shift: U256, value: U256) -> U256 {
+    if shift >= U256::from(256) {
+        U256::zero()
+    } else {
+        value << shift.as_usize()
+    }
+}
+
+/// SHR: 逻辑右移，位移量 >= 256 时结果为 0
+pub fn shr(shift: U256, value: U256) -> U256 {
+    if shift >= U256::from(256) {
+        U256::zero()
+    } else {
+        value >> shift.as_usize()
+    }
+}
+
+/// ADDMOD: (a + b) % n，加法在 512 位精度下完成，不会先回绕
+///
+/// 模数为 0 时结果为 0
+pub fn addmod(a: U256, b: U256, n: U256) -> U256 {
+    if n.is_zero() {
+        return U256::zero();
+    }
+    let sum = U512::from(a) + U512::from(b);
+    narrow(sum % U512::from(n))
+}
+
+/// MULMOD: (a * b) % n，乘法在 512 位精度下完成，不会先回绕
+///
+/// 模数为 0 时结果为 0
+pub fn mulmod(a: U256, b: U256, n: U256) -> U256 {
+    if n.is_zero() {
+        return U256::zero();
+    }
+    let product = U512::from(a) * U512::from(b);
+    narrow(product % U512::from(n))
+}
+
+/// EXP: base ** exponent mod 2^256，使用平方-乘算法
+pub fn exp(base: U256, exponent: U256) -> U256 {
+    let mut result = U256::one();
+    let mut base = base;
+    let mut exponent = exponent;
+
+    while !exponent.is_zero() {
+        if exponent.bit(0) {
+            result = result.overflowing_mul(base).0;
+        }
+        base = base.overflowing_mul(base).0;
+        exponent >>= 1;
+    }
+    result
+}
+
+/// 将取模后的 U512 收窄为 U256（余数一定小于模数，不会截断）
+fn narrow(value: U512) -> U256 {
+    U256::try_from(value).expect("remainder is smaller than a U256 modulus")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neg(value: u64) -> U256 {
+        twos_complement(U256::from(value))
+    }
+
+    #[test]
+    fn test_sdiv_documented_cases() {
+        // 10 / 10 = 1
+        assert_eq!(sdiv(U256::from(10), U256::from(10)), U256::one());
+        // -2 / -1 = 2
+        assert_eq!(sdiv(neg(2), neg(1)), U256::from(2));
+        // -10 / 3 = -3（向 0 取整）
+        assert_eq!(sdiv(neg(10), U256::from(3)), neg(3));
+        // 10 / -3 = -3
+        assert_eq!(sdiv(U256::from(10), neg(3)), neg(3));
+    }
+
+    #[test]
+    fn test_sdiv_edge_cases() {
+        // 除以 0 结果为 0
+        assert_eq!(sdiv(U256::from(7), U256::zero()), U256::zero());
+        assert_eq!(sdiv(neg(7), U256::zero()), U256::zero());
+        // INT_MIN / -1 = INT_MIN
+        assert_eq!(sdiv(I256_MIN, U256::MAX), I256_MIN);
+        // INT_MIN / 1 = INT_MIN
+        assert_eq!(sdiv(I256_MIN, U256::one()), I256_MIN);
+    }
+
+    #[test]
+    fn test_smod_sign_follows_dividend() {
+        // 10 % 3 = 1
+        assert_eq!(smod(U256::from(10), U256::from(3)), U256::one());
+        // -8 % -3 = -2
+        assert_eq!(smod(neg(8), neg(3)), neg(2));
+        // 8 % -3 = 2
+        assert_eq!(smod(U256::from(8), neg(3)), U256::from(2));
+        // 模 0 结果为 0
+        assert_eq!(smod(neg(8), U256::zero()), U256::zero());
+    }
+
+    #[test]
+    fn test_sar() {
+        // 2 >> 1 = 1
+        assert_eq!(sar(U256::one(), U256::from(2)), U256::one());
+        // -16 >> 4 = -1
+        assert_eq!(sar(U256::from(4), neg(16)), U256::MAX);
+        // -17 >> 1 = -9（向负无穷取整）
+        assert_eq!(sar(U256::one(), neg(17)), neg(9));
+        // 位移 >= 256
+        assert_eq!(sar(U256::from(256), neg(1)), U256::MAX);
+        assert_eq!(sar(U256::from(300), U256::from(5)), U256::zero());
+    }
+
+    #[test]
+    fn test_signed_comparison() {
+        // -1 < 1
+        assert!(slt(neg(1), U256::one()));
+        assert!(!slt(U256::one(), neg(1)));
+        assert!(sgt(U256::one(), neg(1)));
+        // -2 < -1
+        assert!(slt(neg(2), neg(1)));
+        // INT_MIN 是最小值，INT_MAX 是最大值
+        assert!(slt(I256_MIN, U256::zero()));
+        assert!(sgt(I256_MIN - 1, U256::zero()));
+        assert!(!slt(U256::from(5), U256::from(5)));
+    }
+
+    #[test]
+    fn test_byte() {
+        let value = U256::from_big_endian(&(1..=32).collect::<Vec<u8>>());
+        // 下标 0 是最高位字节，31 是最低位字节
+        assert_eq!(byte(U256::zero(), value), U256::from(1));
+        assert_eq!(byte(U256::from(31), value), U256::from(32));
+        // 下标越界
+        assert_eq!(byte(U256::from(32), value), U256::zero());
+        assert_eq!(byte(U256::MAX, value), U256::zero());
+    }
+
+    #[test]
+    fn test_shl_and_shr() {
+        assert_eq!(shl(U256::from(4), U256::one()), U256::from(16));
+        // 移出最高位的部分被丢弃
+        assert_eq!(shl(U256::one(), U256::MAX), U256::MAX - 1);
+        assert_eq!(shl(U256::from(255), U256::one()), I256_MIN);
+        // 位移 >= 256
+        assert_eq!(shl(U256::from(256), U256::one()), U256::zero());
+        assert_eq!(shl(U256::MAX, U256::MAX), U256::zero());
+
+        // SHR 是逻辑右移，高位补 0
+        assert_eq!(shr(U256::from(4), U256::from(16)), U256::one());
+        assert_eq!(shr(U256::from(255), U256::MAX), U256::one());
+        assert_eq!(shr(U256::from(256), U256::MAX), U256::zero());
+    }
+
+    #[test]
+    fn test_addmod_uses_full_width() {
+        // (2^256 - 1 + 2) % 3 = 2，而先回绕再取模会得到 1
+        assert_eq!(
+            addmod(U256::MAX, U256::from(2), U256::from(3)),
+            U256::from(2)
+        );
+        assert_eq!(
+            addmod(U256::from(10), U256::from(10), U256::from(8)),
+            U256::from(4)
+        );
+        assert_eq!(
+            addmod(U256::from(10), U256::from(10), U256::zero()),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn test_mulmod_uses_full_width() {
+        // (2^256 - 1)^2 % 12 = 9，而先回绕再取模会得到 1
+        assert_eq!(mulmod(U256::MAX, U256::MAX, U256::from(12)), U256::from(9));
+        assert_eq!(
+            mulmod(U256::from(10), U256::from(10), U256::from(8)),
+            U256::from(4)
+        );
+        assert_eq!(mulmod(U256::MAX, U256::MAX, U256::zero()), U256::zero());
+    }
+
+    #[test]
+    fn test_exp_wraps() {
+        assert_eq!(exp(U256::from(2), U256::from(10)), U256::from(1024));
+        assert_eq!(exp(U256::from(3), U256::zero()), U256::one());
+        assert_eq!(exp(U256::zero(), U256::zero()), U256::one());
+        // 2 ** 255 是最高位，2 ** 256 回绕为 0
+        assert_eq!(exp(U256::from(2), U256::from(255)), I256_MIN);
+        assert_eq!(exp(U256::from(2), U256::from(256)), U256::zero());
+        // (-1) ** 3 = -1
+        assert_eq!(exp(U256::MAX, U256::from(3)), U256::MAX);
+    }
+}
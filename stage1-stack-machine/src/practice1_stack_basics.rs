@@ -5,16 +5,26 @@
 #[derive(Debug)]
 struct SimpleStack {
     data: Vec<u64>, // 为了简化，使用 u64 而不是 H256
+    limit: usize,   // 栈最大深度
 }
 
 impl SimpleStack {
+    // EVM 栈的默认最大深度
+    const DEFAULT_LIMIT: usize = 1024;
+
     fn new() -> Self {
-        Self { data: Vec::new() }
+        Self::with_limit(Self::DEFAULT_LIMIT)
+    }
+
+    fn with_limit(limit: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            limit,
+        }
     }
 
     fn push(&mut self, value: u64) -> Result<(), &'static str> {
-        if self.data.len() >= 1000 {
-            // 简化的栈限制
+        if self.data.len() >= self.limit {
             return Err("Stack overflow");
         }
         self.data.push(value);
@@ -209,10 +219,40 @@ fn main() {
         Err(e) => println!("❌ 预期的错误: {}", e),
     }
 
+    // 练习 4: 展示栈溢出错误（把栈深度限制缩小到 2 便于观察）
+    println!("\n📚 练习 4: 栈溢出错误演示");
+    println!("{}", "-".repeat(30));
+
+    let mut small_stack = SimpleStack::with_limit(2);
+    let result = [1, 2, 3]
+        .into_iter()
+        .try_for_each(|value| small_stack.push(value));
+    match result {
+        Ok(()) => println!("✅ 练习 4 完成!"),
+        Err(e) => println!("❌ 预期的错误: {}", e),
+    }
+
     println!("\n🎓 学习总结:");
     println!("1. EVM 使用栈来存储临时数据");
     println!("2. ADD 指令需要从栈中弹出两个操作数");
     println!("3. 计算结果会被推回栈顶");
     println!("4. 必须确保栈中有足够的操作数，否则会发生下溢");
-    println!("5. 每条指令都有相应的 Gas 消耗");
+    println!("5. 栈深度有上限（EVM 为 1024），超过会发生上溢");
+    println!("6. 每条指令都有相应的 Gas 消耗");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_limit_is_configurable() {
+        assert_eq!(SimpleStack::new().limit, SimpleStack::DEFAULT_LIMIT);
+
+        let mut stack = SimpleStack::with_limit(2);
+        assert!(stack.push(1).is_ok());
+        assert!(stack.push(2).is_ok());
+        assert_eq!(stack.push(3), Err("Stack overflow"));
+        assert_eq!(stack.len(), 2);
+    }
 }
@@ -12,7 +12,8 @@ impl SimpleStack {
     }
 
     fn push(&mut self, value: u64) -> Result<(), &'static str> {
-        if self.data.len() >= 1000 {
+        if self.data.len() >= 1024 {
+            // EVM 栈最大深度
             return Err("Stack overflow");
         }
         self.data.push(value);
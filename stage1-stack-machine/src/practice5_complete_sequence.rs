@@ -1,8 +1,19 @@
 // 🎮 EVM 完整指令序列模拟练习 - 理解完整程序执行流程
 // 🔄 学习如何将多个指令组合成完整的 EVM 程序
 
+use evm_arithmetic::{sar, sdiv, smod};
+use primitive_types::U256;
 use std::collections::HashMap;
 
+// 把 u64 字按补码符号扩展为 U256，复用 evm-arithmetic 中的有符号运算
+fn sign_extend(value: u64) -> U256 {
+    if value >> 63 == 1 {
+        U256::from(value) | (U256::MAX << 64)
+    } else {
+        U256::from(value)
+    }
+}
+
 // 简化的栈实现
 #[derive(Debug, Clone)]
 struct SimpleStack {
@@ -49,6 +60,9 @@ enum Instruction {
     Sub,
     Mul,
     Div,
+    SDiv, // 有符号除法
+    SMod, // 有符号取模
+    Sar,  // 算术右移
 
     // 比较指令
     Lt, // 小于
@@ -232,6 +246,43 @@ impl CompleteEVM {
                 self.pc += 1;
             }
 
+            Instruction::SDiv => {
+                self.consume_gas(5)?;
+                let b = self.stack.pop()?;
+                let a = self.stack.pop()?;
+                // 结果取低 64 位，即补码下的 i64 结果（MIN / -1 仍为 MIN）
+                let result = sdiv(sign_extend(a), sign_extend(b)).low_u64();
+                self.stack.push(result)?;
+                println!("  ➗ SDIV: {} / {} = {}", a as i64, b as i64, result as i64);
+                println!("     栈状态: {:?} | Gas: +5", self.stack.data);
+                self.pc += 1;
+            }
+
+            Instruction::SMod => {
+                self.consume_gas(5)?;
+                let b = self.stack.pop()?;
+                let a = self.stack.pop()?;
+                let result = smod(sign_extend(a), sign_extend(b)).low_u64();
+                self.stack.push(result)?;
+                println!("  🔢 SMOD: {} % {} = {}", a as i64, b as i64, result as i64);
+                println!("     栈状态: {:?} | Gas: +5", self.stack.data);
+                self.pc += 1;
+            }
+
+            Instruction::Sar => {
+                self.consume_gas(3)?;
+                let shift = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                let result = sar(U256::from(shift), sign_extend(value)).low_u64();
+                self.stack.push(result)?;
+                println!(
+                    "  ➡️ SAR: {} >> {} = {}",
+                    value as i64, shift, result as i64
+                );
+                println!("     栈状态: {:?} | Gas: +3", self.stack.data);
+                self.pc += 1;
+            }
+
             Instruction::Lt => {
                 self.consume_gas(3)?;
                 let b = self.stack.pop()?;
@@ -523,6 +574,32 @@ fn main() {
         Err(e) => println!("❌ 执行错误: {}", e),
     }
 
+    // 练习 6: 有符号运算
+    println!("\n📚 练习 6: 有符号运算 (SDIV / SMOD / SAR)");
+    println!("------------------------------------------");
+
+    let instructions = vec![
+        Instruction::Push(-7i64 as u64),    // PC=0: 推入 -7
+        Instruction::Push(2),               // PC=1: 推入 2
+        Instruction::SDiv,                  // PC=2: -7 / 2 = -3（向 0 取整）
+        Instruction::Push(-7i64 as u64),    // PC=3: 推入 -7
+        Instruction::Push(2),               // PC=4: 推入 2
+        Instruction::SMod,                  // PC=5: -7 % 2 = -1（符号跟随被除数）
+        Instruction::Push(i64::MIN as u64), // PC=6: 推入最小值
+        Instruction::Push(-1i64 as u64),    // PC=7: 推入 -1
+        Instruction::SDiv,                  // PC=8: MIN / -1 溢出，结果仍为 MIN
+        Instruction::Push(-16i64 as u64),   // PC=9: 推入 -16
+        Instruction::Push(2),               // PC=10: 推入移位数 2
+        Instruction::Sar,                   // PC=11: -16 >> 2 = -4（保留符号位）
+        Instruction::Stop,                  // PC=12: 停止
+    ];
+
+    let mut evm = CompleteEVM::new(instructions, 1000);
+    match evm.run() {
+        Ok(()) => evm.print_final_state(),
+        Err(e) => println!("❌ 执行错误: {}", e),
+    }
+
     // 学习总结
     println!("\n🎓 练习5学习总结:");
     println!("===========================================");
@@ -533,5 +610,6 @@ fn main() {
     println!("5. 逻辑运算支持复杂的布尔表达式");
     println!("6. Gas消耗模型确保程序执行的可预测性");
     println!("7. 栈机器的简洁性使得程序验证变得容易");
+    println!("8. 有符号指令按补码解释同一个字，与 stage2 共用 evm-arithmetic");
     println!("\n🚀 恭喜！你已经完成了EVM基础阶段的所有练习！");
 }
@@ -0,0 +1,62 @@
+//! 解释器分发循环的基准测试
+//!
+//! 以固定的算术密集型字节码为负载，按每秒执行的指令数报告吞吐量，
+//! 用于发现指令分发方式改动（如改为跳转表）带来的性能回退。
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use stage2_architecture::BytecodeRunner;
+use std::hint::black_box;
+
+/// 足够执行下面所有程序的 gas
+const GAS: u64 = 10_000_000;
+
+/// PUSH1 1 之后重复 `count` 次 (PUSH1 1, ADD)
+///
+/// 返回字节码和执行的指令数。
+fn add_chain(count: usize) -> (Vec<u8>, u64) {
+    let mut code = vec![0x60, 0x01];
+    for _ in 0..count {
+        code.extend([0x60, 0x01, 0x01]);
+    }
+    (code, 2 * count as u64 + 1)
+}
+
+/// 交替执行 MUL、ADD、SUB、DIV、MOD，每轮 10 条指令
+fn mixed_arithmetic(rounds: usize) -> (Vec<u8>, u64) {
+    let mut code = vec![0x60, 0x03];
+    for _ in 0..rounds {
+        code.extend([
+            0x60, 0x07, 0x02, // MUL 7
+            0x60, 0x05, 0x01, // ADD 5
+            0x60, 0x01, 0x03, // SUB：1 - x
+            0x60, 0x03, 0x04, // DIV：3 / x
+            0x60, 0x0d, 0x06, // MOD：13 % x
+        ]);
+    }
+    (code, 10 * rounds as u64 + 1)
+}
+
+fn bench_interpreter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpreter");
+
+    for (name, (code, opcodes)) in [
+        ("add_100k", add_chain(100_000)),
+        ("mixed_arithmetic_20k", mixed_arithmetic(20_000)),
+    ] {
+        // 部署代码和计算代码哈希不计入测量
+        let mut runner = BytecodeRunner::new(&code);
+        group.throughput(Throughput::Elements(opcodes));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let result = runner.run(black_box(GAS));
+                assert!(result.success);
+                result
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_interpreter);
+criterion_main!(benches);
@@ -115,6 +115,11 @@ fn demonstrate_spec_comparison() {
             );
         }
     }
+
+    // 完整对比 Frontier vs London 的 Gas 常量和系统限制
+    println!("\n📋 Frontier vs London 完整对比:");
+    let diff = spec::SpecComparison::compare_all::<spec::Frontier, spec::London>();
+    print!("{}", diff.format_table());
 }
 
 /// 演示条件特性编译
@@ -115,7 +115,7 @@ fn demonstrate_transaction_execution() {
     println!("{}", "-".repeat(50));
 
     // 准备测试环境
-    let mut db = InMemoryDB::with_test_data();
+    let mut db = funded_test_db();
     db.enable_logging();
     let mut evm = create_berlin_evm(db);
 
@@ -136,6 +136,9 @@ fn demonstrate_transaction_execution() {
         data: vec![0x12, 0x34, 0x56, 0x78],
         gas_limit: 100000,
         gas_price: U256::from(20_000_000_000u64), // 20 gwei
+        nonce: None,
+        chain_id: None,
+        access_list: Vec::new(),
     };
 
     let call_result = evm.transact(call_tx).unwrap();
@@ -153,6 +156,9 @@ fn demonstrate_transaction_execution() {
         data: vec![0x60, 0x80, 0x60, 0x40, 0x52, 0x00], // 简单的合约字节码
         gas_limit: 200000,
         gas_price: U256::from(20_000_000_000u64),
+        nonce: None,
+        chain_id: None,
+        access_list: Vec::new(),
     };
 
     let create_result = evm.transact(create_tx).unwrap();
@@ -169,6 +175,21 @@ fn demonstrate_transaction_execution() {
     }
 }
 
+/// 测试数据库，并给调用者足够的余额预付 `gas_limit * gas_price + value`
+fn funded_test_db() -> InMemoryDB {
+    let mut db = InMemoryDB::with_test_data();
+    let caller = Address::from([1u8; 20]);
+    let account = db.get_all_accounts()[&caller].clone();
+    db.insert_account(
+        caller,
+        AccountInfo {
+            balance: U256::from(10u64).pow(U256::from(18)), // 1 ETH
+            ..account
+        },
+    );
+    db
+}
+
 /// 演示规范差异对执行的影响
 fn demonstrate_spec_impact_on_execution() {
     println!("\n⚖️ 演示 4: 规范差异对执行的影响");
@@ -185,20 +206,23 @@ fn demonstrate_spec_impact_on_execution() {
         data: vec![0x12, 0x34],
         gas_limit: 100000,
         gas_price: U256::from(20_000_000_000u64),
+        nonce: None,
+        chain_id: None,
+        access_list: Vec::new(),
     };
 
     println!("📊 相同交易在不同规范下的执行结果:");
 
     // Frontier 执行
-    let mut frontier_evm = create_frontier_evm(InMemoryDB::with_test_data());
+    let mut frontier_evm = create_frontier_evm(funded_test_db());
     let frontier_result = frontier_evm.transact(tx.clone()).unwrap();
 
     // Berlin 执行
-    let mut berlin_evm = create_berlin_evm(InMemoryDB::with_test_data());
+    let mut berlin_evm = create_berlin_evm(funded_test_db());
     let berlin_result = berlin_evm.transact(tx.clone()).unwrap();
 
     // London 执行
-    let mut london_evm = create_london_evm(InMemoryDB::with_test_data());
+    let mut london_evm = create_london_evm(funded_test_db());
     let london_result = london_evm.transact(tx.clone()).unwrap();
 
     println!("\n📈 Gas 消耗对比:");
@@ -250,8 +274,8 @@ fn demonstrate_evm_extensibility() {
 
     // 2. 可以添加新的规范
     println!("  ✅ 可扩展规范系统");
-    println!("     - 现有: Frontier, Berlin, London");
-    println!("     - 可添加: Shanghai, Cancun, Prague 等");
+    println!("     - 现有: Frontier, Berlin, London, Cancun");
+    println!("     - 可添加: Shanghai, Prague 等");
 
     // 3. 类型安全的组合
     println!("  ✅ 类型安全的组合");
@@ -0,0 +1,246 @@
+use crate::database::traits::*;
+use crate::models::*;
+use ethereum_types::{Address, H256, U256};
+use std::collections::{HashMap, HashSet};
+
+/// 写回缓存数据库
+///
+/// 包装一个可提交的数据库：读取时先查缓存，未命中再读内层数据库并缓存结果；
+/// `commit` 的状态变更只进入缓冲区并立即反映到缓存中，
+/// 直到调用 `flush` 才作为一个批次提交给内层数据库。
+#[derive(Debug, Clone)]
+pub struct CachedDB<DB: DatabaseCommit> {
+    /// 内层数据库
+    inner: DB,
+
+    /// 账户缓存，None 表示账户不存在（或已被删除）
+    accounts: HashMap<Address, Option<AccountInfo>>,
+
+    /// 存储槽缓存
+    storage: HashMap<(Address, U256), U256>,
+
+    /// 代码缓存 code_hash -> bytecode
+    code: HashMap<H256, Bytecode>,
+
+    /// 尚未刷新的账户删除，这些账户未缓存的存储槽一律视为 0
+    cleared: HashSet<Address>,
+
+    /// 尚未刷新的状态变更
+    pending: Vec<StateChange>,
+}
+
+impl<DB: DatabaseCommit> CachedDB<DB> {
+    /// 包装内层数据库
+    pub fn new(inner: DB) -> Self {
+        Self {
+            inner,
+            accounts: HashMap::new(),
+            storage: HashMap::new(),
+            code: HashMap::new(),
+            cleared: HashSet::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// 尚未刷新的状态变更
+    pub fn pending_changes(&self) -> &[StateChange] {
+        &self.pending
+    }
+
+    /// 把缓冲的状态变更作为一个批次提交给内层数据库
+    ///
+    /// 提交失败时缓冲区保持不变，可以重试。
+    pub fn flush(&mut self) -> Result<(), DB::Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.inner.commit(self.pending.clone())?;
+        self.pending.clear();
+        self.cleared.clear();
+        Ok(())
+    }
+
+    /// 内层数据库（不包含未刷新的变更）
+    pub fn inner(&self) -> &DB {
+        &self.inner
+    }
+
+    /// 取出内层数据库，丢弃未刷新的变更
+    pub fn into_inner(self) -> DB {
+        self.inner
+    }
+
+    /// 把一条变更应用到缓存上，语义与 `InMemoryDB::commit` 一致
+    fn apply(&mut self, change: &StateChange) -> Result<(), DB::Error> {
+        match change {
+            StateChange::CreateAccount { address, info } => {
+                if let Some(ref code) = info.code {
+                    let bytecode = Bytecode::new(code.clone());
+                    self.code.insert(bytecode.hash, bytecode);
+                }
+                self.accounts.insert(*address, Some(info.clone()));
+            }
+            StateChange::DeleteAccount { address } => {
+                self.accounts.insert(*address, None);
+                self.storage.retain(|(addr, _), _| addr != address);
+                self.cleared.insert(*address);
+            }
+            StateChange::UpdateBalance { address, balance } => {
+                if let Some(account) = self.account_mut(*address)? {
+                    account.balance = *balance;
+                }
+            }
+            StateChange::UpdateNonce { address, nonce } => {
+                if let Some(account) = self.account_mut(*address)? {
+                    account.nonce = *nonce;
+                }
+            }
+            StateChange::SetCode { address, code } => {
+                self.code.insert(code.hash, code.clone());
+                if let Some(account) = self.account_mut(*address)? {
+                    account.code_hash = code.hash;
+                    account.code = Some(code.bytes.clone());
+                }
+            }
+            StateChange::UpdateStorage {
+                address,
+                index,
+                value,
+            } => {
+                self.storage.insert((*address, *index), *value);
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取（必要时加载）账户缓存的可变引用
+    fn account_mut(&mut self, address: Address) -> Result<Option<&mut AccountInfo>, DB::Error> {
+        self.basic(address)?;
+        Ok(self
+            .accounts
+            .get_mut(&address)
+            .and_then(|account| account.as_mut()))
+    }
+}
+
+impl<DB: DatabaseCommit> Database for CachedDB<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(account) = self.accounts.get(&address) {
+            return Ok(account.clone());
+        }
+        let account = self.inner.basic(address)?;
+        self.accounts.insert(address, account.clone());
+        Ok(account)
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Result<Bytecode, Self::Error> {
+        if let Some(code) = self.code.get(&code_hash) {
+            return Ok(code.clone());
+        }
+        let code = self.inner.code_by_hash(code_hash)?;
+        self.code.insert(code_hash, code.clone());
+        Ok(code)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.storage.get(&(address, index)) {
+            return Ok(*value);
+        }
+        let value = if self.cleared.contains(&address) {
+            U256::zero()
+        } else {
+            self.inner.storage(address, index)?
+        };
+        self.storage.insert((address, index), value);
+        Ok(value)
+    }
+}
+
+impl<DB: DatabaseCommit> DatabaseCommit for CachedDB<DB> {
+    /// 只缓冲变更，不写入内层数据库
+    fn commit(&mut self, changes: Vec<StateChange>) -> Result<(), Self::Error> {
+        for change in &changes {
+            self.apply(change)?;
+        }
+        self.pending.extend(changes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDB;
+    use crate::test_utils::addr;
+
+    fn store(address: Address, index: u64, value: u64) -> StateChange {
+        StateChange::UpdateStorage {
+            address,
+            index: U256::from(index),
+            value: U256::from(value),
+        }
+    }
+
+    #[test]
+    fn test_read_sees_pending_write() {
+        let contract = addr(2);
+        let mut db = CachedDB::new(InMemoryDB::with_test_data());
+        assert_eq!(db.storage(contract, U256::zero()), Ok(U256::from(42)));
+
+        db.commit(vec![store(contract, 0, 7), store(contract, 5, 9)])
+            .unwrap();
+
+        // 缓存中可见，内层数据库尚未改变
+        assert_eq!(db.pending_changes().len(), 2);
+        assert_eq!(db.storage(contract, U256::zero()), Ok(U256::from(7)));
+        assert_eq!(db.storage(contract, U256::from(5)), Ok(U256::from(9)));
+        let mut inner = db.inner().clone();
+        assert_eq!(inner.storage(contract, U256::zero()), Ok(U256::from(42)));
+    }
+
+    #[test]
+    fn test_flush_persists_batch() {
+        let user = addr(1);
+        let contract = addr(2);
+        let mut db = CachedDB::new(InMemoryDB::with_test_data());
+
+        db.commit(vec![
+            store(contract, 1, 0),
+            StateChange::UpdateBalance {
+                address: user,
+                balance: U256::from(1),
+            },
+        ])
+        .unwrap();
+        assert_eq!(db.basic(user).unwrap().unwrap().balance, U256::from(1));
+
+        db.flush().unwrap();
+        assert!(db.pending_changes().is_empty());
+
+        let mut inner = db.into_inner();
+        assert_eq!(inner.storage(contract, U256::one()), Ok(U256::zero()));
+        assert_eq!(inner.basic(user).unwrap().unwrap().balance, U256::from(1));
+        // 未被修改的状态保持原样
+        assert_eq!(inner.storage(contract, U256::zero()), Ok(U256::from(42)));
+    }
+
+    #[test]
+    fn test_reads_are_cached_and_deletes_clear_storage() {
+        let contract = addr(2);
+        let mut inner = InMemoryDB::with_test_data();
+        inner.enable_logging();
+        let mut db = CachedDB::new(inner);
+
+        db.storage(contract, U256::zero()).unwrap();
+        db.storage(contract, U256::zero()).unwrap();
+        assert_eq!(db.inner().get_access_log().len(), 1);
+
+        db.commit(vec![StateChange::DeleteAccount { address: contract }])
+            .unwrap();
+        assert_eq!(db.basic(contract), Ok(None));
+        assert_eq!(db.storage(contract, U256::zero()), Ok(U256::zero()));
+        assert_eq!(db.storage(contract, U256::one()), Ok(U256::zero()));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::database::memory::InMemoryDB;
+use crate::database::traits::*;
+use crate::models::*;
+use ethereum_types::{Address, H256, U256};
+use std::collections::HashSet;
+
+/// 分叉数据库
+///
+/// 在任意只读的后备数据库（例如远程节点）之上叠加一层本地 `InMemoryDB`：
+/// 读取时先查本地层，未命中再读后备数据库并缓存到本地层；
+/// `commit` 只写入本地层，因此本地写入会遮蔽后备数据库中的值。
+#[derive(Debug, Clone)]
+pub struct ForkDb<DB: Database> {
+    /// 本地层，保存写入和已读取过的状态
+    overlay: InMemoryDB,
+
+    /// 后备数据库，只读
+    fallback: DB,
+
+    /// 本地层已知的账户（包括已确认不存在或已被删除的账户）
+    accounts: HashSet<Address>,
+
+    /// 本地层已知的存储槽（包括写为 0 的槽位）
+    slots: HashSet<(Address, U256)>,
+
+    /// 本地层已知的代码哈希
+    code: HashSet<H256>,
+
+    /// 在本地被删除的账户，这些账户未知的存储槽一律视为 0
+    cleared: HashSet<Address>,
+}
+
+impl<DB: Database> ForkDb<DB> {
+    /// 以空的本地层分叉 `fallback`
+    pub fn new(fallback: DB) -> Self {
+        Self {
+            overlay: InMemoryDB::new(),
+            fallback,
+            accounts: HashSet::new(),
+            slots: HashSet::new(),
+            code: HashSet::new(),
+            cleared: HashSet::new(),
+        }
+    }
+
+    /// 本地层
+    pub fn overlay(&self) -> &InMemoryDB {
+        &self.overlay
+    }
+
+    /// 后备数据库
+    pub fn fallback(&self) -> &DB {
+        &self.fallback
+    }
+
+    /// 记录状态变更涉及的账户和槽位，必要时先把账户从后备数据库读入本地层
+    fn track(&mut self, change: &StateChange) -> Result<(), DB::Error> {
+        match change {
+            StateChange::CreateAccount { address, info } => {
+                self.accounts.insert(*address);
+                if info.code.is_some() {
+                    self.code.insert(info.code_hash);
+                }
+            }
+            StateChange::DeleteAccount { address } => {
+                self.accounts.insert(*address);
+                self.slots.retain(|(addr, _)| addr != address);
+                self.cleared.insert(*address);
+            }
+            StateChange::UpdateBalance { address, .. }
+            | StateChange::UpdateNonce { address, .. } => {
+                self.basic(*address)?;
+            }
+            StateChange::SetCode { address, code } => {
+                self.basic(*address)?;
+                self.code.insert(code.hash);
+            }
+            StateChange::UpdateStorage { address, index, .. } => {
+                self.slots.insert((*address, *index));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<DB: Database> Database for ForkDb<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if self.accounts.contains(&address) {
+            return Ok(self
+                .overlay
+                .basic(address)
+                .expect("InMemoryDB 的读取不会失败"));
+        }
+        let account = self.fallback.basic(address)?;
+        if let Some(ref info) = account {
+            self.overlay.insert_account(address, info.clone());
+            if info.code.is_some() {
+                self.code.insert(info.code_hash);
+            }
+        }
+        self.accounts.insert(address);
+        Ok(account)
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Result<Bytecode, Self::Error> {
+        if code_hash.is_zero() || self.code.contains(&code_hash) {
+            return Ok(self
+                .overlay
+                .code_by_hash(code_hash)
+                .expect("InMemoryDB 的读取不会失败"));
+        }
+        let code = self.fallback.code_by_hash(code_hash)?;
+        self.overlay.insert_code(code.clone());
+        self.code.insert(code_hash);
+        Ok(code)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if self.slots.contains(&(address, index)) || self.cleared.contains(&address) {
+            return Ok(self
+                .overlay
+                .storage(address, index)
+                .expect("InMemoryDB 的读取不会失败"));
+        }
+        let value = self.fallback.storage(address, index)?;
+        self.overlay.insert_storage(address, index, value);
+        self.slots.insert((address, index));
+        Ok(value)
+    }
+}
+
+impl<DB: Database> DatabaseCommit for ForkDb<DB> {
+    /// 只写入本地层，后备数据库保持不变
+    fn commit(&mut self, changes: Vec<StateChange>) -> Result<(), Self::Error> {
+        for change in &changes {
+            self.track(change)?;
+        }
+        self.overlay
+            .commit(changes)
+            .expect("InMemoryDB 的提交不会失败");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::addr;
+
+    #[test]
+    fn test_local_write_shadows_fallback() {
+        let contract = addr(2);
+        let mut fallback = InMemoryDB::with_test_data();
+        fallback.enable_logging();
+        let mut db = ForkDb::new(fallback);
+
+        db.commit(vec![
+            StateChange::UpdateStorage {
+                address: contract,
+                index: U256::zero(),
+                value: U256::from(7),
+            },
+            // 写为 0 同样会遮蔽后备数据库中的值
+            StateChange::UpdateStorage {
+                address: contract,
+                index: U256::from(5),
+                value: U256::zero(),
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(db.storage(contract, U256::zero()), Ok(U256::from(7)));
+        assert_eq!(db.storage(contract, U256::from(5)), Ok(U256::zero()));
+        // 未写入的槽位读穿到后备数据库，之后从本地层读取
+        assert_eq!(db.storage(contract, U256::one()), Ok(U256::from(100)));
+        assert_eq!(db.storage(contract, U256::one()), Ok(U256::from(100)));
+        assert_eq!(db.fallback().get_access_log().len(), 1);
+
+        // 后备数据库没有被修改
+        let mut fallback = db.fallback().clone();
+        assert_eq!(fallback.storage(contract, U256::zero()), Ok(U256::from(42)));
+    }
+
+    #[test]
+    fn test_account_updates_apply_to_fetched_accounts() {
+        let user = addr(1);
+        let contract = addr(2);
+        let mut db = ForkDb::new(InMemoryDB::with_test_data());
+
+        // 只存在于后备数据库中的账户也能被更新
+        db.commit(vec![StateChange::UpdateBalance {
+            address: user,
+            balance: U256::from(1),
+        }])
+        .unwrap();
+        assert_eq!(db.basic(user).unwrap().unwrap().balance, U256::from(1));
+        assert_eq!(db.basic(user).unwrap().unwrap().nonce, 5);
+
+        // 代码从后备数据库读入并缓存
+        let code = db.code(contract).unwrap();
+        assert_eq!(code.bytes, vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+        assert_eq!(db.overlay().clone().code(contract), Ok(code));
+
+        // 在本地删除的账户不再读穿到后备数据库
+        db.commit(vec![StateChange::DeleteAccount { address: contract }])
+            .unwrap();
+        assert_eq!(db.basic(contract), Ok(None));
+        assert_eq!(db.storage(contract, U256::zero()), Ok(U256::zero()));
+    }
+}
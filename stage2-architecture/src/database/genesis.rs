@@ -0,0 +1,196 @@
+use crate::database::memory::InMemoryDB;
+use crate::models::*;
+use ethereum_types::{Address, H256, U256};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+/// genesis `alloc` 中的单个账户
+///
+/// `balance` 和 `nonce` 与 geth 的 `HexOrDecimal256` 相同：带 `0x` 前缀的字符串按十六进制、
+/// 不带前缀的字符串按十进制解析，也可以是 JSON 整数。缺省字段取零值。
+#[derive(Debug, Deserialize)]
+struct GenesisAccount {
+    #[serde(default, deserialize_with = "hex_or_decimal")]
+    balance: U256,
+    #[serde(default, deserialize_with = "hex_or_decimal_u64")]
+    nonce: u64,
+    #[serde(default)]
+    code: Option<Bytecode>,
+    #[serde(default)]
+    storage: BTreeMap<U256, U256>,
+}
+
+/// genesis 输入：完整的 genesis 文件（只读取 `alloc`），或者单独的 alloc 映射
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum GenesisInput {
+    Genesis {
+        alloc: BTreeMap<Address, GenesisAccount>,
+    },
+    Alloc(BTreeMap<Address, GenesisAccount>),
+}
+
+/// 十六进制或十进制书写的数值
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HexOrDecimal {
+    Text(String),
+    Number(u64),
+}
+
+/// 带 `0x` 前缀按十六进制解析，否则按十进制解析
+fn parse_hex_or_decimal(text: &str) -> Option<U256> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) if !hex.is_empty() => U256::from_str_radix(hex, 16).ok(),
+        Some(_) => None,
+        None => U256::from_dec_str(text).ok(),
+    }
+}
+
+fn hex_or_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    match HexOrDecimal::deserialize(deserializer)? {
+        HexOrDecimal::Number(number) => Ok(U256::from(number)),
+        HexOrDecimal::Text(text) => parse_hex_or_decimal(&text)
+            .ok_or_else(|| D::Error::custom(format!("invalid number: {}", text))),
+    }
+}
+
+/// 与 `hex_or_decimal` 相同，超过 u64 的值被拒绝而不是截断
+fn hex_or_decimal_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = hex_or_decimal(deserializer)?;
+    if value > U256::from(u64::MAX) {
+        return Err(D::Error::custom(format!("value exceeds u64: {}", value)));
+    }
+    Ok(value.as_u64())
+}
+
+impl InMemoryDB {
+    /// 从 geth 风格的 genesis alloc 构建数据库
+    ///
+    /// 输入可以是带 `alloc` 字段的完整 genesis 文件（其余字段被忽略），
+    /// 也可以直接是 地址 -> {balance, nonce, code, storage} 的映射。
+    /// 代码按十六进制解码并计算 `code_hash`，值为 0 的存储槽被忽略。
+    /// 解析失败时返回 `Error::Genesis`。
+    pub fn from_genesis_json(json: &str) -> Result<Self, Error> {
+        let input = serde_json::from_str(json).map_err(|err| Error::Genesis(err.to_string()))?;
+        let alloc = match input {
+            GenesisInput::Genesis { alloc } | GenesisInput::Alloc(alloc) => alloc,
+        };
+
+        let mut db = Self::new();
+        for (address, account) in alloc {
+            let (code_hash, code) = match account.code {
+                Some(code) if !code.bytes.is_empty() => (code.hash, Some(code.bytes)),
+                _ => (H256::zero(), None),
+            };
+            db.insert_account(
+                address,
+                AccountInfo {
+                    balance: account.balance,
+                    nonce: account.nonce,
+                    code_hash,
+                    code,
+                },
+            );
+            for (index, value) in account.storage {
+                if !value.is_zero() {
+                    db.insert_storage(address, index, value);
+                }
+            }
+        }
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::test_utils::addr;
+
+    #[test]
+    fn test_load_two_account_genesis() {
+        let json = r#"{
+            "0x0101010101010101010101010101010101010101": {
+                "balance": "0xde0b6b3a7640000",
+                "nonce": "0x5"
+            },
+            "0x0202020202020202020202020202020202020202": {
+                "balance": "0x0",
+                "code": "0x6080604052",
+                "storage": {
+                    "0x00": "0x2a",
+                    "0x01": "0x64"
+                }
+            }
+        }"#;
+        let user = addr(1);
+        let contract = addr(2);
+
+        let mut db = InMemoryDB::from_genesis_json(json).unwrap();
+
+        let account = db.basic(user).unwrap().unwrap();
+        assert_eq!(account.balance, U256::exp10(18));
+        assert_eq!(account.nonce, 5);
+        assert!(db.code(user).unwrap().bytes.is_empty());
+
+        let code = db.code(contract).unwrap();
+        let expected = vec![0x60, 0x80, 0x60, 0x40, 0x52];
+        assert_eq!(code.bytes, expected);
+        assert_eq!(code.hash, keccak_hash::keccak(&expected));
+        assert_eq!(db.storage(contract, U256::zero()), Ok(U256::from(42)));
+        assert_eq!(db.storage(contract, U256::one()), Ok(U256::from(100)));
+        assert_eq!(db.storage(contract, U256::from(2)), Ok(U256::zero()));
+    }
+
+    #[test]
+    fn test_load_full_genesis_file() {
+        let json = r#"{
+            "config": { "chainId": 1337 },
+            "gasLimit": "0x1c9c380",
+            "alloc": {
+                "0x0101010101010101010101010101010101010101": { "balance": "0x64" },
+                "0x0202020202020202020202020202020202020202": {
+                    "code": "0x00",
+                    "storage": { "0x07": "0x01" }
+                }
+            }
+        }"#;
+
+        let mut db = InMemoryDB::from_genesis_json(json).unwrap();
+        assert_eq!(db.basic(addr(1)).unwrap().unwrap().balance, U256::from(100));
+        assert_eq!(db.code(addr(2)).unwrap().bytes, vec![0x00]);
+        assert_eq!(db.storage(addr(2), U256::from(7)), Ok(U256::one()));
+        assert_eq!(db.get_all_accounts().len(), 2);
+    }
+
+    #[test]
+    fn test_genesis_rejects_invalid_code() {
+        let json = r#"{ "0x0202020202020202020202020202020202020202": { "code": "0x60zz" } }"#;
+        assert!(matches!(
+            InMemoryDB::from_genesis_json(json),
+            Err(Error::Genesis(_))
+        ));
+    }
+
+    #[test]
+    fn test_genesis_numbers_are_hex_or_decimal() {
+        let json = r#"{
+            "0x0101010101010101010101010101010101010101": { "balance": "1000", "nonce": "7" },
+            "0x0202020202020202020202020202020202020202": { "balance": "0x1000", "nonce": 3 }
+        }"#;
+
+        let mut db = InMemoryDB::from_genesis_json(json).unwrap();
+        let decimal = db.basic(addr(1)).unwrap().unwrap();
+        assert_eq!((decimal.balance, decimal.nonce), (U256::from(1000), 7));
+        let hex = db.basic(addr(2)).unwrap().unwrap();
+        assert_eq!((hex.balance, hex.nonce), (U256::from(0x1000), 3));
+
+        // nonce 超过 u64 时报错而不是截断
+        let json = r#"{ "0x0101010101010101010101010101010101010101": { "nonce": "0x10000000000000000" } }"#;
+        assert!(InMemoryDB::from_genesis_json(json).is_err());
+        let json = r#"{ "0x0101010101010101010101010101010101010101": { "balance": "12ab" } }"#;
+        assert!(InMemoryDB::from_genesis_json(json).is_err());
+    }
+}
@@ -57,6 +57,11 @@ impl InMemoryDB {
         self.accounts.insert(address, info);
     }
 
+    /// 预设字节码，按代码哈希索引
+    pub fn insert_code(&mut self, code: Bytecode) {
+        self.code.insert(code.hash, code);
+    }
+
     /// 预设存储值（用于测试）
     pub fn insert_storage(&mut self, address: Address, index: U256, value: U256) {
         self.storage.insert((address, index), value);
@@ -76,6 +81,57 @@ impl InMemoryDB {
             .collect()
     }
 
+    /// 把全部状态导出为状态变更列表
+    ///
+    /// 先按地址排序输出 `CreateAccount`，再按 (地址, 槽位) 排序输出 `UpdateStorage`，
+    /// 结果是确定的，可以直接序列化保存；用 `from_dump` 恢复。
+    pub fn dump(&self) -> Vec<StateChange> {
+        let mut accounts: Vec<_> = self.accounts.iter().collect();
+        accounts.sort_by_key(|(address, _)| **address);
+        let mut storage: Vec<_> = self.storage.iter().collect();
+        storage.sort_by_key(|(key, _)| **key);
+
+        accounts
+            .into_iter()
+            .map(|(address, info)| StateChange::CreateAccount {
+                address: *address,
+                info: info.clone(),
+            })
+            .chain(storage.into_iter().map(|((address, index), value)| {
+                StateChange::UpdateStorage {
+                    address: *address,
+                    index: *index,
+                    value: *value,
+                }
+            }))
+            .collect()
+    }
+
+    /// 从 `dump` 导出的状态变更重建数据库
+    pub fn from_dump(changes: Vec<StateChange>) -> Self {
+        let mut db = Self::new();
+        db.commit(changes).expect("InMemoryDB 的提交不会失败");
+        db
+    }
+
+    /// 把全部状态序列化为 bincode 字节，用于保存快照
+    ///
+    /// 地址、哈希和 U256 按定长大端字节写入，而不是 JSON 使用的十六进制字符串。
+    #[cfg(feature = "serde")]
+    pub fn dump_to_bytes(&self) -> Vec<u8> {
+        let snapshot: Vec<SnapshotEntry> = self.dump().into_iter().map(Into::into).collect();
+        bincode::serialize(&snapshot).expect("快照的序列化不会失败")
+    }
+
+    /// 从 `dump_to_bytes` 生成的字节恢复数据库
+    #[cfg(feature = "serde")]
+    pub fn load_from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        let snapshot: Vec<SnapshotEntry> = bincode::deserialize(bytes)?;
+        Ok(Self::from_dump(
+            snapshot.into_iter().map(Into::into).collect(),
+        ))
+    }
+
     /// 记录访问日志
     fn log(&mut self, operation: &str) {
         if self.log_access {
@@ -84,6 +140,90 @@ impl InMemoryDB {
     }
 }
 
+// === bincode 快照格式 ===
+
+/// 快照中的一条记录，对应 `dump` 输出的两种状态变更
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+enum SnapshotEntry {
+    Account {
+        address: [u8; 20],
+        balance: [u8; 32],
+        nonce: u64,
+        code_hash: [u8; 32],
+        code: Option<Vec<u8>>,
+    },
+    Storage {
+        address: [u8; 20],
+        index: [u8; 32],
+        value: [u8; 32],
+    },
+}
+
+#[cfg(feature = "serde")]
+fn u256_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+#[cfg(feature = "serde")]
+impl From<StateChange> for SnapshotEntry {
+    fn from(change: StateChange) -> Self {
+        match change {
+            StateChange::CreateAccount { address, info } => SnapshotEntry::Account {
+                address: address.0,
+                balance: u256_bytes(info.balance),
+                nonce: info.nonce,
+                code_hash: info.code_hash.0,
+                code: info.code,
+            },
+            StateChange::UpdateStorage {
+                address,
+                index,
+                value,
+            } => SnapshotEntry::Storage {
+                address: address.0,
+                index: u256_bytes(index),
+                value: u256_bytes(value),
+            },
+            other => unreachable!("dump 只输出账户和存储槽: {:?}", other),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SnapshotEntry> for StateChange {
+    fn from(entry: SnapshotEntry) -> Self {
+        match entry {
+            SnapshotEntry::Account {
+                address,
+                balance,
+                nonce,
+                code_hash,
+                code,
+            } => StateChange::CreateAccount {
+                address: Address::from(address),
+                info: AccountInfo {
+                    balance: U256::from_big_endian(&balance),
+                    nonce,
+                    code_hash: H256::from(code_hash),
+                    code,
+                },
+            },
+            SnapshotEntry::Storage {
+                address,
+                index,
+                value,
+            } => StateChange::UpdateStorage {
+                address: Address::from(address),
+                index: U256::from_big_endian(&index),
+                value: U256::from_big_endian(&value),
+            },
+        }
+    }
+}
+
 impl Default for InMemoryDB {
     fn default() -> Self {
         Self::new()
@@ -214,4 +354,145 @@ impl InMemoryDB {
 
         db
     }
+
+    /// 从十六进制字符串部署合约代码
+    ///
+    /// 支持可选的 `0x` 前缀，代码哈希按解码后的字节计算。
+    pub fn deploy_hex(&mut self, address: Address, hex: &str, balance: U256) -> Result<(), Error> {
+        let bytecode = Bytecode::from_hex(hex)?;
+
+        self.insert_account(
+            address,
+            AccountInfo {
+                balance,
+                nonce: 1,
+                code_hash: bytecode.hash,
+                code: Some(bytecode.bytes),
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::addr;
+
+    #[test]
+    fn test_deploy_hex() {
+        let mut db = InMemoryDB::new();
+        let address = addr(3);
+        db.deploy_hex(address, "0x6080604052", U256::from(10))
+            .unwrap();
+
+        let code = db.code(address).unwrap();
+        let expected = vec![0x60, 0x80, 0x60, 0x40, 0x52];
+        assert_eq!(code.bytes, expected);
+        assert_eq!(code.hash, keccak_hash::keccak(&expected));
+        assert_eq!(db.basic(address).unwrap().unwrap().balance, U256::from(10));
+
+        // 不带前缀同样可以
+        db.deploy_hex(address, "6080604052", U256::zero()).unwrap();
+        assert_eq!(db.code(address).unwrap().bytes, expected);
+    }
+
+    #[test]
+    fn test_deploy_hex_rejects_invalid() {
+        let mut db = InMemoryDB::new();
+        let address = addr(3);
+        assert_eq!(
+            db.deploy_hex(address, "0x60zz", U256::zero()),
+            Err(Error::InvalidHex)
+        );
+        assert_eq!(
+            db.deploy_hex(address, "0x608", U256::zero()),
+            Err(Error::InvalidHex)
+        );
+        assert!(db.basic(address).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dump_round_trip() {
+        let db = InMemoryDB::with_test_data();
+        let dump = db.dump();
+        // 2 个账户 + 2 个存储槽，账户在前
+        assert_eq!(dump.len(), 4);
+        assert!(matches!(dump[0], StateChange::CreateAccount { .. }));
+        assert!(matches!(dump[3], StateChange::UpdateStorage { .. }));
+
+        let restored = InMemoryDB::from_dump(dump.clone());
+        assert_eq!(restored.dump(), dump);
+        assert_eq!(restored.get_all_accounts(), db.get_all_accounts());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_dump_json_round_trip() {
+        let contract = addr(2);
+        let db = InMemoryDB::with_test_data();
+        let dump = db.dump();
+
+        let json = serde_json::to_string(&dump).unwrap();
+        let decoded: Vec<StateChange> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, dump);
+
+        let mut restored = InMemoryDB::from_dump(decoded);
+        assert_eq!(restored.storage(contract, U256::zero()), Ok(U256::from(42)));
+        assert_eq!(restored.code(contract), db.clone().code(contract));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_dump_bytes_round_trip() {
+        let db = InMemoryDB::with_test_data();
+        let bytes = db.dump_to_bytes();
+
+        let restored = InMemoryDB::load_from_bytes(&bytes).unwrap();
+        assert_eq!(restored.dump(), db.dump());
+        assert_eq!(restored.get_all_accounts(), db.get_all_accounts());
+
+        // 截断的数据无法恢复
+        assert!(InMemoryDB::load_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_dump_bytes_use_fixed_size_values() {
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            Address::repeat_byte(0x11),
+            AccountInfo {
+                balance: U256::from(1_000_000u64),
+                nonce: 7,
+                ..Default::default()
+            },
+        );
+        db.insert_storage(Address::repeat_byte(0x11), U256::one(), U256::MAX);
+        let bytes = db.dump_to_bytes();
+
+        // 列表长度 8 字节；每条记录 4 字节变体标签
+        // 账户：地址 20 + 余额 32 + nonce 8 + 代码哈希 32 + 无代码 1
+        // 存储槽：地址 20 + 槽位 32 + 值 32
+        assert_eq!(
+            bytes.len(),
+            8 + (4 + 20 + 32 + 8 + 32 + 1) + (4 + 20 + 32 + 32)
+        );
+        assert_eq!(
+            InMemoryDB::load_from_bytes(&bytes).unwrap().dump(),
+            db.dump()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bytecode_json_uses_hex() {
+        let code = Bytecode::new(vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+        let json = serde_json::to_string(&code).unwrap();
+        assert_eq!(json, "\"0x6080604052\"");
+        assert_eq!(serde_json::from_str::<Bytecode>(&json).unwrap(), code);
+
+        let result: Result<Bytecode, _> = serde_json::from_str("\"0x60zz\"");
+        assert!(result.is_err());
+    }
 }
@@ -1,5 +1,11 @@
+pub mod cache;
+pub mod fork;
+#[cfg(feature = "serde")]
+pub mod genesis;
 pub mod memory;
 pub mod traits;
 
+pub use cache::*;
+pub use fork::*;
 pub use memory::*;
 pub use traits::*;
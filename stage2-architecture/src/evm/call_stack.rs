@@ -1,12 +1,13 @@
 use crate::models::*;
 use ethereum_types::{Address, U256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 /// EVM 调用帧
 ///
 /// 每个调用帧代表一次函数调用的上下文，包含了该调用的所有必要信息。
 /// 这是实现 EVM 调用栈的核心数据结构。
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CallFrame {
     /// 调用者地址
     pub caller: Address,
@@ -29,6 +30,9 @@ pub struct CallFrame {
     /// 已使用的 Gas
     pub gas_used: u64,
 
+    /// 发起当前子调用之前的已用 Gas，子调用结束时据此结算
+    pub gas_snapshot: u64,
+
     /// 是否为只读调用（STATICCALL）
     pub read_only: bool,
 
@@ -79,6 +83,7 @@ impl CallFrame {
             data,
             gas_limit,
             gas_used: 0,
+            gas_snapshot: 0,
             read_only: call_type == CallType::StaticCall,
             call_type,
             depth,
@@ -105,6 +110,7 @@ impl CallFrame {
             data,
             gas_limit,
             gas_used: 0,
+            gas_snapshot: 0,
             read_only: false,
             call_type: CallType::DelegateCall,
             depth,
@@ -130,6 +136,7 @@ impl CallFrame {
             data: init_code,
             gas_limit,
             gas_used: 0,
+            gas_snapshot: 0,
             read_only: false,
             call_type: create_type,
             depth,
@@ -156,6 +163,151 @@ impl CallFrame {
     pub fn can_modify_state(&self) -> bool {
         !self.read_only
     }
+
+    /// 以链式调用构建普通调用帧，未设置的字段取零值
+    pub fn builder() -> CallFrameBuilder {
+        CallFrameBuilder::default()
+    }
+}
+
+impl fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} -> {} (gas: {}/{}, depth: {})",
+            self.call_type,
+            format_address(self.caller),
+            format_address(self.to_address),
+            self.gas_used,
+            self.gas_limit,
+            self.depth
+        )
+    }
+}
+
+/// 调用帧构建器
+///
+/// `build` 等价于用同样的参数调用 `CallFrame::new_call`；
+/// 默认调用类型为 CALL，其余字段默认为零值。
+#[derive(Debug, Clone)]
+pub struct CallFrameBuilder {
+    caller: Address,
+    to: Address,
+    value: U256,
+    data: Vec<u8>,
+    gas_limit: u64,
+    call_type: CallType,
+    depth: usize,
+}
+
+impl Default for CallFrameBuilder {
+    fn default() -> Self {
+        Self {
+            caller: Address::zero(),
+            to: Address::zero(),
+            value: U256::zero(),
+            data: Vec::new(),
+            gas_limit: 0,
+            call_type: CallType::Call,
+            depth: 0,
+        }
+    }
+}
+
+impl CallFrameBuilder {
+    pub fn caller(mut self, caller: Address) -> Self {
+        self.caller = caller;
+        self
+    }
+
+    /// 被调用地址，同时作为代码地址和接收地址
+    pub fn to(mut self, to: Address) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn value(mut self, value: U256) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    pub fn call_type(mut self, call_type: CallType) -> Self {
+        self.call_type = call_type;
+        self
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn build(self) -> CallFrame {
+        CallFrame::new_call(
+            self.caller,
+            self.to,
+            self.value,
+            self.data,
+            self.gas_limit,
+            self.call_type,
+            self.depth,
+        )
+    }
+}
+
+/// 调用历史中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallEvent {
+    /// 推入调用帧
+    Push {
+        depth: usize,
+        call_type: CallType,
+        caller: Address,
+        to: Address,
+        gas_limit: u64,
+    },
+    /// 弹出调用帧
+    Pop {
+        depth: usize,
+        call_type: CallType,
+        gas_used: u64,
+    },
+}
+
+impl fmt::Display for CallEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallEvent::Push {
+                depth,
+                call_type,
+                caller,
+                to,
+                gas_limit,
+            } => write!(
+                f,
+                "PUSH[{}] {:?} {} -> {} (gas: {})",
+                depth,
+                call_type,
+                format_address(*caller),
+                format_address(*to),
+                gas_limit
+            ),
+            CallEvent::Pop {
+                depth,
+                call_type,
+                gas_used,
+            } => write!(f, "POP[{}] {:?} gas_used: {}", depth, call_type, gas_used),
+        }
+    }
 }
 
 /// EVM 调用栈
@@ -173,7 +325,7 @@ pub struct CallStack {
     max_depth: usize,
 
     /// 调用历史（用于调试）
-    call_history: Vec<String>,
+    call_history: Vec<CallEvent>,
 
     /// 是否记录调用历史
     record_history: bool,
@@ -197,10 +349,51 @@ impl CallStack {
     }
 
     /// 获取调用历史
-    pub fn get_history(&self) -> &[String] {
+    pub fn get_history(&self) -> &[CallEvent] {
         &self.call_history
     }
 
+    /// 把调用历史导出为 Graphviz DOT 格式的调用图
+    ///
+    /// 每次推入调用帧生成一条 调用者 -> 被调用者 的边，标注调用类型和转发的 gas，
+    /// 可以直接交给 `dot -Tpng` 渲染。需要先调用 `enable_history`。
+    pub fn to_dot(&self) -> String {
+        let mut nodes = Vec::new();
+        let mut edges = String::new();
+        for event in &self.call_history {
+            if let CallEvent::Push {
+                call_type,
+                caller,
+                to,
+                gas_limit,
+                ..
+            } = event
+            {
+                for address in [caller, to] {
+                    if !nodes.contains(address) {
+                        nodes.push(*address);
+                    }
+                }
+                edges.push_str(&format!(
+                    "    \"{:#x}\" -> \"{:#x}\" [label=\"{:?} (gas: {})\"];\n",
+                    caller, to, call_type, gas_limit
+                ));
+            }
+        }
+
+        let mut dot = String::from("digraph calls {\n");
+        for address in nodes {
+            dot.push_str(&format!(
+                "    \"{:#x}\" [label=\"{}\"];\n",
+                address,
+                format_address(address)
+            ));
+        }
+        dot.push_str(&edges);
+        dot.push_str("}\n");
+        dot
+    }
+
     /// 推入新的调用帧
     pub fn push_frame(&mut self, mut frame: CallFrame) -> Result<(), Error> {
         // 检查调用深度限制
@@ -213,15 +406,13 @@ impl CallStack {
 
         // 记录调用历史
         if self.record_history {
-            let history_entry = format!(
-                "PUSH[{}] {:?} {} -> {} (gas: {})",
-                self.current_depth,
-                frame.call_type,
-                format_address(frame.caller),
-                format_address(frame.to_address),
-                frame.gas_limit
-            );
-            self.call_history.push(history_entry);
+            self.call_history.push(CallEvent::Push {
+                depth: self.current_depth,
+                call_type: frame.call_type.clone(),
+                caller: frame.caller,
+                to: frame.to_address,
+                gas_limit: frame.gas_limit,
+            });
         }
 
         // 推入帧并增加深度
@@ -238,11 +429,11 @@ impl CallStack {
 
             // 记录调用历史
             if self.record_history {
-                let history_entry = format!(
-                    "POP[{}] {:?} gas_used: {}",
-                    frame.depth, frame.call_type, frame.gas_used
-                );
-                self.call_history.push(history_entry);
+                self.call_history.push(CallEvent::Pop {
+                    depth: frame.depth,
+                    call_type: frame.call_type.clone(),
+                    gas_used: frame.gas_used,
+                });
             }
 
             Some(frame)
@@ -270,6 +461,30 @@ impl CallStack {
         }
     }
 
+    /// 获取指定深度的调用帧，最外层为 0
+    pub fn frame_at(&self, depth: usize) -> Option<&CallFrame> {
+        self.frames.get(depth)
+    }
+
+    /// 从最外层到最内层遍历调用帧
+    pub fn iter(&self) -> impl Iterator<Item = &CallFrame> {
+        self.frames.iter()
+    }
+
+    /// 所有指定调用类型的调用帧，从外到内排列
+    pub fn frames_matching(&self, call_type: CallType) -> Vec<&CallFrame> {
+        self.frames
+            .iter()
+            .filter(|frame| frame.call_type == call_type)
+            .collect()
+    }
+
+    /// 获取调用者帧的可变引用
+    fn caller_frame_mut(&mut self) -> Option<&mut CallFrame> {
+        let index = self.frames.len().checked_sub(2)?;
+        self.frames.get_mut(index)
+    }
+
     /// 获取当前调用深度
     pub fn depth(&self) -> usize {
         self.current_depth
@@ -314,16 +529,7 @@ impl CallStack {
         result.push_str("=== Call Stack ===\n");
 
         for (i, frame) in self.frames.iter().enumerate() {
-            result.push_str(&format!(
-                "[{}] {:?} {} -> {} (gas: {}/{}, depth: {})\n",
-                i,
-                frame.call_type,
-                format_address(frame.caller),
-                format_address(frame.to_address),
-                frame.gas_used,
-                frame.gas_limit,
-                frame.depth
-            ));
+            result.push_str(&format!("[{}] {}\n", i, frame));
         }
 
         if self.frames.is_empty() {
@@ -348,7 +554,13 @@ pub struct CallManager {
     /// 状态变更记录（每个调用深度一个记录）
     state_changes: HashMap<usize, Vec<StateChange>>,
 
-    /// 事件日志
+    /// 最外层调用成功后确认的状态变更
+    committed_changes: Vec<StateChange>,
+
+    /// 各调用深度尚未确认的事件日志，与状态变更一起合并或回滚
+    frame_logs: HashMap<usize, Vec<Log>>,
+
+    /// 最外层调用成功后确认的事件日志
     logs: Vec<Log>,
 }
 
@@ -359,19 +571,30 @@ impl CallManager {
             stack: CallStack::new(max_depth),
             return_data: Vec::new(),
             state_changes: HashMap::new(),
+            committed_changes: Vec::new(),
+            frame_logs: HashMap::new(),
             logs: Vec::new(),
         }
     }
 
     /// 开始新的调用
+    ///
+    /// 父帧记录调用前的已用 gas，并预先扣除转发给子帧的全部 gas。
     pub fn begin_call(&mut self, frame: CallFrame) -> Result<(), Error> {
-        let depth = frame.depth;
+        // push_frame 会按当前深度重新设置 frame.depth
+        let depth = self.stack.depth();
 
         // 推入调用帧
+        let allotment = frame.gas_limit;
         self.stack.push_frame(frame)?;
+        if let Some(parent) = self.stack.caller_frame_mut() {
+            parent.gas_snapshot = parent.gas_used;
+            parent.gas_used = parent.gas_used.saturating_add(allotment);
+        }
 
-        // 初始化该深度的状态变更记录
+        // 初始化该深度的状态变更和日志记录
         self.state_changes.insert(depth, Vec::new());
+        self.frame_logs.insert(depth, Vec::new());
 
         Ok(())
     }
@@ -381,18 +604,37 @@ impl CallManager {
         if let Some(frame) = self.stack.pop_frame() {
             let depth = frame.depth;
 
+            // 父帧只承担子帧实际消耗的 gas（不超过转发的数额），其余退回。
+            // 异常终止的子帧应先把 gas_used 记满，使转发的 gas 全部被消耗
+            if let Some(parent) = self.stack.current_frame_mut() {
+                parent.gas_used = parent.gas_snapshot + frame.gas_used.min(frame.gas_limit);
+            }
+
             if success {
-                // 调用成功，保留状态变更
+                // 调用成功，状态变更并入上一层（最外层则直接确认）
+                let changes = self.state_changes.remove(&depth).unwrap_or_default();
+                let logs = self.frame_logs.remove(&depth).unwrap_or_default();
+                match depth.checked_sub(1) {
+                    Some(parent) => {
+                        self.state_changes
+                            .entry(parent)
+                            .or_default()
+                            .extend(changes);
+                        self.frame_logs.entry(parent).or_default().extend(logs);
+                    }
+                    None => {
+                        self.committed_changes.extend(changes);
+                        self.logs.extend(logs);
+                    }
+                }
                 self.return_data = return_data;
             } else {
-                // 调用失败，回滚状态变更
+                // 调用失败，回滚状态变更，丢弃该帧的日志
                 self.rollback_state_changes(depth);
+                self.frame_logs.remove(&depth);
                 self.return_data.clear();
             }
 
-            // 清理该深度的状态变更记录
-            self.state_changes.remove(&depth);
-
             Some(frame)
         } else {
             None
@@ -407,23 +649,92 @@ impl CallManager {
         }
     }
 
+    /// 记录交易级的状态变更（如发送者 nonce），直接确认，不随调用失败回滚
+    pub fn commit_state_change(&mut self, change: StateChange) {
+        self.committed_changes.push(change);
+    }
+
+    /// 查询尚未写入数据库的存储值
+    ///
+    /// 按从内到外的顺序查找最近一次写入，未写入过则返回 None。
+    pub fn pending_storage(&self, address: Address, index: U256) -> Option<U256> {
+        self.pending_changes().find_map(|change| match change {
+            StateChange::UpdateStorage {
+                address: addr,
+                index: idx,
+                value,
+            } if *addr == address && *idx == index => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// 查询尚未写入数据库的账户余额
+    ///
+    /// 与 `pending_storage` 相同按从内到外查找，被删除的账户余额为 0。
+    pub fn pending_balance(&self, address: Address) -> Option<U256> {
+        self.pending_changes().find_map(|change| match change {
+            StateChange::UpdateBalance {
+                address: addr,
+                balance,
+            } if *addr == address => Some(*balance),
+            StateChange::DeleteAccount { address: addr } if *addr == address => Some(U256::zero()),
+            _ => None,
+        })
+    }
+
+    /// 查询尚未写入数据库的账户 nonce
+    ///
+    /// 与 `pending_balance` 相同按从内到外查找，被删除的账户 nonce 为 0。
+    pub fn pending_nonce(&self, address: Address) -> Option<u64> {
+        self.pending_changes().find_map(|change| match change {
+            StateChange::UpdateNonce {
+                address: addr,
+                nonce,
+            } if *addr == address => Some(*nonce),
+            StateChange::CreateAccount {
+                address: addr,
+                info,
+            } if *addr == address => Some(info.nonce),
+            StateChange::DeleteAccount { address: addr } if *addr == address => Some(0),
+            _ => None,
+        })
+    }
+
+    /// 按从新到旧的顺序遍历尚未写入数据库的状态变更
+    fn pending_changes(&self) -> impl Iterator<Item = &StateChange> {
+        let in_flight = (0..=self.stack.depth())
+            .rev()
+            .filter_map(|depth| self.state_changes.get(&depth));
+
+        in_flight
+            .chain(std::iter::once(&self.committed_changes))
+            .flat_map(|changes| changes.iter().rev())
+    }
+
+    /// 取出已确认的状态变更
+    pub fn take_committed_changes(&mut self) -> Vec<StateChange> {
+        std::mem::take(&mut self.committed_changes)
+    }
+
     /// 回滚指定深度的状态变更
     fn rollback_state_changes(&mut self, depth: usize) {
-        if let Some(changes) = self.state_changes.remove(&depth) {
-            // 这里应该实际回滚状态变更
-            // 简化实现，只是记录日志
-            println!("回滚深度 {} 的 {} 个状态变更", depth, changes.len());
-        }
+        // 状态变更在调用成功前只存在于记录中，丢弃即完成回滚
+        self.state_changes.remove(&depth);
     }
 
     /// 添加事件日志
+    ///
+    /// 日志记录在当前帧中，调用成功时才并入上一层；没有调用帧时直接确认。
     pub fn add_log(&mut self, log: Log) -> Result<(), Error> {
         // 检查是否在静态上下文中
         if self.stack.is_in_static_context() {
             return Err(Error::InvalidOpcode); // 静态调用不能产生日志
         }
 
-        self.logs.push(log);
+        match self.stack.current_frame() {
+            Some(frame) => self.frame_logs.entry(frame.depth).or_default().push(log),
+            None => self.logs.push(log),
+        }
         Ok(())
     }
 
@@ -442,7 +753,7 @@ impl CallManager {
         &self.return_data
     }
 
-    /// 获取事件日志
+    /// 获取已确认的事件日志
     pub fn logs(&self) -> &[Log] {
         &self.logs
     }
@@ -463,6 +774,19 @@ impl CallManager {
         Ok(())
     }
 
+    /// 检测重入：返回在调用栈中不止一次作为 `to_address` 出现的地址
+    ///
+    /// 例如 A→B→A 中的 A。DELEGATECALL 和 CALLCODE 帧本来就在调用方的
+    /// 上下文中执行，不算作重新进入，因此被忽略。
+    pub fn detect_reentrancy(&self) -> Option<Address> {
+        let mut seen = HashSet::new();
+        self.stack
+            .iter()
+            .filter(|frame| !matches!(frame.call_type, CallType::DelegateCall | CallType::CallCode))
+            .map(|frame| frame.to_address)
+            .find(|address| !seen.insert(*address))
+    }
+
     /// 处理调用失败的清理工作
     pub fn handle_call_failure(&mut self, target_depth: usize) {
         // 回滚到目标深度
@@ -494,11 +818,12 @@ fn format_address(addr: Address) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::addr;
 
     #[test]
     fn test_call_frame_creation() {
-        let caller = Address::from([1u8; 20]);
-        let to = Address::from([2u8; 20]);
+        let caller = addr(1);
+        let to = addr(2);
         let value = U256::from(100);
         let data = vec![0x12, 0x34];
 
@@ -512,6 +837,57 @@ mod tests {
         assert_eq!(frame.call_type, CallType::Call);
     }
 
+    #[test]
+    fn test_call_frame_builder() {
+        let frame = CallFrame::builder()
+            .caller(addr(1))
+            .to(addr(2))
+            .value(U256::from(100))
+            .data(vec![0x12, 0x34])
+            .gas_limit(10000)
+            .call_type(CallType::StaticCall)
+            .depth(3)
+            .build();
+        let expected = CallFrame::new_call(
+            addr(1),
+            addr(2),
+            U256::from(100),
+            vec![0x12, 0x34],
+            10000,
+            CallType::StaticCall,
+            3,
+        );
+        assert_eq!(frame, expected);
+        assert!(frame.read_only);
+
+        // 未设置的字段取默认值
+        let frame = CallFrame::builder().to(addr(2)).build();
+        assert_eq!(
+            frame,
+            CallFrame::new_call(
+                Address::zero(),
+                addr(2),
+                U256::zero(),
+                vec![],
+                0,
+                CallType::Call,
+                0
+            )
+        );
+
+        // Display 与 format_stack 的每行格式一致
+        let mut stack = CallStack::new(4);
+        stack.push_frame(frame.clone()).unwrap();
+        assert_eq!(
+            stack.format_stack(),
+            format!("=== Call Stack ===\n[0] {}\n", frame)
+        );
+        assert_eq!(
+            frame.to_string(),
+            "Call 0x0 -> 0x2020202...2020202 (gas: 0/0, depth: 0)"
+        );
+    }
+
     #[test]
     fn test_call_stack_operations() {
         let mut stack = CallStack::new(10);
@@ -522,8 +898,8 @@ mod tests {
 
         // 推入帧
         let frame1 = CallFrame::new_call(
-            Address::from([1u8; 20]),
-            Address::from([2u8; 20]),
+            addr(1),
+            addr(2),
             U256::zero(),
             vec![],
             10000,
@@ -541,14 +917,83 @@ mod tests {
         assert!(stack.is_empty());
     }
 
+    #[test]
+    fn test_call_stack_iteration() {
+        let mut stack = CallStack::new(10);
+        for (to, call_type) in [
+            (addr(2), CallType::Call),
+            (addr(3), CallType::StaticCall),
+            (addr(2), CallType::Call),
+        ] {
+            let frame = CallFrame::builder().to(to).call_type(call_type).build();
+            stack.push_frame(frame).unwrap();
+        }
+
+        assert_eq!(stack.iter().count(), stack.len());
+        let targets: Vec<_> = stack.iter().map(|frame| frame.to_address).collect();
+        assert_eq!(targets, [addr(2), addr(3), addr(2)]);
+        assert_eq!(stack.frame_at(1).unwrap().call_type, CallType::StaticCall);
+        assert_eq!(stack.frame_at(2), stack.current_frame());
+        assert!(stack.frame_at(3).is_none());
+
+        let calls = stack.frames_matching(CallType::Call);
+        assert_eq!(calls.len(), 2);
+        assert_eq!((calls[0].depth, calls[1].depth), (0, 2));
+    }
+
+    #[test]
+    fn test_call_graph_dot() {
+        let mut stack = CallStack::new(10);
+        stack.enable_history();
+        for (caller, to, call_type) in [
+            (addr(1), addr(2), CallType::Call),
+            (addr(2), addr(3), CallType::DelegateCall),
+            (addr(2), addr(4), CallType::StaticCall),
+        ] {
+            let frame = CallFrame::builder()
+                .caller(caller)
+                .to(to)
+                .gas_limit(1000)
+                .call_type(call_type)
+                .build();
+            stack.push_frame(frame).unwrap();
+        }
+        stack.pop_frame();
+
+        assert_eq!(
+            stack.get_history()[3],
+            CallEvent::Pop {
+                depth: 2,
+                call_type: CallType::StaticCall,
+                gas_used: 0,
+            }
+        );
+        assert_eq!(
+            stack.get_history()[0].to_string(),
+            "PUSH[0] Call 0x1010101...1010101 -> 0x2020202...2020202 (gas: 1000)"
+        );
+
+        let dot = stack.to_dot();
+        assert!(dot.starts_with("digraph calls {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches(" -> ").count(), 3);
+        assert!(dot.contains(&format!(
+            "\"{:#x}\" -> \"{:#x}\" [label=\"DelegateCall (gas: 1000)\"];",
+            addr(2),
+            addr(3)
+        )));
+        // 每个地址只声明一次
+        assert_eq!(dot.matches("[label=\"0x").count(), 4);
+    }
+
     #[test]
     fn test_call_depth_limit() {
         let mut stack = CallStack::new(2);
 
         // 推入第一帧
         let frame1 = CallFrame::new_call(
-            Address::from([1u8; 20]),
-            Address::from([2u8; 20]),
+            addr(1),
+            addr(2),
             U256::zero(),
             vec![],
             10000,
@@ -559,8 +1004,8 @@ mod tests {
 
         // 推入第二帧
         let frame2 = CallFrame::new_call(
-            Address::from([2u8; 20]),
-            Address::from([3u8; 20]),
+            addr(2),
+            addr(3),
             U256::zero(),
             vec![],
             10000,
@@ -571,8 +1016,8 @@ mod tests {
 
         // 尝试推入第三帧应该失败
         let frame3 = CallFrame::new_call(
-            Address::from([3u8; 20]),
-            Address::from([4u8; 20]),
+            addr(3),
+            addr(4),
             U256::zero(),
             vec![],
             10000,
@@ -585,4 +1030,108 @@ mod tests {
             Err(Error::CallDepthExceeded)
         ));
     }
+
+    #[test]
+    fn test_detect_reentrancy() {
+        let manager_with = |frames: &[(Address, CallType)]| {
+            let mut manager = CallManager::new(10);
+            for (to, call_type) in frames {
+                let frame = CallFrame::builder()
+                    .to(*to)
+                    .call_type(call_type.clone())
+                    .build();
+                manager.begin_call(frame).unwrap();
+            }
+            manager
+        };
+        let (a, b, c) = (addr(0xa), addr(0xb), addr(0xc));
+
+        let manager = manager_with(&[
+            (a, CallType::Call),
+            (b, CallType::Call),
+            (a, CallType::Call),
+        ]);
+        assert_eq!(manager.detect_reentrancy(), Some(a));
+
+        let manager = manager_with(&[
+            (a, CallType::Call),
+            (b, CallType::Call),
+            (c, CallType::Call),
+        ]);
+        assert_eq!(manager.detect_reentrancy(), None);
+
+        // DELEGATECALL 帧沿用调用方的地址，不算重入
+        let manager = manager_with(&[(a, CallType::Call), (a, CallType::DelegateCall)]);
+        assert_eq!(manager.detect_reentrancy(), None);
+    }
+
+    #[test]
+    fn test_failed_child_returns_unused_gas() {
+        let frame = |to, gas_limit| CallFrame::builder().to(to).gas_limit(gas_limit).build();
+        let mut manager = CallManager::new(4);
+        manager.begin_call(frame(addr(2), 10_000)).unwrap();
+        manager
+            .stack_mut()
+            .current_frame_mut()
+            .unwrap()
+            .consume_gas(100)
+            .unwrap();
+
+        // 转发的 5000 gas 在子调用期间从父帧预扣
+        manager.begin_call(frame(addr(3), 5_000)).unwrap();
+        assert_eq!(manager.stack().caller_frame().unwrap().gas_used, 5_100);
+
+        // 子调用用掉 1000 后 REVERT：未用的 4000 退回父帧
+        manager
+            .stack_mut()
+            .current_frame_mut()
+            .unwrap()
+            .consume_gas(1_000)
+            .unwrap();
+        manager.end_call(false, vec![]);
+        let parent = manager.stack().current_frame().unwrap();
+        assert_eq!(parent.gas_used, 1_100);
+        assert_eq!(parent.remaining_gas(), 8_900);
+
+        // 异常终止的子帧用满了转发的 gas，父帧全部承担
+        manager.begin_call(frame(addr(3), 5_000)).unwrap();
+        manager
+            .stack_mut()
+            .current_frame_mut()
+            .unwrap()
+            .consume_gas(5_000)
+            .unwrap();
+        manager.end_call(false, vec![]);
+        assert_eq!(manager.stack().current_frame().unwrap().gas_used, 6_100);
+    }
+
+    #[test]
+    fn test_logs_of_failed_frame_are_discarded() {
+        let frame =
+            |to| CallFrame::new_call(addr(1), to, U256::zero(), vec![], 0, CallType::Call, 0);
+        let log = |address| Log {
+            address,
+            topics: vec![],
+            data: vec![],
+        };
+        let mut manager = CallManager::new(4);
+
+        manager.begin_call(frame(addr(2))).unwrap();
+        manager.add_log(log(addr(2))).unwrap();
+
+        // 失败的子调用中的日志被丢弃
+        manager.begin_call(frame(addr(3))).unwrap();
+        manager.add_log(log(addr(3))).unwrap();
+        manager.end_call(false, vec![]);
+
+        // 成功的子调用中的日志并入父帧
+        manager.begin_call(frame(addr(4))).unwrap();
+        manager.add_log(log(addr(4))).unwrap();
+        manager.end_call(true, vec![]);
+
+        // 最外层调用结束前日志尚未确认
+        assert!(manager.logs().is_empty());
+        manager.end_call(true, vec![]);
+        assert_eq!(manager.logs(), [log(addr(2)), log(addr(4))]);
+    }
 }
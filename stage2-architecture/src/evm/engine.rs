@@ -1,9 +1,25 @@
-use crate::database::Database;
+use crate::database::{Database, DatabaseCommit};
+use crate::evm::call_stack::{CallFrame, CallManager, CallType};
+use crate::evm::handler::{ExecutionContext, OpcodeHandlerTable};
+use crate::evm::inspector::Inspector;
+use crate::evm::precompile::{precompile_id, Precompile, PrecompileRegistry};
+use crate::evm::stack::Stack;
+use crate::evm::tracer::{StackSnapshotTracer, Tracer};
 use crate::models::*;
-use crate::spec::Spec;
-use ethereum_types::{Address, U256};
+use crate::spec::{Spec, SpecId};
+use ethereum_types::{Address, H256, U256};
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 
+/// 打印执行过程，关闭 `verbose` 或安装了追踪器时不输出
+macro_rules! narrate {
+    ($evm:expr, $($arg:tt)*) => {
+        if $evm.verbose && $evm.tracer.is_none() {
+            println!($($arg)*);
+        }
+    };
+}
+
 /// EVM 执行机器状态
 #[derive(Debug, Clone)]
 pub struct Machine {
@@ -11,7 +27,7 @@ pub struct Machine {
     pub pc: usize,
 
     /// 执行栈
-    pub stack: Vec<U256>,
+    pub stack: Stack,
 
     /// 内存
     pub memory: Vec<u8>,
@@ -21,50 +37,92 @@ pub struct Machine {
 
     /// 剩余 Gas
     pub gas: u64,
+
+    /// 当前执行的合约地址（存储上下文）
+    ///
+    /// DELEGATECALL 时为调用方的地址，而不是被执行代码所在的地址。
+    pub self_address: Address,
+
+    /// 本帧（含成功返回的子帧）累计的 gas 退款
+    pub refund: i64,
+
+    /// 按操作码累计的 gas 消耗（含子调用内部的消耗）
+    gas_by_opcode: HashMap<u8, u64>,
+
+    /// 当前指令中子调用已计入报告的 gas，避免重复计入调用指令本身
+    pub(crate) nested_gas: u64,
+
+    /// 内存大小上限（通常取 `SPEC::MEMORY_LIMIT`）
+    pub(crate) memory_limit: usize,
 }
 
 impl Machine {
     pub fn new(gas: u64) -> Self {
+        Self::with_stack_limit(gas, crate::evm::stack::STACK_LIMIT)
+    }
+
+    /// 按规范的栈深度和内存上限创建机器
+    pub fn for_spec<SPEC: Spec>(gas: u64) -> Self {
+        let mut machine = Self::with_stack_limit(gas, SPEC::STACK_LIMIT);
+        machine.memory_limit = SPEC::MEMORY_LIMIT;
+        machine
+    }
+
+    /// 创建指定栈深度上限的机器（通常取 `SPEC::STACK_LIMIT`）
+    pub fn with_stack_limit(gas: u64, stack_limit: usize) -> Self {
         Self {
             pc: 0,
-            stack: Vec::new(),
+            stack: Stack::new(stack_limit),
             memory: Vec::new(),
             return_data: Vec::new(),
             gas,
+            self_address: Address::zero(),
+            refund: 0,
+            gas_by_opcode: HashMap::new(),
+            nested_gas: 0,
+            memory_limit: MEMORY_LIMIT,
         }
     }
 
     /// 栈操作：推入值
     pub fn push(&mut self, value: U256) -> Result<(), Error> {
-        if self.stack.len() >= 1024 {
-            return Err(Error::StackOverflow);
-        }
-        self.stack.push(value);
-        Ok(())
+        self.stack.push(value)
     }
 
     /// 栈操作：弹出值
     pub fn pop(&mut self) -> Result<U256, Error> {
-        self.stack.pop().ok_or(Error::StackUnderflow)
+        self.stack.pop()
     }
 
     /// 内存操作：扩展内存
+    ///
+    /// 超过内存上限时在分配之前返回 `Error::OutOfMemory`；
+    /// 每次扩展按新旧大小的差值收取二次方增长的内存 gas。
     pub fn expand_memory(&mut self, offset: usize, size: usize) -> Result<(), Error> {
-        let required_size = offset + size;
+        let required_size = offset.checked_add(size).ok_or(Error::OutOfMemory)?;
+        if required_size > self.memory_limit {
+            return Err(Error::OutOfMemory);
+        }
         if required_size > self.memory.len() {
             // 内存按 32 字节对齐扩展
             let aligned_size = (required_size + 31) / 32 * 32;
+            let old_words = (self.memory.len() / 32) as u64;
+            let new_words = (aligned_size / 32) as u64;
+            self.use_gas(memory_gas(new_words) - memory_gas(old_words))?;
             self.memory.resize(aligned_size, 0);
         }
         Ok(())
     }
 
     /// 内存操作：读取内存
+    ///
+    /// 越界（包括 `offset + size` 溢出）时返回 `Error::OutOfMemory` 而不是 panic。
     pub fn memory_read(&self, offset: usize, size: usize) -> Result<Vec<u8>, Error> {
-        if offset + size > self.memory.len() {
-            return Err(Error::OutOfMemory);
-        }
-        Ok(self.memory[offset..offset + size].to_vec())
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= self.memory.len())
+            .ok_or(Error::OutOfMemory)?;
+        Ok(self.memory[offset..end].to_vec())
     }
 
     /// 内存操作：写入内存
@@ -74,6 +132,18 @@ impl Machine {
         Ok(())
     }
 
+    /// 按操作码统计的 gas 消耗
+    ///
+    /// 调用类指令只统计自身的成本，子调用内部的消耗计入各自的操作码。
+    pub fn gas_report(&self) -> HashMap<u8, u64> {
+        self.gas_by_opcode.clone()
+    }
+
+    /// 记录一条指令消耗的 gas
+    pub(crate) fn record_gas(&mut self, opcode: u8, gas: u64) {
+        *self.gas_by_opcode.entry(opcode).or_default() += gas;
+    }
+
     /// 消耗 Gas
     pub fn use_gas(&mut self, gas: u64) -> Result<(), Error> {
         if self.gas < gas {
@@ -84,6 +154,77 @@ impl Machine {
     }
 }
 
+/// 默认的内存大小上限，与主网规范的 `MEMORY_LIMIT` 相同
+pub const MEMORY_LIMIT: usize = 0x1FFFFFFE0;
+
+/// 内存每个字的线性成本
+pub const GAS_MEMORY: u64 = 3;
+
+/// 内存二次方成本的除数
+pub const GAS_MEMORY_QUAD_DIVISOR: u64 = 512;
+
+/// 内存大小为 `words` 个字时的总成本：`3 * words + words² / 512`
+pub fn memory_gas(words: u64) -> u64 {
+    GAS_MEMORY * words + words * words / GAS_MEMORY_QUAD_DIVISOR
+}
+
+/// 计算交易最终的 gas 退款
+///
+/// 所有成功帧的退款在交易结束时汇总，然后统一按
+/// `gas_used / SPEC::MAX_REFUND_QUOTIENT` 封顶（EIP-3529 起为 1/5）。
+pub fn effective_refund<SPEC: Spec>(gas_used: u64, refund: i64) -> u64 {
+    let refund = refund.max(0) as u64;
+    refund.min(gas_used / SPEC::MAX_REFUND_QUOTIENT)
+}
+
+/// 检查交易级的 gas 记账（退款前）
+///
+/// 必须满足 `intrinsic <= gas_used <= gas_limit` 且
+/// `gas_used + gas_remaining == gas_limit`，否则返回描述错误的信息。
+pub fn check_gas_accounting(
+    gas_limit: u64,
+    gas_used: u64,
+    gas_remaining: u64,
+    intrinsic: u64,
+) -> Result<(), String> {
+    if gas_used > gas_limit {
+        return Err(format!(
+            "gas_used {} 超过 gas_limit {}",
+            gas_used, gas_limit
+        ));
+    }
+    if gas_used < intrinsic {
+        return Err(format!("gas_used {} 低于固有成本 {}", gas_used, intrinsic));
+    }
+    if gas_used.checked_add(gas_remaining) != Some(gas_limit) {
+        return Err(format!(
+            "gas_used {} + gas_remaining {} != gas_limit {}",
+            gas_used, gas_remaining, gas_limit
+        ));
+    }
+    Ok(())
+}
+
+/// 执行资源限制
+///
+/// 与 gas 无关的额外保护，用于在嵌入场景中限制单笔交易占用的资源。
+/// 默认不设任何限制。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// RETURN/REVERT 返回数据的最大字节数，None 表示不限制
+    pub max_return_size: Option<usize>,
+}
+
+impl ExecutionLimits {
+    /// 检查 RETURN/REVERT 指定的返回数据大小
+    pub fn check_return_size(&self, size: usize) -> Result<(), Error> {
+        match self.max_return_size {
+            Some(max) if size > max => Err(Error::OutOfMemory),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// 模块化 EVM 引擎
 ///
 /// 这个 EVM 引擎展示了模块化设计的核心理念：
@@ -92,81 +233,332 @@ impl Machine {
 /// - DB 参数支持可插拔存储
 pub struct EVM<SPEC: Spec, DB: Database> {
     /// 数据库后端
-    database: DB,
+    pub(crate) database: DB,
 
     /// 执行环境
-    env: Environment,
+    pub(crate) env: Environment,
 
     /// 执行机器状态
-    machine: Machine,
+    pub(crate) machine: Machine,
+
+    /// 调用栈管理器
+    pub(crate) call_manager: CallManager,
+
+    /// 执行观察者（可选）
+    pub(crate) inspector: Option<Box<dyn Inspector>>,
+
+    /// 逐步执行追踪器（可选）
+    pub(crate) tracer: Option<Box<dyn Tracer>>,
+
+    /// 用户注册的指令处理函数，优先于内置实现
+    pub(crate) opcode_table: OpcodeHandlerTable,
+
+    /// 本次交易中已访问过的存储槽（EIP-2929 热存储）
+    pub(crate) warm_slots: HashSet<(Address, U256)>,
+
+    /// 本次交易中已访问过的账户（EIP-2929 热账户）
+    pub(crate) warm_addresses: HashSet<Address>,
+
+    /// 本次交易中已执行过 SELFDESTRUCT 的合约，销毁退款每个地址只计一次
+    pub(crate) destructed: HashSet<Address>,
+
+    /// 瞬时存储 (EIP-1153)，只在一笔交易内有效
+    pub(crate) transient_storage: HashMap<(Address, U256), U256>,
+
+    /// 单步执行模式下加载的字节码
+    pub(crate) loaded_code: Option<Bytecode>,
+
+    /// 执行资源限制
+    pub(crate) limits: ExecutionLimits,
+
+    /// 单笔交易最多执行的指令数，与 gas 无关
+    pub(crate) max_steps: u64,
+
+    /// 本笔交易已执行的指令数（包括子调用）
+    pub(crate) step_count: u64,
+
+    /// 预编译合约的实现
+    pub(crate) precompiles: PrecompileRegistry,
+
+    /// 调用栈最大深度，默认取 `SPEC::CALL_DEPTH_LIMIT`
+    pub(crate) call_depth_limit: usize,
+
+    /// 栈最大深度，默认取 `SPEC::STACK_LIMIT`
+    pub(crate) stack_limit: usize,
+
+    /// 当前这批交易（或区块）已累计使用的 gas
+    pub(crate) cumulative_gas_used: u64,
+
+    /// 是否打印执行过程，安装了追踪器时不打印
+    pub(crate) verbose: bool,
+
+    /// 测试钩子：人为多记的 gas，用于验证记账检查能发现错误
+    #[cfg(test)]
+    pub(crate) gas_used_skew: u64,
 
     /// 规范类型标记（零大小类型）
     _spec: PhantomData<SPEC>,
 }
 
 impl<SPEC: Spec, DB: Database> EVM<SPEC, DB> {
+    /// 以构建器创建 EVM，可以覆盖规范中的调用深度和栈深度上限
+    pub fn builder(database: DB) -> EvmBuilder<SPEC, DB> {
+        EvmBuilder::new(database)
+    }
+
     /// 创建新的 EVM 实例
     pub fn new(database: DB, env: Environment) -> Self {
         Self {
             database,
             env,
             machine: Machine::new(0), // gas 将在执行时设置
+            call_manager: CallManager::new(SPEC::CALL_DEPTH_LIMIT),
+            inspector: None,
+            tracer: None,
+            opcode_table: OpcodeHandlerTable::new(),
+            warm_slots: HashSet::new(),
+            warm_addresses: HashSet::new(),
+            destructed: HashSet::new(),
+            transient_storage: HashMap::new(),
+            loaded_code: None,
+            limits: ExecutionLimits::default(),
+            max_steps: u64::MAX,
+            step_count: 0,
+            precompiles: PrecompileRegistry::for_spec::<SPEC>(),
+            call_depth_limit: SPEC::CALL_DEPTH_LIMIT,
+            stack_limit: SPEC::STACK_LIMIT,
+            cumulative_gas_used: 0,
+            verbose: true,
+            #[cfg(test)]
+            gas_used_skew: 0,
             _spec: PhantomData,
         }
     }
 
     /// 执行交易
     pub fn transact(&mut self, tx: Transaction) -> Result<ExecutionResult, Error> {
-        // 设置初始 gas
-        self.machine.gas = tx.gas_limit;
+        // 每笔交易使用全新的机器状态和调用栈
+        // 栈深度和内存上限分别在 push 和内存扩展时检查
+        self.machine = self.new_machine(tx.gas_limit);
+        self.call_manager = CallManager::new(self.call_depth_limit);
+        self.warm_slots.clear();
+        self.warm_addresses.clear();
+        self.destructed.clear();
+        self.transient_storage.clear();
+        self.step_count = 0;
+
+        // EIP-2929：发送者、接收者、所有预编译合约以及访问列表 (EIP-2930)
+        // 中的地址和存储槽在交易开始时就是热的
+        if SPEC::ENABLE_ACCESS_LISTS {
+            self.warm_addresses.insert(tx.caller);
+            self.warm_addresses.extend(tx.to);
+            self.warm_addresses.extend(
+                SPEC::precompiles()
+                    .iter()
+                    .map(|id| Address::from_low_u64_be(*id as u64)),
+            );
+            for (address, keys) in &tx.access_list {
+                self.warm_addresses.insert(*address);
+                self.warm_slots
+                    .extend(keys.iter().map(|index| (*address, *index)));
+            }
+        }
+
+        narrate!(self, "🚀 开始执行交易 (规范: {})", SPEC::NAME);
+        narrate!(self, "   调用者: {:#x}", tx.caller);
+        narrate!(self, "   Gas 限制: {}", tx.gas_limit);
 
-        println!("🚀 开始执行交易 (规范: {})", SPEC::NAME);
-        println!("   调用者: {:#x}", tx.caller);
-        println!("   Gas 限制: {}", tx.gas_limit);
+        // 带链 ID 的交易只能在对应的链上执行，旧式交易不校验 (EIP-155)
+        if let Some(chain_id) = tx.chain_id {
+            if U256::from(chain_id) != self.env.chain_id {
+                return Err(Error::ChainIdMismatch {
+                    expected: self.env.chain_id,
+                    got: chain_id,
+                });
+            }
+        }
 
-        // 检查栈限制（使用规范参数）
-        if self.machine.stack.len() > SPEC::STACK_LIMIT {
-            return Err(Error::StackOverflow);
+        // 初始化代码超过上限的创建交易无效 (EIP-3860)
+        if tx.to.is_none() && tx.data.len() > SPEC::MAX_INITCODE_SIZE {
+            return Err(Error::CodeTooLarge);
+        }
+
+        // 固有成本不足的交易无效，不会被执行
+        let intrinsic = tx.intrinsic_gas::<SPEC>();
+        if tx.gas_limit < intrinsic {
+            return Err(Error::IntrinsicGasTooLow);
+        }
+        self.machine.use_gas(intrinsic)?;
+        narrate!(self, "   固有 gas 成本: {}", intrinsic);
+
+        // 校验并递增发送者 nonce，即使执行失败 nonce 也会增加
+        let (nonce, balance) = self
+            .database
+            .basic(tx.caller)
+            .map_err(Error::database)?
+            .map_or((0, U256::zero()), |account| {
+                (account.nonce, account.balance)
+            });
+        // 发送者必须能预付全部 gas 费用和转账金额
+        let gas_cost = U256::from(tx.gas_limit)
+            .checked_mul(tx.gas_price)
+            .ok_or(Error::InsufficientFunds)?;
+        match gas_cost.checked_add(tx.value) {
+            Some(required) if balance >= required => {}
+            _ => return Err(Error::InsufficientFunds),
+        }
+        if let Some(got) = tx.nonce {
+            if got != nonce {
+                return Err(Error::NonceMismatch {
+                    expected: nonce,
+                    got,
+                });
+            }
+        }
+        self.call_manager
+            .commit_state_change(StateChange::UpdateNonce {
+                address: tx.caller,
+                nonce: nonce + 1,
+            });
+        if !gas_cost.is_zero() {
+            self.call_manager
+                .commit_state_change(StateChange::UpdateBalance {
+                    address: tx.caller,
+                    balance: balance - gas_cost,
+                });
         }
 
         // 根据交易类型执行
         let result = match tx.to {
             Some(to) => {
-                println!("   类型: CALL to {:#x}", to);
+                narrate!(self, "   类型: CALL to {:#x}", to);
                 self.execute_call(tx.caller, to, tx.value, &tx.data)
             }
             None => {
-                println!("   类型: CREATE");
-                self.execute_create(tx.caller, tx.value, &tx.data)
+                narrate!(self, "   类型: CREATE");
+                self.execute_create(tx.caller, nonce, tx.value, &tx.data)
             }
         };
+        // 瞬时存储在交易结束时清空，不会带入下一笔交易
+        self.transient_storage.clear();
 
+        // 执行前的错误（如预编译合约失败）同样视为异常终止
+        let result = result.unwrap_or_else(|reason| CallResult::Halt {
+            reason,
+            gas_left: 0,
+        });
         match result {
-            Ok(return_data) => {
-                let gas_used = tx.gas_limit - self.machine.gas;
-                println!("✅ 交易执行成功，Gas 使用: {}", gas_used);
+            // 步数上限是宿主的保护措施而不是执行结果，直接中止交易
+            CallResult::Halt {
+                reason: Error::StepLimitExceeded,
+                ..
+            } => {
+                narrate!(self, "⛔ 执行步数达到上限 ({})，中止交易", self.max_steps);
+                Err(Error::StepLimitExceeded)
+            }
+            CallResult::Success {
+                output: return_data,
+                ..
+            } => {
+                let gas_used = self.gas_used(tx.gas_limit, intrinsic);
+                let gas_refunded = effective_refund::<SPEC>(gas_used, self.machine.refund);
+                let gas_used = gas_used - gas_refunded;
+                self.refund_unused_gas(&tx, gas_used)?;
+                narrate!(
+                    self,
+                    "✅ 交易执行成功，Gas 使用: {} (退款 {})",
+                    gas_used,
+                    gas_refunded
+                );
 
                 Ok(ExecutionResult {
                     success: true,
                     gas_used,
+                    gas_refunded,
                     return_data,
-                    logs: Vec::new(),
+                    logs: self.call_manager.logs().to_vec(),
                 })
             }
-            Err(e) => {
-                let gas_used = tx.gas_limit - self.machine.gas;
-                println!("❌ 交易执行失败: {}, Gas 使用: {}", e, gas_used);
+            failure => {
+                // REVERT 保留剩余 gas 和 revert 数据，其余异常终止耗尽全部 gas
+                let (reason, gas_used) = match failure {
+                    CallResult::Halt { ref reason, .. } => (reason.clone(), tx.gas_limit),
+                    _ => (
+                        Error::Reverted(failure.output().to_vec()),
+                        self.gas_used(tx.gas_limit, intrinsic),
+                    ),
+                };
+                let return_data = failure.into_output();
+                narrate!(self, "❌ 交易执行失败: {}, Gas 使用: {}", reason, gas_used);
+                // 转账随执行失败回滚，发送者只支付 gas 费用
+                self.refund_unused_gas(&tx, gas_used)?;
 
+                // 执行失败时退款作废
                 Ok(ExecutionResult {
                     success: false,
                     gas_used,
-                    return_data: Vec::new(),
+                    gas_refunded: 0,
+                    return_data,
                     logs: Vec::new(),
                 })
             }
         }
     }
 
+    /// 执行交易并生成收据
+    ///
+    /// 收据的累计 gas 接续 `cumulative_gas_used`，连续调用时按区块内的顺序累加。
+    pub fn transact_with_receipt(
+        &mut self,
+        tx: Transaction,
+    ) -> Result<(ExecutionResult, Receipt), Error> {
+        let result = self.transact(tx)?;
+        self.cumulative_gas_used += result.gas_used;
+        let receipt = Receipt::new(
+            result.success,
+            self.cumulative_gas_used,
+            result.logs.clone(),
+        );
+        Ok((result, receipt))
+    }
+
+    /// 按本 EVM 的栈深度上限和规范的内存上限创建机器
+    pub(crate) fn new_machine(&self, gas: u64) -> Machine {
+        let mut machine = Machine::for_spec::<SPEC>(gas);
+        machine.stack = Stack::new(self.stack_limit);
+        machine
+    }
+
+    /// 把未使用的 gas 费用退还给发送者（交易开始时已预扣 `gas_limit * gas_price`）
+    fn refund_unused_gas(&mut self, tx: &Transaction, gas_used: u64) -> Result<(), Error> {
+        let unused = U256::from(tx.gas_limit - gas_used) * tx.gas_price;
+        if !unused.is_zero() {
+            let balance = self.balance(tx.caller)?;
+            self.call_manager
+                .commit_state_change(StateChange::UpdateBalance {
+                    address: tx.caller,
+                    balance: balance + unused,
+                });
+        }
+        Ok(())
+    }
+
+    /// 计算退款前消耗的 gas，debug 构建下检查记账是否自洽
+    fn gas_used(&self, gas_limit: u64, intrinsic: u64) -> u64 {
+        let gas_used = gas_limit.saturating_sub(self.machine.gas);
+        #[cfg(test)]
+        let gas_used = gas_used + self.gas_used_skew;
+
+        if cfg!(debug_assertions) {
+            if let Err(message) =
+                check_gas_accounting(gas_limit, gas_used, self.machine.gas, intrinsic)
+            {
+                panic!("gas 记账错误: {}", message);
+            }
+        }
+        gas_used
+    }
+
     /// 执行调用
     fn execute_call(
         &mut self,
@@ -174,37 +566,88 @@ impl<SPEC: Spec, DB: Database> EVM<SPEC, DB> {
         to: Address,
         value: U256,
         data: &[u8],
-    ) -> Result<Vec<u8>, Error> {
-        // 消耗 CALL 的基础 gas（使用规范参数）
-        self.machine.use_gas(SPEC::GAS_CALL)?;
-
-        println!("   CALL gas 成本: {}", SPEC::GAS_CALL);
+    ) -> Result<CallResult, Error> {
+        // 预编译合约不执行字节码，直接交给分发器
+        if let Some(id) = precompile_id(to).filter(|id| SPEC::precompiles().contains(id)) {
+            narrate!(self, "   调用预编译合约 {:#x}", to);
+            let transfer = self.transfer_changes(caller, to, value)?;
+            let (output, gas_used) = self.precompiles.run(id, data, self.machine.gas)?;
+            self.machine.use_gas(gas_used)?;
+            for change in transfer {
+                self.call_manager.commit_state_change(change);
+            }
+            return Ok(CallResult::Success {
+                output,
+                gas_left: self.machine.gas,
+            });
+        }
 
         // 检查目标账户
-        let account = self.database.basic(to).map_err(|_| Error::DatabaseError)?;
+        let account = self.database.basic(to).map_err(Error::database)?;
 
         match account {
             Some(acc) if acc.code_hash != Default::default() => {
-                println!("   调用合约 {:#x}", to);
+                narrate!(self, "   调用合约 {:#x}", to);
 
                 // 获取合约代码
-                let code = self.database.code(to).map_err(|_| Error::DatabaseError)?;
+                let code = self.database.code(to).map_err(Error::database)?;
 
-                println!("   合约代码长度: {} 字节", code.bytes.len());
+                narrate!(self, "   合约代码长度: {} 字节", code.bytes.len());
 
-                // 模拟简单的合约执行
-                if !code.bytes.is_empty() {
-                    // 这里可以添加真正的字节码解释器
-                    // 现在只是返回一些模拟数据
-                    Ok(vec![0x42, 0x00]) // 模拟返回值
-                } else {
-                    Ok(Vec::new())
+                // 登记最外层调用帧，供指令读取调用上下文
+                let frame = CallFrame::new_call(
+                    caller,
+                    to,
+                    value,
+                    data.to_vec(),
+                    self.machine.gas,
+                    CallType::Call,
+                    0,
+                );
+                self.call_manager.begin_call(frame)?;
+                self.machine.self_address = to;
+
+                // 转账记录在最外层帧中，执行失败时一并回滚
+                for change in self.transfer_changes(caller, to, value)? {
+                    self.call_manager.record_state_change(change);
                 }
+
+                // 解释执行合约字节码
+                Ok(match self.execute_bytecode(&code) {
+                    Ok((true, output)) => {
+                        self.call_manager.end_call(true, output.clone());
+                        CallResult::Success {
+                            output,
+                            gas_left: self.machine.gas,
+                        }
+                    }
+                    Ok((false, output)) => {
+                        // REVERT：回滚状态变更，剩余 gas 保留
+                        self.call_manager.end_call(false, output.clone());
+                        CallResult::Revert {
+                            output,
+                            gas_left: self.machine.gas,
+                        }
+                    }
+                    Err(reason) => {
+                        self.call_manager.end_call(false, Vec::new());
+                        CallResult::Halt {
+                            reason,
+                            gas_left: 0,
+                        }
+                    }
+                })
             }
             _ => {
-                println!("   调用外部账户 {:#x}", to);
-                // 外部账户调用，没有代码执行
-                Ok(Vec::new())
+                narrate!(self, "   调用外部账户 {:#x}", to);
+                // 外部账户调用，没有代码执行，只转账
+                for change in self.transfer_changes(caller, to, value)? {
+                    self.call_manager.commit_state_change(change);
+                }
+                Ok(CallResult::Success {
+                    output: Vec::new(),
+                    gas_left: self.machine.gas,
+                })
             }
         }
     }
@@ -213,48 +656,131 @@ impl<SPEC: Spec, DB: Database> EVM<SPEC, DB> {
     fn execute_create(
         &mut self,
         caller: Address,
+        nonce: u64,
         value: U256,
         init_code: &[u8],
-    ) -> Result<Vec<u8>, Error> {
-        // 消耗 CREATE 的基础 gas（使用规范参数）
-        self.machine.use_gas(SPEC::GAS_CREATE)?;
-
-        println!("   CREATE gas 成本: {}", SPEC::GAS_CREATE);
+    ) -> Result<CallResult, Error> {
+        // 计算新合约地址
+        let contract_address = self.calculate_create_address(caller, nonce);
 
-        // 检查代码大小限制
-        if init_code.len() > SPEC::MAX_CODE_SIZE {
-            return Err(Error::OutOfMemory);
-        }
+        narrate!(self, "   新合约地址: {:#x}", contract_address);
+        narrate!(self, "   初始化代码长度: {} 字节", init_code.len());
 
-        // 计算新合约地址
-        let contract_address = self.calculate_create_address(caller, 1); // 简化的 nonce
+        // 登记最外层创建帧，初始化代码在其中执行
+        let mut frame = CallFrame::new_create(
+            caller,
+            value,
+            init_code.to_vec(),
+            self.machine.gas,
+            CallType::Create,
+            0,
+        );
+        frame.code_address = contract_address;
+        frame.to_address = contract_address;
+        self.call_manager.begin_call(frame)?;
+        self.machine.self_address = contract_address;
 
-        println!("   新合约地址: {:#x}", contract_address);
-        println!("   初始化代码长度: {} 字节", init_code.len());
+        for change in self.transfer_changes(caller, contract_address, value)? {
+            self.call_manager.record_state_change(change);
+        }
 
-        // 计算代码部署成本
-        let deploy_cost = (init_code.len() as u64) * SPEC::GAS_CODE_DEPOSIT;
-        self.machine.use_gas(deploy_cost)?;
+        // 初始化代码中的 SSTORE 退款累计在 machine.refund 中，由 transact 统一封顶
+        Ok(
+            match self.execute_bytecode(&Bytecode::new(init_code.to_vec())) {
+                Ok((true, code)) => {
+                    // 计算代码部署成本
+                    let deploy_cost = (code.len() as u64) * SPEC::GAS_CODE_DEPOSIT;
+                    // 部署的代码受 EIP-170 大小限制
+                    let deposit = if code.len() > SPEC::MAX_CODE_SIZE {
+                        Err(Error::CodeTooLarge)
+                    } else {
+                        self.machine.use_gas(deploy_cost)
+                    };
+                    if let Err(reason) = deposit {
+                        self.call_manager.end_call(false, Vec::new());
+                        return Ok(CallResult::Halt {
+                            reason,
+                            gas_left: 0,
+                        });
+                    }
 
-        println!("   代码部署 gas 成本: {}", deploy_cost);
+                    narrate!(self, "   代码部署 gas 成本: {}", deploy_cost);
 
-        // 模拟合约创建成功
-        Ok(contract_address.as_bytes().to_vec())
+                    let balance = self.balance(contract_address)?;
+                    let code = Bytecode::new(code);
+                    self.call_manager
+                        .record_state_change(StateChange::CreateAccount {
+                            address: contract_address,
+                            info: AccountInfo {
+                                balance,
+                                nonce: 1,
+                                code_hash: code.hash,
+                                code: Some(code.bytes),
+                            },
+                        });
+                    self.call_manager.end_call(true, Vec::new());
+                    CallResult::Success {
+                        output: contract_address.as_bytes().to_vec(),
+                        gas_left: self.machine.gas,
+                    }
+                }
+                Ok((false, output)) => {
+                    self.call_manager.end_call(false, output.clone());
+                    CallResult::Revert {
+                        output,
+                        gas_left: self.machine.gas,
+                    }
+                }
+                Err(reason) => {
+                    self.call_manager.end_call(false, Vec::new());
+                    CallResult::Halt {
+                        reason,
+                        gas_left: 0,
+                    }
+                }
+            },
+        )
     }
 
-    /// 计算 CREATE 地址
-    fn calculate_create_address(&self, caller: Address, nonce: u64) -> Address {
-        // 简化实现：使用 caller + nonce 计算地址
-        // 实际实现应该使用 RLP 编码 + Keccak256
-        let mut addr_bytes = [0u8; 20];
-        let caller_bytes = caller.as_bytes();
+    /// 计算 CREATE 地址：keccak256(rlp([caller, nonce])) 的低 20 字节
+    pub(crate) fn calculate_create_address(&self, caller: Address, nonce: u64) -> Address {
+        // nonce 按 RLP 编码为去掉前导零的大端字节，0 编码为空字节串 0x80，
+        // 小于 0x80 的单字节编码为其自身
         let nonce_bytes = nonce.to_be_bytes();
-
-        for i in 0..20 {
-            addr_bytes[i] = caller_bytes[i] ^ nonce_bytes[i % 8];
+        let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+        let mut nonce_rlp = Vec::with_capacity(9);
+        match nonce_bytes {
+            [byte] if *byte < 0x80 => nonce_rlp.push(*byte),
+            bytes => {
+                nonce_rlp.push(0x80 + bytes.len() as u8);
+                nonce_rlp.extend_from_slice(bytes);
+            }
         }
 
-        Address::from(addr_bytes)
+        // 列表总长度不超过 30 字节，使用短列表前缀 0xc0 + 长度
+        let mut rlp = Vec::with_capacity(32);
+        rlp.push(0xc0 + 21 + nonce_rlp.len() as u8);
+        rlp.push(0x80 + 20);
+        rlp.extend_from_slice(caller.as_bytes());
+        rlp.extend_from_slice(&nonce_rlp);
+
+        Address::from_slice(&keccak_hash::keccak(&rlp)[12..])
+    }
+
+    /// 计算 CREATE2 地址 (EIP-1014)：keccak256(0xff ++ caller ++ salt ++ keccak256(init_code)) 的低 20 字节
+    pub(crate) fn calculate_create2_address(
+        &self,
+        caller: Address,
+        salt: H256,
+        init_code_hash: H256,
+    ) -> Address {
+        let mut preimage = Vec::with_capacity(85);
+        preimage.push(0xff);
+        preimage.extend_from_slice(caller.as_bytes());
+        preimage.extend_from_slice(salt.as_bytes());
+        preimage.extend_from_slice(init_code_hash.as_bytes());
+
+        Address::from_slice(&keccak_hash::keccak(&preimage)[12..])
     }
 
     /// 获取数据库引用（用于测试）
@@ -267,6 +793,139 @@ impl<SPEC: Spec, DB: Database> EVM<SPEC, DB> {
         &mut self.database
     }
 
+    /// 安装执行观察者，替换已有的观察者
+    pub fn set_inspector<I: Inspector>(&mut self, inspector: I) {
+        self.inspector = Some(Box::new(inspector));
+    }
+
+    /// 获取已安装的观察者，类型不匹配时返回 None
+    pub fn inspector<I: Inspector>(&self) -> Option<&I> {
+        let inspector: &dyn std::any::Any = self.inspector.as_deref()?;
+        inspector.downcast_ref()
+    }
+
+    /// 注册自定义指令处理函数，覆盖同一操作码的内置实现
+    pub fn register_opcode<F>(&mut self, opcode: u8, handler: F)
+    where
+        F: Fn(&mut Machine, &mut ExecutionContext) -> Result<(), Error> + 'static,
+    {
+        self.opcode_table.register(opcode, handler);
+    }
+
+    /// 指令处理函数表（可变）
+    pub fn opcode_table_mut(&mut self) -> &mut OpcodeHandlerTable {
+        &mut self.opcode_table
+    }
+
+    /// 设置执行资源限制
+    pub fn set_limits(&mut self, limits: ExecutionLimits) {
+        self.limits = limits;
+    }
+
+    /// 当前的执行资源限制
+    pub fn limits(&self) -> &ExecutionLimits {
+        &self.limits
+    }
+
+    /// 设置单笔交易最多执行的指令数
+    ///
+    /// 即使 gas 记账出错导致循环不消耗 gas，执行也会在达到上限时
+    /// 以 `Error::StepLimitExceeded` 中止。默认为 `u64::MAX`。
+    pub fn set_max_steps(&mut self, max_steps: u64) {
+        self.max_steps = max_steps;
+    }
+
+    /// 单笔交易最多执行的指令数
+    pub fn max_steps(&self) -> u64 {
+        self.max_steps
+    }
+
+    /// 本笔交易（或单步执行中）已执行的指令数
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    /// 最近一次 `apply_transactions` 或 `execute_block` 累计使用的 gas，
+    /// 之后通过 `transact_with_receipt` 执行的交易继续累加
+    pub fn cumulative_gas_used(&self) -> u64 {
+        self.cumulative_gas_used
+    }
+
+    /// 注册预编译合约的实现，替换同一编号已有的实现
+    ///
+    /// 只有 `SPEC::precompiles()` 中列出的编号会被当作预编译合约调用。
+    pub fn register_precompile<P: Precompile + 'static>(&mut self, id: u8, precompile: P) {
+        self.precompiles.register(id, precompile);
+    }
+
+    /// 安装逐步执行追踪器，替换已有的追踪器
+    pub fn set_tracer<T: Tracer>(&mut self, tracer: T) {
+        self.tracer = Some(Box::new(tracer));
+    }
+
+    /// 是否打印执行过程（默认打印）
+    ///
+    /// 安装追踪器后执行过程由追踪器输出，不再打印，例如 `JsonTracer` 的结果只有 JSON。
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// 获取已安装的追踪器，类型不匹配时返回 None
+    pub fn tracer<T: Tracer>(&self) -> Option<&T> {
+        let tracer: &dyn std::any::Any = self.tracer.as_deref()?;
+        tracer.downcast_ref()
+    }
+
+    /// 按函数选择器和 ABI 编码的参数调用合约
+    ///
+    /// 调用数据为 `selector ++ args`，以零地址为调用者、不附带转账。
+    /// 执行失败时返回 `Error::Reverted`，携带 revert 数据（异常终止时为空）。
+    pub fn call_function(
+        &mut self,
+        to: Address,
+        selector: [u8; 4],
+        args: Vec<u8>,
+        gas: u64,
+    ) -> Result<Vec<u8>, Error> {
+        let mut data = selector.to_vec();
+        data.extend(args);
+        let result = self.transact(Transaction {
+            caller: Address::zero(),
+            to: Some(to),
+            value: U256::zero(),
+            data,
+            gas_limit: gas,
+            gas_price: U256::zero(),
+            nonce: None,
+            chain_id: None,
+            access_list: Vec::new(),
+        })?;
+
+        if result.success {
+            Ok(result.return_data)
+        } else {
+            Err(Error::Reverted(result.return_data))
+        }
+    }
+
+    /// 执行交易并返回每条指令执行后的栈内容（从栈底到栈顶），用于栈可视化
+    ///
+    /// 执行期间临时替换为 `StackSnapshotTracer`，结束后恢复原有的追踪器。
+    /// 交易失败时返回失败前已记录的快照。
+    pub fn trace_stack_evolution(&mut self, tx: Transaction) -> Vec<Vec<U256>> {
+        let previous = self.tracer.replace(Box::new(StackSnapshotTracer::new()));
+        let _ = self.transact(tx);
+        let tracer: Box<dyn std::any::Any> = match std::mem::replace(&mut self.tracer, previous) {
+            Some(tracer) => tracer,
+            None => return Vec::new(),
+        };
+
+        tracer
+            .downcast::<StackSnapshotTracer>()
+            .map(|tracer| tracer.into_snapshots())
+            .unwrap_or_default()
+    }
+
     /// 获取当前机器状态（用于调试）
     pub fn machine(&self) -> &Machine {
         &self.machine
@@ -312,6 +971,171 @@ impl<SPEC: Spec, DB: Database> EVM<SPEC, DB> {
     }
 }
 
+impl<SPEC: Spec, DB: DatabaseCommit> EVM<SPEC, DB> {
+    /// 在当前环境中按顺序执行一批交易
+    ///
+    /// 每笔交易结束后把它的状态变更（包括 nonce 和余额）提交到数据库，
+    /// 后面的交易可以看到前面交易的效果；累计的 gas 由 `cumulative_gas_used` 返回。
+    /// 无效交易中止整批执行，已执行的交易的变更保持提交状态。
+    pub fn apply_transactions(
+        &mut self,
+        txs: Vec<Transaction>,
+    ) -> Result<Vec<ExecutionResult>, Error> {
+        self.cumulative_gas_used = 0;
+        txs.into_iter()
+            .map(|tx| self.apply_transaction(tx))
+            .collect()
+    }
+
+    /// 以区块头设置执行环境，按顺序执行区块内的交易
+    ///
+    /// 与 `apply_transactions` 相同，另外在每笔交易执行前检查区块剩余的 gas：
+    /// 已使用的 gas 加上交易的 gas 上限超过区块 gas 上限时返回
+    /// `Error::BlockGasLimitExceeded`。每笔交易的优先费支付给 coinbase；
+    /// 启用 EIP-1559 时基础费用被销毁，gas 价格低于基础费用的交易无效。
+    pub fn execute_block(
+        &mut self,
+        header: BlockHeader,
+        txs: Vec<Transaction>,
+    ) -> Result<BlockResult, Error> {
+        self.env.block_number = U256::from(header.number);
+        self.env.block_timestamp = U256::from(header.timestamp);
+        self.env.coinbase = header.coinbase;
+        self.env.base_fee = header.base_fee;
+        self.env.block_gas_limit = header.gas_limit;
+        self.cumulative_gas_used = 0;
+        narrate!(
+            self,
+            "📦 执行区块 #{} ({} 笔交易)",
+            header.number,
+            txs.len()
+        );
+
+        let mut results = Vec::with_capacity(txs.len());
+        for tx in txs {
+            // 交易的 gas 上限必须能放入区块的剩余空间
+            match self.cumulative_gas_used.checked_add(tx.gas_limit) {
+                Some(total) if total <= self.env.block_gas_limit => {}
+                _ => return Err(Error::BlockGasLimitExceeded),
+            }
+            if SPEC::ENABLE_EIP1559 && tx.gas_price < self.env.base_fee {
+                return Err(Error::GasPriceBelowBaseFee);
+            }
+
+            let gas_price = tx.gas_price;
+            let result = self.apply_transaction(tx)?;
+            self.pay_coinbase(gas_price, result.gas_used)?;
+            results.push(result);
+        }
+
+        narrate!(
+            self,
+            "📦 区块执行完成，累计 Gas 使用: {}",
+            self.cumulative_gas_used
+        );
+        Ok(BlockResult {
+            results,
+            gas_used: self.cumulative_gas_used,
+        })
+    }
+
+    /// 把一笔交易的优先费支付给 coinbase，基础费用部分被销毁 (EIP-1559)
+    fn pay_coinbase(&mut self, gas_price: U256, gas_used: u64) -> Result<(), Error> {
+        let priority_fee = if SPEC::ENABLE_EIP1559 {
+            gas_price - self.env.base_fee
+        } else {
+            gas_price
+        };
+        let reward = priority_fee * U256::from(gas_used);
+        if reward.is_zero() {
+            return Ok(());
+        }
+
+        let coinbase = self.env.coinbase;
+        let change = match self.database.basic(coinbase).map_err(Error::database)? {
+            Some(account) => StateChange::UpdateBalance {
+                address: coinbase,
+                balance: account.balance.saturating_add(reward),
+            },
+            None => StateChange::CreateAccount {
+                address: coinbase,
+                info: AccountInfo {
+                    balance: reward,
+                    ..Default::default()
+                },
+            },
+        };
+        self.database.commit(vec![change]).map_err(Error::database)
+    }
+
+    /// 执行一笔交易，把状态变更提交到数据库并累计 gas
+    fn apply_transaction(&mut self, tx: Transaction) -> Result<ExecutionResult, Error> {
+        let result = self.transact(tx)?;
+        let changes = self.call_manager.take_committed_changes();
+        self.database.commit(changes).map_err(Error::database)?;
+        self.cumulative_gas_used += result.gas_used;
+        Ok(result)
+    }
+}
+
+/// EVM 构建器
+///
+/// 不定义新规范也能覆盖调用深度和栈深度上限（例如用于模糊测试），
+/// 未设置的项取规范中的常量，环境默认为 `Environment::default()`。
+pub struct EvmBuilder<SPEC: Spec, DB: Database> {
+    database: DB,
+    env: Environment,
+    call_depth_limit: Option<usize>,
+    stack_limit: Option<usize>,
+    verbose: bool,
+    _spec: PhantomData<SPEC>,
+}
+
+impl<SPEC: Spec, DB: Database> EvmBuilder<SPEC, DB> {
+    pub fn new(database: DB) -> Self {
+        Self {
+            database,
+            env: Environment::default(),
+            call_depth_limit: None,
+            stack_limit: None,
+            verbose: true,
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn env(mut self, env: Environment) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// 调用栈最大深度，覆盖 `SPEC::CALL_DEPTH_LIMIT`
+    pub fn call_depth_limit(mut self, limit: usize) -> Self {
+        self.call_depth_limit = Some(limit);
+        self
+    }
+
+    /// 栈最大深度，覆盖 `SPEC::STACK_LIMIT`
+    pub fn stack_limit(mut self, limit: usize) -> Self {
+        self.stack_limit = Some(limit);
+        self
+    }
+
+    /// 是否打印执行过程，见 `EVM::set_verbose`
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn build(self) -> EVM<SPEC, DB> {
+        let mut evm = EVM::new(self.database, self.env);
+        evm.call_depth_limit = self.call_depth_limit.unwrap_or(SPEC::CALL_DEPTH_LIMIT);
+        evm.stack_limit = self.stack_limit.unwrap_or(SPEC::STACK_LIMIT);
+        evm.call_manager = CallManager::new(evm.call_depth_limit);
+        evm.verbose = self.verbose;
+        evm
+    }
+}
+
 /// 演示模块化设计的工厂函数
 pub fn create_berlin_evm<DB: Database>(database: DB) -> EVM<crate::spec::Berlin, DB> {
     use crate::spec::Berlin;
@@ -327,3 +1151,420 @@ pub fn create_frontier_evm<DB: Database>(database: DB) -> EVM<crate::spec::Front
     use crate::spec::Frontier;
     EVM::<Frontier, DB>::new(database, Environment::default())
 }
+
+/// `run_bytecode` 部署代码的地址
+pub const RUN_BYTECODE_ADDRESS: Address = Address::repeat_byte(0xc0);
+
+/// 在空白的 London 状态上反复执行同一段字节码
+///
+/// 代码在创建时部署到 `RUN_BYTECODE_ADDRESS`（只计算一次代码哈希），
+/// 每次 `run` 复用同一个 EVM，由零地址以给定的 gas limit 调用。
+/// 交易的状态变更不提交到数据库，多次执行互不影响；执行过程不打印，适合基准测试。
+pub struct BytecodeRunner {
+    evm: EVM<crate::spec::London, crate::database::InMemoryDB>,
+}
+
+impl BytecodeRunner {
+    pub fn new(code: &[u8]) -> Self {
+        let mut db = crate::database::InMemoryDB::new();
+        db.insert_account(
+            RUN_BYTECODE_ADDRESS,
+            AccountInfo {
+                balance: U256::zero(),
+                nonce: 1,
+                code_hash: keccak_hash::keccak(code),
+                code: Some(code.to_vec()),
+            },
+        );
+        let mut evm = create_london_evm(db);
+        evm.set_verbose(false);
+        Self { evm }
+    }
+
+    /// 以 `gas` 为 gas limit 执行一次
+    ///
+    /// 交易本身无效（如 gas 低于固有成本）时返回未消耗 gas 的失败结果。
+    pub fn run(&mut self, gas: u64) -> ExecutionResult {
+        self.evm
+            .transact(Transaction {
+                caller: Address::zero(),
+                to: Some(RUN_BYTECODE_ADDRESS),
+                value: U256::zero(),
+                data: Vec::new(),
+                gas_limit: gas,
+                gas_price: U256::zero(),
+                nonce: None,
+                chain_id: None,
+                access_list: Vec::new(),
+            })
+            .unwrap_or_else(|_| ExecutionResult {
+                success: false,
+                gas_used: 0,
+                gas_refunded: 0,
+                return_data: Vec::new(),
+                logs: Vec::new(),
+            })
+    }
+}
+
+/// 在空白的 London 状态上执行一段字节码
+///
+/// 省去手动构造数据库和 `Transaction`，见 `BytecodeRunner`。
+/// 需要反复执行同一段代码时直接复用 `BytecodeRunner`。
+pub fn run_bytecode(code: &[u8], gas: u64) -> ExecutionResult {
+    BytecodeRunner::new(code).run(gas)
+}
+
+/// 对任意规范的 EVM 执行的操作，配合 `with_spec_id` 使用
+///
+/// 闭包不能带泛型参数，因此用 trait 的泛型方法表达
+/// “对每个具体规范都单独单态化”的操作。
+pub trait WithSpec<DB: Database> {
+    type Output;
+
+    fn run<SPEC: Spec>(self, evm: EVM<SPEC, DB>) -> Self::Output;
+}
+
+/// 按运行时的规范标识构造对应的 `EVM<SPEC, DB>` 并交给 `f`
+///
+/// 分发只发生一次，`f` 内部仍然是编译期特化的代码。
+pub fn with_spec_id<DB: Database, F: WithSpec<DB>>(id: SpecId, db: DB, f: F) -> F::Output {
+    use crate::spec::{Berlin, Cancun, Frontier, London};
+
+    let env = Environment::default();
+    match id {
+        SpecId::Frontier => f.run(EVM::<Frontier, DB>::new(db, env)),
+        SpecId::Berlin => f.run(EVM::<Berlin, DB>::new(db, env)),
+        SpecId::London => f.run(EVM::<London, DB>::new(db, env)),
+        SpecId::Cancun => f.run(EVM::<Cancun, DB>::new(db, env)),
+    }
+}
+
+/// 在指定执行环境下执行一笔交易
+struct TransactWithEnv {
+    env: Environment,
+    tx: Transaction,
+}
+
+impl<DB: Database> WithSpec<DB> for TransactWithEnv {
+    type Output = Result<ExecutionResult, Error>;
+
+    fn run<SPEC: Spec>(self, mut evm: EVM<SPEC, DB>) -> Self::Output {
+        evm.env = self.env;
+        evm.transact(self.tx)
+    }
+}
+
+/// 按区块所在的分叉选择规范并执行交易
+///
+/// 根据 `SpecId::for_block` 选出生效的规范，用对应区块号和时间戳的
+/// 执行环境构造 EVM，再执行交易。
+pub fn run_with_fork_for_block<DB: Database>(
+    block_number: u64,
+    timestamp: u64,
+    tx: Transaction,
+    db: DB,
+) -> Result<ExecutionResult, Error> {
+    let env = Environment {
+        block_number: U256::from(block_number),
+        block_timestamp: U256::from(timestamp),
+        ..Environment::default()
+    };
+
+    with_spec_id(
+        SpecId::for_block(block_number, timestamp),
+        db,
+        TransactWithEnv { env, tx },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDB;
+    use crate::spec::{Berlin, London};
+    use crate::test_utils::{addr, call_tx, deploy};
+
+    #[test]
+    fn test_block_transactions_share_state() {
+        let caller = addr(1);
+        // 部署的代码返回 42
+        let runtime = [
+            0x60,
+            0x2a,
+            0x60,
+            0x00,
+            0x52,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        // CODECOPY(0, 12, 10); RETURN(0, 10)
+        let mut init_code = vec![
+            0x60,
+            0x0a,
+            0x60,
+            0x0c,
+            0x60,
+            0x00,
+            0x39,
+            0x60,
+            0x0a,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        init_code.extend(runtime);
+
+        let tx = |to, data| Transaction {
+            caller,
+            to,
+            data,
+            gas_limit: 1_000_000,
+            ..Default::default()
+        };
+        let mut db = InMemoryDB::new();
+        db.insert_account(caller, AccountInfo::default());
+        let mut evm = create_berlin_evm(db);
+        let contract = evm.calculate_create_address(caller, 0);
+        let header = BlockHeader {
+            number: 1,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        let txs = vec![tx(None, init_code), tx(Some(contract), Vec::new())];
+
+        // 第二笔交易调用第一笔交易创建的合约
+        let block = evm.execute_block(header.clone(), txs.clone()).unwrap();
+        let results = &block.results;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.success));
+        assert_eq!(Address::from_slice(&results[0].return_data), contract);
+        assert_eq!(
+            U256::from_big_endian(&results[1].return_data),
+            U256::from(42)
+        );
+        assert_eq!(block.gas_used, results[0].gas_used + results[1].gas_used);
+        assert_eq!(evm.database_mut().basic(caller).unwrap().unwrap().nonce, 2);
+
+        // 第一笔交易之后，剩余空间放不下第二笔交易的 gas 上限
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        let small = BlockHeader {
+            gas_limit: 1_000_000 + results[0].gas_used - 1,
+            ..header
+        };
+        assert_eq!(
+            evm.execute_block(small, txs).unwrap_err(),
+            Error::BlockGasLimitExceeded
+        );
+    }
+
+    #[test]
+    fn test_block_pays_priority_fee_to_coinbase() {
+        let caller = addr(1);
+        let coinbase = addr(0xc0);
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            caller,
+            AccountInfo {
+                balance: U256::from(1_000_000_000u64),
+                ..Default::default()
+            },
+        );
+        let header = BlockHeader {
+            number: 1,
+            coinbase,
+            base_fee: U256::from(10),
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        let tx = |gas_price: u64| Transaction {
+            caller,
+            to: Some(addr(2)),
+            gas_limit: 21_000,
+            gas_price: U256::from(gas_price),
+            ..Default::default()
+        };
+
+        let mut evm = create_london_evm(db);
+        let block = evm.execute_block(header.clone(), vec![tx(13)]).unwrap();
+        assert_eq!(block.gas_used, 21_000);
+
+        // 发送者支付全部 gas 费用，coinbase 只收到超出基础费用的部分
+        let balance = |evm: &mut EVM<London, InMemoryDB>, address| {
+            evm.database_mut()
+                .basic(address)
+                .unwrap()
+                .map_or(U256::zero(), |account| account.balance)
+        };
+        assert_eq!(
+            balance(&mut evm, caller),
+            U256::from(1_000_000_000u64 - 13 * 21_000)
+        );
+        assert_eq!(balance(&mut evm, coinbase), U256::from(3 * 21_000));
+
+        // gas 价格低于基础费用的交易无效
+        assert_eq!(
+            evm.execute_block(header, vec![tx(9)]).unwrap_err(),
+            Error::GasPriceBelowBaseFee
+        );
+    }
+
+    #[test]
+    fn test_transact_with_receipt() {
+        use ethereum_types::BloomInput;
+
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        // LOG0(0, 0)
+        deploy(&mut db, contract, vec![0x60, 0x00, 0x60, 0x00, op::LOG0]);
+
+        let mut evm = create_berlin_evm(db);
+        let tx = Transaction {
+            gas_limit: 100_000,
+            ..call_tx(contract)
+        };
+        let (first, receipt) = evm.transact_with_receipt(tx.clone()).unwrap();
+        assert!(receipt.status);
+        assert_eq!(receipt.cumulative_gas_used, first.gas_used);
+        assert_eq!(receipt.logs, first.logs);
+        assert_eq!(receipt.logs_bloom, logs_bloom(&first.logs));
+        assert!(receipt
+            .logs_bloom
+            .contains_input(BloomInput::Raw(contract.as_bytes())));
+
+        // 累计 gas 跨交易累加
+        let (second, receipt) = evm.transact_with_receipt(tx).unwrap();
+        assert_eq!(
+            receipt.cumulative_gas_used,
+            first.gas_used + second.gas_used
+        );
+    }
+
+    #[test]
+    fn test_apply_transactions_carries_state() {
+        let caller = addr(1);
+        let counter = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            caller,
+            AccountInfo {
+                balance: U256::from(1_000_000),
+                ..Default::default()
+            },
+        );
+        // SSTORE(0, SLOAD(0) + 1)，返回新值
+        let code = assemble(
+            "PUSH1 0 SLOAD PUSH1 1 ADD PUSH1 0 SSTORE
+             PUSH1 0 SLOAD PUSH1 0 MSTORE PUSH1 0x20 PUSH1 0 RETURN",
+        )
+        .unwrap();
+        deploy(&mut db, counter, code);
+
+        let tx = Transaction {
+            caller,
+            to: Some(counter),
+            gas_limit: 100_000,
+            gas_price: U256::one(),
+            ..Default::default()
+        };
+        let mut evm = create_berlin_evm(db);
+        let results = evm.apply_transactions(vec![tx.clone(), tx]).unwrap();
+
+        // 第二笔交易读到第一笔交易写入的值
+        let values: Vec<_> = results
+            .iter()
+            .map(|result| U256::from_big_endian(&result.return_data))
+            .collect();
+        assert_eq!(values, [U256::from(1), U256::from(2)]);
+
+        let total = results[0].gas_used + results[1].gas_used;
+        assert_eq!(evm.cumulative_gas_used(), total);
+        let account = evm.database_mut().basic(caller).unwrap().unwrap();
+        assert_eq!(account.nonce, 2);
+        assert_eq!(account.balance, U256::from(1_000_000 - total));
+    }
+
+    #[test]
+    fn test_transaction_chain_id_must_match_environment() {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        assert_eq!(evm.env.chain_id, U256::one());
+        let tx = |chain_id| Transaction {
+            gas_limit: 100_000,
+            chain_id,
+            ..call_tx(addr(2))
+        };
+
+        assert!(evm.transact(tx(Some(1))).unwrap().success);
+        assert_eq!(
+            evm.transact(tx(Some(5))).unwrap_err(),
+            Error::ChainIdMismatch {
+                expected: U256::one(),
+                got: 5
+            }
+        );
+        // 被拒绝的交易不会提升 nonce
+        assert!(evm.call_manager.take_committed_changes().is_empty());
+
+        // 旧式交易不校验链 ID
+        assert!(evm.transact(tx(None)).unwrap().success);
+    }
+
+    #[test]
+    fn test_builder_overrides_call_depth_limit() {
+        let (a, b, c) = (addr(0xaa), addr(0xbb), addr(0xcc));
+        // CALL(0xffff, to, 0, 0, 0, 0, 0)
+        let call_code = |to: Address| {
+            let mut code = vec![
+                0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+            ];
+            code.extend_from_slice(to.as_bytes());
+            code.extend([0x61, 0xff, 0xff, op::CALL]);
+            code
+        };
+        let run = |depth_limit: Option<usize>| {
+            // A 调用 B，B 调用 C，C 发出一条 LOG0
+            let mut db = InMemoryDB::new();
+            deploy(&mut db, a, call_code(b));
+            deploy(&mut db, b, call_code(c));
+            deploy(&mut db, c, vec![0x60, 0x00, 0x60, 0x00, op::LOG0]);
+
+            let mut builder = EVM::<Berlin, _>::builder(db);
+            if let Some(limit) = depth_limit {
+                builder = builder.call_depth_limit(limit);
+            }
+            builder.build().transact(call_tx(a)).unwrap()
+        };
+
+        // 默认取规范的深度上限，三层调用都能执行
+        assert_eq!(run(None).logs.len(), 1);
+        assert_eq!(run(Some(3)).logs.len(), 1);
+
+        // 深度上限为 2 时 B 对 C 的调用失败，A 仍然正常结束
+        let limited = run(Some(2));
+        assert!(limited.success);
+        assert!(limited.logs.is_empty());
+    }
+
+    #[test]
+    fn test_builder_overrides_stack_limit() {
+        let mut evm = EVM::<Berlin, _>::builder(InMemoryDB::new())
+            .stack_limit(2)
+            .build();
+        evm.load_code(addr(0xaa), vec![0x60, 0x01, 0x60, 0x02, 0x60, 0x03], 1_000);
+        assert!(evm.step().is_ok());
+        assert!(evm.step().is_ok());
+        assert_eq!(evm.step().unwrap_err(), Error::StackOverflow);
+    }
+
+    #[test]
+    fn test_builder_sets_verbose() {
+        assert!(create_berlin_evm(InMemoryDB::new()).verbose);
+        let evm = EVM::<Berlin, _>::builder(InMemoryDB::new())
+            .verbose(false)
+            .build();
+        assert!(!evm.verbose);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::evm::engine::Machine;
+use crate::models::*;
+use ethereum_types::{Address, U256};
+use std::collections::HashMap;
+
+/// 自定义指令可以读取的执行上下文
+#[derive(Debug)]
+pub struct ExecutionContext<'a> {
+    /// 区块和交易环境
+    pub env: &'a Environment,
+    /// 当前执行代码的合约地址（存储上下文）
+    pub address: Address,
+    /// 当前调用帧的调用者
+    pub caller: Address,
+    /// 当前调用帧携带的 value
+    pub value: U256,
+    /// 当前调用帧的调用数据
+    pub calldata: &'a [u8],
+    /// 调用深度，最外层为 1
+    pub depth: usize,
+}
+
+/// 指令处理函数
+///
+/// 执行前 pc 已经越过操作码本身；处理函数负责扣除 gas、操作栈和内存，
+/// 如果指令带有立即数，也需要自行移动 pc。
+pub type OpcodeHandler = Box<dyn Fn(&mut Machine, &mut ExecutionContext) -> Result<(), Error>>;
+
+/// 指令处理函数表
+///
+/// 解释器在执行每条指令前先查表，已注册的处理函数优先于内置实现，
+/// 未注册的操作码走内置的指令表。用于在不修改解释器的情况下
+/// 试验新指令或替换已有指令的行为。
+#[derive(Default)]
+pub struct OpcodeHandlerTable {
+    handlers: HashMap<u8, OpcodeHandler>,
+}
+
+impl OpcodeHandlerTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册处理函数，替换该操作码已有的自定义处理函数
+    pub fn register<F>(&mut self, opcode: u8, handler: F)
+    where
+        F: Fn(&mut Machine, &mut ExecutionContext) -> Result<(), Error> + 'static,
+    {
+        self.handlers.insert(opcode, Box::new(handler));
+    }
+
+    /// 移除自定义处理函数，恢复内置实现
+    pub fn unregister(&mut self, opcode: u8) -> bool {
+        self.handlers.remove(&opcode).is_some()
+    }
+
+    /// 查找操作码的自定义处理函数
+    pub fn get(&self, opcode: u8) -> Option<&OpcodeHandler> {
+        self.handlers.get(&opcode)
+    }
+
+    /// 是否没有注册任何自定义处理函数
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+impl std::fmt::Debug for OpcodeHandlerTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut opcodes: Vec<_> = self.handlers.keys().copied().collect();
+        opcodes.sort_unstable();
+        f.debug_struct("OpcodeHandlerTable")
+            .field("opcodes", &opcodes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDB;
+    use crate::evm::create_berlin_evm;
+    use crate::test_utils::{addr, call_tx};
+
+    fn transact_code(
+        code: Vec<u8>,
+        setup: impl FnOnce(&mut OpcodeHandlerTable),
+    ) -> ExecutionResult {
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            contract,
+            AccountInfo {
+                code_hash: keccak_hash::keccak(&code),
+                code: Some(code),
+                ..Default::default()
+            },
+        );
+
+        let mut evm = create_berlin_evm(db);
+        setup(evm.opcode_table_mut());
+        evm.transact(Transaction {
+            gas_limit: 100_000,
+            ..call_tx(contract)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_custom_opcode_pushes_constant() {
+        // 0x0C（未使用）PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let code = vec![0x0c, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+
+        let result = transact_code(code, |table| {
+            table.register(0x0c, |machine, context| {
+                machine.use_gas(2)?;
+                machine.push(U256::from(0x1234) + U256::from(context.depth))
+            });
+        });
+
+        assert!(result.success);
+        assert_eq!(
+            U256::from_big_endian(&result.return_data),
+            U256::from(0x1235)
+        );
+    }
+
+    #[test]
+    fn test_override_takes_precedence_over_builtin() {
+        // PUSH1 1 PUSH1 2 ADD PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let code = vec![
+            0x60, 0x01, 0x60, 0x02, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+        ];
+
+        // 把 ADD 替换为乘法
+        let result = transact_code(code.clone(), |table| {
+            table.register(0x01, |machine, _| {
+                machine.use_gas(3)?;
+                let a = machine.pop()?;
+                let b = machine.pop()?;
+                machine.push(a * b)
+            });
+        });
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::from(2));
+
+        // 注销后恢复内置实现
+        let result = transact_code(code, |table| {
+            table.register(0x01, |_, _| Err(Error::InvalidOpcode));
+            assert!(table.unregister(0x01));
+        });
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::from(3));
+    }
+}
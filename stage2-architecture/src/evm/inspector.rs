@@ -0,0 +1,150 @@
+use ethereum_types::{Address, U256};
+use std::any::Any;
+
+/// 执行观察者（Inspector）
+///
+/// 解释器在执行特定指令时回调对应的钩子，默认实现均为空，
+/// 实现者只需覆盖关心的事件。通过 `EVM::set_inspector` 安装，
+/// 执行结束后用 `EVM::inspector::<T>()` 取回具体类型读取记录。
+pub trait Inspector: Any {
+    /// SLOAD 读取存储槽之后调用
+    fn sload(&mut self, _address: Address, _slot: U256, _value: U256) {}
+
+    /// SSTORE 写入存储槽之后调用，携带写入前后的值
+    fn sstore(&mut self, _address: Address, _slot: U256, _old_value: U256, _new_value: U256) {}
+}
+
+/// 一次存储访问
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageAccess {
+    /// SLOAD
+    Read {
+        address: Address,
+        slot: U256,
+        value: U256,
+    },
+    /// SSTORE
+    Write {
+        address: Address,
+        slot: U256,
+        old_value: U256,
+        new_value: U256,
+    },
+}
+
+/// 存储访问追踪器
+///
+/// 按执行顺序记录每次 SLOAD/SSTORE，便于审计存储值的变化。
+/// 注意：被回滚的子调用中的访问同样会被记录。
+#[derive(Debug, Clone, Default)]
+pub struct StorageTracer {
+    records: Vec<StorageAccess>,
+}
+
+impl StorageTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按执行顺序返回所有存储访问
+    pub fn records(&self) -> &[StorageAccess] {
+        &self.records
+    }
+
+    /// 所有 SLOAD 记录：(地址, 槽位, 值)
+    pub fn reads(&self) -> Vec<(Address, U256, U256)> {
+        self.records
+            .iter()
+            .filter_map(|record| match record {
+                StorageAccess::Read {
+                    address,
+                    slot,
+                    value,
+                } => Some((*address, *slot, *value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 所有 SSTORE 记录：(地址, 槽位, 旧值, 新值)
+    pub fn writes(&self) -> Vec<(Address, U256, U256, U256)> {
+        self.records
+            .iter()
+            .filter_map(|record| match record {
+                StorageAccess::Write {
+                    address,
+                    slot,
+                    old_value,
+                    new_value,
+                } => Some((*address, *slot, *old_value, *new_value)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Inspector for StorageTracer {
+    fn sload(&mut self, address: Address, slot: U256, value: U256) {
+        self.records.push(StorageAccess::Read {
+            address,
+            slot,
+            value,
+        });
+    }
+
+    fn sstore(&mut self, address: Address, slot: U256, old_value: U256, new_value: U256) {
+        self.records.push(StorageAccess::Write {
+            address,
+            slot,
+            old_value,
+            new_value,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDB;
+    use crate::evm::create_berlin_evm;
+    use crate::models::*;
+    use crate::test_utils::{addr, call_tx};
+
+    #[test]
+    fn test_storage_tracer_records_transition() {
+        let contract = addr(0xaa);
+        // PUSH1 42 PUSH1 1 SSTORE PUSH1 1 SLOAD
+        let code = vec![0x60, 0x2a, 0x60, 0x01, 0x55, 0x60, 0x01, 0x54];
+
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            contract,
+            AccountInfo {
+                code_hash: keccak_hash::keccak(&code),
+                code: Some(code),
+                ..Default::default()
+            },
+        );
+
+        let mut evm = create_berlin_evm(db);
+        evm.set_inspector(StorageTracer::new());
+        let result = evm
+            .transact(Transaction {
+                gas_limit: 100_000,
+                ..call_tx(contract)
+            })
+            .unwrap();
+        assert!(result.success);
+
+        let tracer = evm.inspector::<StorageTracer>().unwrap();
+        assert_eq!(
+            tracer.writes(),
+            vec![(contract, U256::one(), U256::zero(), U256::from(42))]
+        );
+        // 同一交易内的读取能看到尚未提交的写入
+        assert_eq!(
+            tracer.reads(),
+            vec![(contract, U256::one(), U256::from(42))]
+        );
+    }
+}
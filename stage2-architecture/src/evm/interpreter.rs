@@ -0,0 +1,4545 @@
+use crate::database::Database;
+use crate::evm::precompile::precompile_id;
+use crate::evm::{
+    CallFrame, CallManager, CallType, ExecutionContext, ExecutionLimits, Machine, EVM,
+};
+use crate::models::arithmetic;
+use crate::models::*;
+use crate::spec::{
+    Spec, GAS_CALL_VALUE, GAS_COLD_ACCOUNT_ACCESS, GAS_COLD_SLOAD, GAS_COPY, GAS_KECCAK256_WORD,
+    GAS_LOG_DATA, GAS_WARM_ACCESS,
+};
+use ethereum_types::{Address, H256, U256};
+
+/// 执行一条指令的结果
+///
+/// 把 EVM 语义中的正常结束（STOP/RETURN/REVERT/SELFDESTRUCT）与真正的故障
+/// (`Fatal`) 区分开：REVERT 不是错误，只有 `Fatal` 携带 `Error`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InstructionResult {
+    /// 继续执行下一条指令
+    Continue,
+    /// STOP 或执行到代码末尾
+    Stop,
+    /// RETURN，携带返回数据
+    Return(Vec<u8>),
+    /// REVERT，携带回滚数据，剩余 gas 不会被消耗
+    Revert(Vec<u8>),
+    /// SELFDESTRUCT
+    SelfDestruct,
+    /// 异常终止，消耗全部 gas
+    Fatal(Error),
+}
+
+/// 指令处理函数
+///
+/// 执行前 pc 已经越过操作码本身，带立即数的指令需要自行移动 pc。
+/// 返回的错误在分发处转换为 `InstructionResult::Fatal`。
+type OpFn<SPEC, DB> = fn(&mut EVM<SPEC, DB>, &Bytecode) -> Result<InstructionResult, Error>;
+
+/// 指令表中的一项：处理函数和分发前检查用的栈效果
+struct Instruction<SPEC: Spec, DB: Database> {
+    execute: OpFn<SPEC, DB>,
+    /// 执行前栈中至少需要的元素数
+    pops: u8,
+    /// 执行后压入的元素数
+    pushes: u8,
+}
+
+impl<SPEC: Spec, DB: Database> Clone for Instruction<SPEC, DB> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<SPEC: Spec, DB: Database> Copy for Instruction<SPEC, DB> {}
+
+/// 把 `opcode` 的处理函数写入指令表，栈效果取自 `Opcode` 表
+const fn set<SPEC: Spec, DB: Database>(
+    table: &mut [Instruction<SPEC, DB>; 256],
+    opcode: u8,
+    execute: OpFn<SPEC, DB>,
+) {
+    let (pops, pushes) = match Opcode::from_u8(opcode) {
+        Some(opcode) => opcode.stack_io(),
+        None => (0, 0),
+    };
+    table[opcode as usize] = Instruction {
+        execute,
+        pops,
+        pushes,
+    };
+}
+
+/// 单步执行的结果（供调试器使用）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepResult {
+    /// 本步执行的操作码（执行到代码末尾时为 STOP）
+    pub opcode: u8,
+    /// 本步消耗的 gas（包含子调用消耗的部分）
+    pub gas_used: u64,
+    /// 是否还可以继续执行下一步
+    pub should_continue: bool,
+}
+
+/// 将栈上的 U256 转换为内存偏移/长度
+fn as_usize(value: U256) -> Result<usize, Error> {
+    if value > U256::from(usize::MAX) {
+        return Err(Error::OutOfMemory);
+    }
+    Ok(value.as_usize())
+}
+
+/// 弹出 RETURN/REVERT 的 (offset, size) 并读取返回数据
+///
+/// size 为 0 时 offset 被忽略，直接返回空数据，不读取也不扩展内存。
+fn return_output(machine: &mut Machine, limits: &ExecutionLimits) -> Result<Vec<u8>, Error> {
+    let offset = machine.pop()?;
+    let size = as_usize(machine.pop()?)?;
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+    let offset = as_usize(offset)?;
+    // 在扩展内存之前检查，避免为超限的返回数据分配内存
+    limits.check_return_size(size)?;
+    machine.expand_memory(offset, size)?;
+    machine.memory_read(offset, size)
+}
+
+/// 取栈上 U256 的低 20 字节作为地址
+fn as_address(value: U256) -> Address {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    Address::from_slice(&bytes[12..])
+}
+
+/// 校验跳转目标，必须是代码中作为指令出现的 JUMPDEST
+fn jump_target(code: &Bytecode, dest: U256) -> Result<usize, Error> {
+    if dest > U256::from(usize::MAX) || !code.is_valid_jump(dest.as_usize()) {
+        return Err(Error::InvalidJump);
+    }
+    Ok(dest.as_usize())
+}
+
+/// 地址左侧补 0 转换为栈上的 U256
+fn address_word(address: Address) -> U256 {
+    U256::from_big_endian(address.as_bytes())
+}
+
+/// 从 data 的 offset 处读取 size 字节，超出末尾的部分补 0
+fn padded_slice(data: &[u8], offset: U256, size: usize) -> Vec<u8> {
+    let mut output = vec![0u8; size];
+    if offset < U256::from(data.len()) {
+        let start = offset.as_usize();
+        let end = start.saturating_add(size).min(data.len());
+        output[..end - start].copy_from_slice(&data[start..end]);
+    }
+    output
+}
+
+/// EIP-150：子调用最多只能获得当前可用 gas 的 63/64
+pub fn max_call_gas(available: u64) -> u64 {
+    available - available / 64
+}
+
+/// EIP-2200 / EIP-2929 的 SSTORE 净计量
+///
+/// 按交易开始时的原始值、当前值和新值返回 (gas 成本, 退款变化)，不含冷访问附加费：
+/// - 值不变或槽位已被本交易改过时只收热访问成本
+/// - 首次修改时原始值为 0 按 SET 计费，否则按扣除冷访问成本后的 RESET 计费
+/// - 改回原始值时退还首次修改多收的部分，清零与恢复非零值相应地增减清除退款
+fn sstore_net_cost<SPEC: Spec>(original: U256, current: U256, new: U256) -> (u64, i64) {
+    if current == new {
+        return (GAS_WARM_ACCESS, 0);
+    }
+
+    let reset = SPEC::GAS_SSTORE_RESET - GAS_COLD_SLOAD;
+    let clear = SPEC::GAS_SSTORE_CLEAR_REFUND;
+    if original == current {
+        let gas = if original.is_zero() {
+            SPEC::GAS_SSTORE_SET
+        } else {
+            reset
+        };
+        let refund = if !original.is_zero() && new.is_zero() {
+            clear
+        } else {
+            0
+        };
+        return (gas, refund);
+    }
+
+    let mut refund = 0;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= clear;
+        }
+        if new.is_zero() {
+            refund += clear;
+        }
+    }
+    if original == new {
+        let first_write = if original.is_zero() {
+            SPEC::GAS_SSTORE_SET
+        } else {
+            reset
+        };
+        refund += (first_write - GAS_WARM_ACCESS) as i64;
+    }
+    (GAS_WARM_ACCESS, refund)
+}
+
+/// 不执行代码，静态估算字节码的 gas 消耗
+///
+/// 按顺序反汇编整段代码（跳过 PUSH 的立即数），累加每条指令的最低成本。
+/// 结果是一个下界：不考虑跳转、动态成本和实际执行路径，
+/// 可用于在 `transact` 之前发现明显过低的 gas limit。
+pub fn static_gas_estimate<SPEC: Spec>(code: &[u8]) -> u64 {
+    let mut total = 0;
+    let mut pc = 0;
+    while let Some(&opcode) = code.get(pc) {
+        total += Opcode::from_u8(opcode).map_or(0, SPEC::opcode_gas);
+        pc += 1;
+        if (op::PUSH1..=op::PUSH32).contains(&opcode) {
+            pc += (opcode - op::PUSH1 + 1) as usize;
+        }
+    }
+    total
+}
+
+impl<SPEC: Spec, DB: Database> EVM<SPEC, DB> {
+    /// 在当前 machine 上解释执行字节码
+    ///
+    /// 从 pc = 0 开始逐条执行，直到遇到 STOP/RETURN/REVERT、
+    /// 执行到代码末尾（等同于 STOP），或者出现错误。
+    /// 返回 (是否成功, 输出数据)，REVERT 时成功标志为 false。
+    pub(crate) fn execute_bytecode(&mut self, code: &Bytecode) -> Result<(bool, Vec<u8>), Error> {
+        loop {
+            match self.execute_opcode(code) {
+                InstructionResult::Continue => {}
+                InstructionResult::Stop | InstructionResult::SelfDestruct => {
+                    return Ok((true, Vec::new()))
+                }
+                InstructionResult::Return(output) => return Ok((true, output)),
+                InstructionResult::Revert(output) => return Ok((false, output)),
+                InstructionResult::Fatal(error) => return Err(error),
+            }
+        }
+    }
+
+    /// 加载字节码，准备单步执行
+    ///
+    /// 重置机器状态并以 `address` 作为当前合约登记最外层调用帧，
+    /// 之后反复调用 `step()`，并可在两步之间通过 `machine()` 查看状态。
+    pub fn load_code(&mut self, address: Address, code: Vec<u8>, gas_limit: u64) {
+        self.machine = self.new_machine(gas_limit);
+        self.machine.self_address = address;
+        self.call_manager = CallManager::new(self.call_depth_limit);
+        self.warm_slots.clear();
+        self.warm_addresses.clear();
+        self.destructed.clear();
+        self.transient_storage.clear();
+        self.step_count = 0;
+
+        let frame = CallFrame::new_call(
+            Address::zero(),
+            address,
+            U256::zero(),
+            Vec::new(),
+            gas_limit,
+            CallType::Call,
+            0,
+        );
+        // 空调用栈上登记第一帧不会超过深度限制
+        let _ = self.call_manager.begin_call(frame);
+        self.loaded_code = Some(Bytecode::new(code));
+    }
+
+    /// 执行恰好一条指令
+    ///
+    /// 执行结束（STOP/RETURN/REVERT 或代码末尾）后 `should_continue` 为 false，
+    /// 之后继续调用会一直返回 STOP。出错时返回对应的 `Error`。
+    pub fn step(&mut self) -> Result<StepResult, Error> {
+        let code = self
+            .loaded_code
+            .take()
+            .unwrap_or_else(|| Bytecode::new(Vec::new()));
+        let opcode = code.bytes.get(self.machine.pc).copied().unwrap_or(op::STOP);
+        let gas_before = self.machine.gas;
+
+        let result = self.execute_opcode(&code);
+        self.loaded_code = Some(code);
+
+        let should_continue = match result {
+            InstructionResult::Fatal(error) => return Err(error),
+            result => result == InstructionResult::Continue,
+        };
+        Ok(StepResult {
+            opcode,
+            gas_used: gas_before - self.machine.gas,
+            should_continue,
+        })
+    }
+
+    /// 执行 pc 处的一条指令，并按操作码记录消耗的 gas
+    fn execute_opcode(&mut self, code: &Bytecode) -> InstructionResult {
+        // 达到上限后每一层调用帧的下一条指令都会失败，直到最外层
+        if self.step_count >= self.max_steps {
+            return InstructionResult::Fatal(Error::StepLimitExceeded);
+        }
+        self.step_count += 1;
+
+        let opcode = code.bytes.get(self.machine.pc).copied().unwrap_or(op::STOP);
+        let gas_before = self.machine.gas;
+        // 代码末尾的隐式 STOP 不是真实指令，不进行追踪
+        let traced = self.machine.pc < code.bytes.len();
+
+        if let Some(tracer) = self.tracer.as_mut().filter(|_| traced) {
+            let depth = self.call_manager.stack().depth();
+            let machine = &self.machine;
+            tracer.step(machine.pc, opcode, gas_before, machine.stack.data(), depth);
+        }
+
+        let result = self
+            .execute_instruction(code)
+            .unwrap_or_else(InstructionResult::Fatal);
+
+        // 子调用内部的消耗已经按各自的操作码合并进来了
+        let spent = gas_before.saturating_sub(self.machine.gas);
+        let nested = std::mem::take(&mut self.machine.nested_gas);
+        self.machine
+            .record_gas(opcode, spent.saturating_sub(nested));
+
+        if let Some(tracer) = self.tracer.as_mut().filter(|_| traced) {
+            tracer.step_end(spent, self.machine.stack.data());
+        }
+        result
+    }
+
+    /// 解码并执行 pc 处的一条指令
+    fn execute_instruction(&mut self, code: &Bytecode) -> Result<InstructionResult, Error> {
+        let machine = &mut self.machine;
+
+        // 超出代码末尾视为 STOP
+        let opcode = match code.bytes.get(machine.pc) {
+            Some(&opcode) => opcode,
+            None => return Ok(InstructionResult::Stop),
+        };
+        machine.pc += 1;
+
+        // 用户注册的处理函数优先于内置实现
+        if let Some(handler) = self.opcode_table.get(opcode) {
+            let frame = self.call_manager.stack().current_frame();
+            let mut context = ExecutionContext {
+                env: &self.env,
+                address: machine.self_address,
+                caller: frame.map(|f| f.caller).unwrap_or_default(),
+                value: frame.map(|f| f.value).unwrap_or_default(),
+                calldata: frame.map_or(&[][..], |f| &f.data),
+                depth: self.call_manager.stack().depth(),
+            };
+            handler(machine, &mut context)?;
+            return Ok(InstructionResult::Continue);
+        }
+
+        self.stack_height_check(opcode)?;
+        // 静态成本统一按规范扣除，指令只收取动态部分
+        if let Some(opcode) = Opcode::from_u8(opcode) {
+            self.machine.use_gas(SPEC::opcode_gas(opcode))?;
+        }
+        (Self::INSTRUCTIONS[opcode as usize].execute)(self, code)
+    }
+
+    /// 分发前检查栈高度，保证指令不会在执行到一半时才发现栈不足或溢出
+    fn stack_height_check(&self, opcode: u8) -> Result<(), Error> {
+        let instruction = &Self::INSTRUCTIONS[opcode as usize];
+        let stack = &self.machine.stack;
+        let remaining = stack
+            .len()
+            .checked_sub(instruction.pops as usize)
+            .ok_or(Error::StackUnderflow)?;
+        if remaining + instruction.pushes as usize > stack.limit() {
+            return Err(Error::StackOverflow);
+        }
+        Ok(())
+    }
+
+    // === 指令表 ===
+
+    /// 按操作码字节索引的指令表，每个 (SPEC, DB) 组合在编译期构建一次
+    const INSTRUCTIONS: [Instruction<SPEC, DB>; 256] = Self::instruction_table();
+
+    /// 构建指令表
+    ///
+    /// 未定义的操作码以及当前规范未启用的指令都指向 `op_invalid`。
+    const fn instruction_table() -> [Instruction<SPEC, DB>; 256] {
+        let mut table = [Instruction {
+            execute: Self::op_invalid,
+            pops: 0,
+            pushes: 0,
+        }; 256];
+
+        set(&mut table, op::STOP, Self::op_stop);
+
+        // 算术指令
+        set(&mut table, op::ADD, Self::op_add);
+        set(&mut table, op::MUL, Self::op_mul);
+        set(&mut table, op::SUB, Self::op_sub);
+        set(&mut table, op::DIV, Self::op_div);
+        set(&mut table, op::SDIV, Self::op_sdiv);
+        set(&mut table, op::MOD, Self::op_mod);
+        set(&mut table, op::SMOD, Self::op_smod);
+        set(&mut table, op::ADDMOD, Self::op_addmod);
+        set(&mut table, op::MULMOD, Self::op_mulmod);
+        set(&mut table, op::EXP, Self::op_exp);
+
+        // 比较与位运算指令
+        set(&mut table, op::LT, Self::op_lt);
+        set(&mut table, op::GT, Self::op_gt);
+        set(&mut table, op::SLT, Self::op_slt);
+        set(&mut table, op::SGT, Self::op_sgt);
+        set(&mut table, op::EQ, Self::op_eq);
+        set(&mut table, op::ISZERO, Self::op_iszero);
+        set(&mut table, op::AND, Self::op_and);
+        set(&mut table, op::OR, Self::op_or);
+        set(&mut table, op::XOR, Self::op_xor);
+        set(&mut table, op::NOT, Self::op_not);
+        set(&mut table, op::BYTE, Self::op_byte);
+        if SPEC::ENABLE_BITWISE_SHIFTING {
+            set(&mut table, op::SHL, Self::op_shl);
+            set(&mut table, op::SHR, Self::op_shr);
+            set(&mut table, op::SAR, Self::op_sar);
+        }
+
+        // 环境指令
+        set(&mut table, op::ADDRESS, Self::op_address);
+        set(&mut table, op::BALANCE, Self::op_balance);
+        set(&mut table, op::CALLER, Self::op_caller);
+        set(&mut table, op::CALLVALUE, Self::op_callvalue);
+        set(&mut table, op::CALLDATALOAD, Self::op_calldataload);
+        set(&mut table, op::CALLDATASIZE, Self::op_calldatasize);
+        set(&mut table, op::CALLDATACOPY, Self::op_calldatacopy);
+        set(&mut table, op::CODECOPY, Self::op_codecopy);
+        set(&mut table, op::EXTCODESIZE, Self::op_extcodesize);
+        set(&mut table, op::EXTCODECOPY, Self::op_extcodecopy);
+        set(&mut table, op::RETURNDATASIZE, Self::op_returndatasize);
+        set(&mut table, op::RETURNDATACOPY, Self::op_returndatacopy);
+        if SPEC::ENABLE_EXTCODEHASH {
+            set(&mut table, op::EXTCODEHASH, Self::op_extcodehash);
+        }
+
+        // 区块信息指令
+        set(&mut table, op::TIMESTAMP, Self::op_timestamp);
+        set(&mut table, op::NUMBER, Self::op_number);
+        set(&mut table, op::PREVRANDAO, Self::op_prevrandao);
+        set(&mut table, op::GASLIMIT, Self::op_gaslimit);
+        if SPEC::ENABLE_CHAINID {
+            set(&mut table, op::CHAINID, Self::op_chainid);
+        }
+        if SPEC::ENABLE_SELFBALANCE {
+            set(&mut table, op::SELFBALANCE, Self::op_selfbalance);
+        }
+        if SPEC::ENABLE_EIP1559 {
+            set(&mut table, op::BASEFEE, Self::op_basefee);
+        }
+
+        // 内存与存储指令
+        set(&mut table, op::MLOAD, Self::op_mload);
+        set(&mut table, op::MSTORE, Self::op_mstore);
+        set(&mut table, op::MSIZE, Self::op_msize);
+        set(&mut table, op::SLOAD, Self::op_sload);
+        set(&mut table, op::SSTORE, Self::op_sstore);
+        if SPEC::ENABLE_MCOPY {
+            set(&mut table, op::MCOPY, Self::op_mcopy);
+        }
+        if SPEC::ENABLE_TRANSIENT_STORAGE {
+            set(&mut table, op::TLOAD, Self::op_tload);
+            set(&mut table, op::TSTORE, Self::op_tstore);
+        }
+
+        // 栈与控制指令
+        set(&mut table, op::POP, Self::op_pop);
+        let mut opcode = op::PUSH1;
+        while opcode <= op::PUSH32 {
+            set(&mut table, opcode, Self::op_push);
+            opcode += 1;
+        }
+        set(&mut table, op::JUMP, Self::op_jump);
+        set(&mut table, op::JUMPI, Self::op_jumpi);
+        set(&mut table, op::PC, Self::op_pc);
+        set(&mut table, op::JUMPDEST, Self::op_jumpdest);
+        set(&mut table, op::GAS, Self::op_gas);
+        set(&mut table, op::RETURN, Self::op_return);
+        set(&mut table, op::REVERT, Self::op_revert);
+
+        // 日志、创建与调用指令
+        let mut opcode = op::LOG0;
+        while opcode <= op::LOG4 {
+            set(&mut table, opcode, Self::op_log);
+            opcode += 1;
+        }
+        set(&mut table, op::CREATE, Self::op_create);
+        if SPEC::ENABLE_CREATE2 {
+            set(&mut table, op::CREATE2, Self::op_create2);
+        }
+        set(&mut table, op::CALL, Self::op_call);
+        set(&mut table, op::CALLCODE, Self::op_callcode);
+        set(&mut table, op::DELEGATECALL, Self::op_delegatecall);
+        set(&mut table, op::STATICCALL, Self::op_staticcall);
+        set(&mut table, op::SELFDESTRUCT, Self::op_selfdestruct);
+        set(&mut table, op::INVALID, Self::op_invalid);
+
+        table
+    }
+
+    /// 弹出两个操作数 a、b（a 为栈顶），压入 f(a, b)
+    fn binary_op(
+        &mut self,
+        f: impl FnOnce(U256, U256) -> U256,
+    ) -> Result<InstructionResult, Error> {
+        let a = self.machine.pop()?;
+        let b = self.machine.pop()?;
+        self.machine.push(f(a, b))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// 压入一个值
+    fn push_value(&mut self, value: U256) -> Result<InstructionResult, Error> {
+        self.machine.push(value)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// 弹出复制类指令的 (dest_offset, offset, size)，并按复制的字数扣除 gas
+    fn pop_copy_args(&mut self) -> Result<(usize, U256, usize), Error> {
+        let dest_offset = as_usize(self.machine.pop()?)?;
+        let offset = self.machine.pop()?;
+        let size = as_usize(self.machine.pop()?)?;
+        self.machine
+            .use_gas(GAS_COPY * (size as u64).div_ceil(32))?;
+        Ok((dest_offset, offset, size))
+    }
+
+    fn op_stop(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        Ok(InstructionResult::Stop)
+    }
+
+    // === 算术指令 ===
+
+    fn op_add(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| a.overflowing_add(b).0)
+    }
+
+    fn op_mul(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| a.overflowing_mul(b).0)
+    }
+
+    fn op_sub(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| a.overflowing_sub(b).0)
+    }
+
+    fn op_div(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| if b.is_zero() { U256::zero() } else { a / b })
+    }
+
+    fn op_sdiv(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(arithmetic::sdiv)
+    }
+
+    fn op_mod(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| if b.is_zero() { U256::zero() } else { a % b })
+    }
+
+    fn op_smod(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(arithmetic::smod)
+    }
+
+    fn op_addmod(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let a = self.machine.pop()?;
+        let b = self.machine.pop()?;
+        let n = self.machine.pop()?;
+        self.machine.push(arithmetic::addmod(a, b, n))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_mulmod(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let a = self.machine.pop()?;
+        let b = self.machine.pop()?;
+        let n = self.machine.pop()?;
+        self.machine.push(arithmetic::mulmod(a, b, n))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_exp(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let base = self.machine.pop()?;
+        let exponent = self.machine.pop()?;
+        let exponent_bytes = exponent.bits().div_ceil(8) as u64;
+        self.machine.use_gas(SPEC::GAS_EXP_BYTE * exponent_bytes)?;
+        self.machine.push(arithmetic::exp(base, exponent))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    // === 比较与位运算指令 ===
+
+    fn op_lt(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| U256::from((a < b) as u8))
+    }
+
+    fn op_gt(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| U256::from((a > b) as u8))
+    }
+
+    fn op_slt(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| U256::from(arithmetic::slt(a, b) as u8))
+    }
+
+    fn op_sgt(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| U256::from(arithmetic::sgt(a, b) as u8))
+    }
+
+    fn op_eq(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| U256::from((a == b) as u8))
+    }
+
+    fn op_iszero(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let a = self.machine.pop()?;
+        self.machine.push(U256::from(a.is_zero() as u8))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_and(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| a & b)
+    }
+
+    fn op_or(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| a | b)
+    }
+
+    fn op_xor(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(|a, b| a ^ b)
+    }
+
+    fn op_not(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let a = self.machine.pop()?;
+        self.machine.push(!a)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_byte(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(arithmetic::byte)
+    }
+
+    fn op_shl(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(arithmetic::shl)
+    }
+
+    fn op_shr(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(arithmetic::shr)
+    }
+
+    fn op_sar(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.binary_op(arithmetic::sar)
+    }
+
+    // === 环境指令 ===
+
+    fn op_address(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(address_word(self.machine.self_address))
+    }
+
+    fn op_caller(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let caller = self.call_manager.stack().current_frame().map(|f| f.caller);
+        self.push_value(address_word(caller.unwrap_or_default()))
+    }
+
+    fn op_callvalue(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let value = self.call_manager.stack().current_frame().map(|f| f.value);
+        self.push_value(value.unwrap_or_default())
+    }
+
+    fn op_calldataload(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let offset = self.machine.pop()?;
+        let word = padded_slice(self.calldata(), offset, 32);
+        self.machine.push(U256::from_big_endian(&word))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_calldatasize(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let size = self.calldata().len();
+        self.push_value(U256::from(size))
+    }
+
+    fn op_calldatacopy(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let (dest_offset, offset, size) = self.pop_copy_args()?;
+        if size > 0 {
+            let data = padded_slice(self.calldata(), offset, size);
+            self.machine.memory_write(dest_offset, &data)?;
+        }
+        Ok(InstructionResult::Continue)
+    }
+
+    /// CODECOPY: 复制当前执行的代码，超出代码末尾的部分补 0
+    fn op_codecopy(&mut self, code: &Bytecode) -> Result<InstructionResult, Error> {
+        let (dest_offset, offset, size) = self.pop_copy_args()?;
+        if size > 0 {
+            let data = padded_slice(&code.bytes, offset, size);
+            self.machine.memory_write(dest_offset, &data)?;
+        }
+        Ok(InstructionResult::Continue)
+    }
+
+    /// RETURNDATASIZE: 最近一次子调用（或创建）的返回数据长度
+    fn op_returndatasize(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let size = self.machine.return_data.len();
+        self.push_value(U256::from(size))
+    }
+
+    /// RETURNDATACOPY: EIP-211 中读取超出返回数据末尾的部分是异常，而不是补零
+    fn op_returndatacopy(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let (dest_offset, offset, size) = self.pop_copy_args()?;
+        let machine = &mut self.machine;
+
+        let end = offset
+            .checked_add(U256::from(size))
+            .filter(|end| *end <= U256::from(machine.return_data.len()))
+            .ok_or(Error::ReturnDataOutOfBounds)?;
+        if size > 0 {
+            let data = machine.return_data[offset.as_usize()..end.as_usize()].to_vec();
+            machine.memory_write(dest_offset, &data)?;
+        }
+        Ok(InstructionResult::Continue)
+    }
+
+    // === 区块信息指令 ===
+
+    fn op_timestamp(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(self.env.block_timestamp)
+    }
+
+    fn op_number(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(self.env.block_number)
+    }
+
+    /// PREVRANDAO: 合并（The Merge）之后 DIFFICULTY 改为 PREVRANDAO，这里统一读取 block_difficulty
+    fn op_prevrandao(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(self.env.block_difficulty)
+    }
+
+    fn op_gaslimit(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(U256::from(self.env.block_gas_limit))
+    }
+
+    /// CHAINID: EIP-1344
+    fn op_chainid(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(self.env.chain_id)
+    }
+
+    /// BASEFEE: EIP-3198
+    fn op_basefee(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(self.env.base_fee)
+    }
+
+    // === 内存指令 ===
+
+    fn op_mload(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let offset = as_usize(self.machine.pop()?)?;
+        self.machine.expand_memory(offset, 32)?;
+        let word = self.machine.memory_read(offset, 32)?;
+        self.machine.push(U256::from_big_endian(&word))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_mstore(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let offset = as_usize(self.machine.pop()?)?;
+        let value = self.machine.pop()?;
+        let mut word = [0u8; 32];
+        value.to_big_endian(&mut word);
+        self.machine.memory_write(offset, &word)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// MSIZE: 当前内存大小，总是 32 的倍数
+    fn op_msize(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let size = self.machine.memory.len();
+        self.push_value(U256::from(size))
+    }
+
+    /// MCOPY: EIP-5656 内存内部复制，源和目标区域可以重叠
+    fn op_mcopy(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let machine = &mut self.machine;
+        let dest_offset = as_usize(machine.pop()?)?;
+        let offset = as_usize(machine.pop()?)?;
+        let size = as_usize(machine.pop()?)?;
+        machine.use_gas(GAS_COPY * (size as u64).div_ceil(32))?;
+        if size > 0 {
+            machine.expand_memory(offset.max(dest_offset), size)?;
+            machine
+                .memory
+                .copy_within(offset..offset + size, dest_offset);
+        }
+        Ok(InstructionResult::Continue)
+    }
+
+    // === 瞬时存储指令 ===
+
+    /// TLOAD: EIP-1153 瞬时存储按热访问收费，交易结束时清空
+    fn op_tload(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let key = (self.machine.self_address, self.machine.pop()?);
+        let value = self.transient_storage.get(&key).copied();
+        self.machine.push(value.unwrap_or_default())?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_tstore(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.call_manager.check_permissions("modify_state")?;
+        let key = (self.machine.self_address, self.machine.pop()?);
+        let value = self.machine.pop()?;
+        self.transient_storage.insert(key, value);
+        Ok(InstructionResult::Continue)
+    }
+
+    // === 栈指令 ===
+
+    /// PUSH1..PUSH32: 代码末尾不足的立即数按 0 补齐
+    fn op_push(&mut self, code: &Bytecode) -> Result<InstructionResult, Error> {
+        let machine = &mut self.machine;
+        let size = (code.bytes[machine.pc - 1] - op::PUSH1 + 1) as usize;
+        let start = machine.pc.min(code.bytes.len());
+        let end = (machine.pc + size).min(code.bytes.len());
+
+        let mut bytes = [0u8; 32];
+        bytes[32 - size..32 - size + (end - start)].copy_from_slice(&code.bytes[start..end]);
+        machine.push(U256::from_big_endian(&bytes[32 - size..]))?;
+        machine.pc += size;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// POP: 丢弃栈顶元素
+    fn op_pop(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.machine.pop()?;
+        Ok(InstructionResult::Continue)
+    }
+
+    // === 控制指令 ===
+
+    fn op_jump(&mut self, code: &Bytecode) -> Result<InstructionResult, Error> {
+        let dest = self.machine.pop()?;
+        self.machine.pc = jump_target(code, dest)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_jumpi(&mut self, code: &Bytecode) -> Result<InstructionResult, Error> {
+        let dest = self.machine.pop()?;
+        let condition = self.machine.pop()?;
+        if !condition.is_zero() {
+            self.machine.pc = jump_target(code, dest)?;
+        }
+        Ok(InstructionResult::Continue)
+    }
+
+    /// PC: 压入本条指令自身的位置（执行前 pc 已经越过操作码）
+    fn op_pc(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.push_value(U256::from(self.machine.pc - 1))
+    }
+
+    fn op_jumpdest(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        Ok(InstructionResult::Continue)
+    }
+
+    /// GAS: 压入扣除本指令成本之后的剩余 gas
+    fn op_gas(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.machine.push(U256::from(self.machine.gas))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_return(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let output = return_output(&mut self.machine, &self.limits)?;
+        Ok(InstructionResult::Return(output))
+    }
+
+    fn op_revert(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let output = return_output(&mut self.machine, &self.limits)?;
+        Ok(InstructionResult::Revert(output))
+    }
+
+    /// INVALID 以及未定义或当前规范未启用的指令
+    ///
+    /// 异常终止会耗尽剩余 gas（REVERT 则会退回）。
+    fn op_invalid(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.machine.gas = 0;
+        Err(Error::InvalidOpcode)
+    }
+
+    /// 当前调用帧的调用数据，没有调用帧时为空
+    fn calldata(&self) -> &[u8] {
+        self.call_manager
+            .stack()
+            .current_frame()
+            .map_or(&[], |frame| frame.data.as_slice())
+    }
+
+    /// 当前调用帧的存储上下文地址
+    fn current_address(&self) -> Address {
+        self.machine.self_address
+    }
+
+    /// 读取账户余额，账户不存在时为 0
+    pub(crate) fn balance(&mut self, address: Address) -> Result<U256, Error> {
+        if let Some(balance) = self.call_manager.pending_balance(address) {
+            return Ok(balance);
+        }
+        let account = self.database.basic(address).map_err(Error::database)?;
+        Ok(account.map_or(U256::zero(), |account| account.balance))
+    }
+
+    /// 读取账户 nonce，优先读取本次交易中尚未提交的更新，账户不存在时为 0
+    pub(crate) fn nonce(&mut self, address: Address) -> Result<u64, Error> {
+        if let Some(nonce) = self.call_manager.pending_nonce(address) {
+            return Ok(nonce);
+        }
+        let account = self.database.basic(address).map_err(Error::database)?;
+        Ok(account.map_or(0, |account| account.nonce))
+    }
+
+    /// 计算从 `from` 向 `to` 转账 `value` 所需的状态变更
+    ///
+    /// 余额不足时返回 `Error::InsufficientBalance`，接收方账户不存在时先创建。
+    /// 变更由调用方记录到合适的调用帧中，使其随调用失败一起回滚。
+    pub(crate) fn transfer_changes(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+    ) -> Result<Vec<StateChange>, Error> {
+        if value.is_zero() {
+            return Ok(Vec::new());
+        }
+        let from_balance = self.balance(from)?;
+        if from_balance < value {
+            return Err(Error::InsufficientBalance);
+        }
+        if from == to {
+            return Ok(Vec::new());
+        }
+
+        let mut changes = Vec::new();
+        let to_balance = match self.call_manager.pending_balance(to) {
+            Some(balance) => balance,
+            None => match self.database.basic(to).map_err(Error::database)? {
+                Some(account) => account.balance,
+                None => {
+                    changes.push(StateChange::CreateAccount {
+                        address: to,
+                        info: AccountInfo::default(),
+                    });
+                    U256::zero()
+                }
+            },
+        };
+        changes.push(StateChange::UpdateBalance {
+            address: from,
+            balance: from_balance - value,
+        });
+        changes.push(StateChange::UpdateBalance {
+            address: to,
+            balance: to_balance.saturating_add(value),
+        });
+        Ok(changes)
+    }
+
+    /// 访问外部账户在静态成本之外的冷访问附加费
+    ///
+    /// 静态成本在 EIP-2929 起为热访问成本，之前的规范为固定成本；
+    /// EIP-2929 起首次访问补足冷访问成本，并把账户加入热集合。
+    fn cold_account_surcharge(&mut self, address: Address) -> u64 {
+        if SPEC::ENABLE_EIP2929 && self.warm_addresses.insert(address) {
+            GAS_COLD_ACCOUNT_ACCESS - GAS_WARM_ACCESS
+        } else {
+            0
+        }
+    }
+
+    /// 弹出账户地址并扣除冷访问附加费
+    fn pop_accessed_address(&mut self) -> Result<Address, Error> {
+        let address = as_address(self.machine.pop()?);
+        let gas = self.cold_account_surcharge(address);
+        self.machine.use_gas(gas)?;
+        Ok(address)
+    }
+
+    /// 账户代码，不存在的账户为空
+    fn account_code(&mut self, address: Address) -> Result<Bytecode, Error> {
+        self.database.code(address).map_err(Error::database)
+    }
+
+    /// BALANCE: 任意账户的余额
+    fn op_balance(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let address = self.pop_accessed_address()?;
+        let balance = self.balance(address)?;
+        self.machine.push(balance)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// EXTCODESIZE: 任意账户的代码长度
+    fn op_extcodesize(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let address = self.pop_accessed_address()?;
+        let size = self.account_code(address)?.bytes.len();
+        self.machine.push(U256::from(size))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// EXTCODECOPY: 把任意账户的代码复制到内存，超出代码末尾的部分补零
+    fn op_extcodecopy(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let address = self.pop_accessed_address()?;
+        let (dest_offset, offset, size) = self.pop_copy_args()?;
+
+        if size > 0 {
+            let code = self.account_code(address)?;
+            let data = padded_slice(&code.bytes, offset, size);
+            self.machine.memory_write(dest_offset, &data)?;
+        }
+        Ok(InstructionResult::Continue)
+    }
+
+    /// EXTCODEHASH: 任意账户的代码哈希（EIP-1052）
+    ///
+    /// 不存在的账户返回 0；存在但没有代码的账户返回空字节串的哈希。
+    fn op_extcodehash(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let address = self.pop_accessed_address()?;
+        let account = self.database.basic(address).map_err(Error::database)?;
+
+        let hash = match account {
+            None => H256::zero(),
+            Some(account) if account.code_hash.is_zero() => keccak_hash::keccak([]),
+            Some(account) => account.code_hash,
+        };
+        self.machine.push(U256::from_big_endian(hash.as_bytes()))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// SELFBALANCE: 当前合约的余额
+    ///
+    /// 与 BALANCE 不同，读取自身余额没有冷账户访问成本，固定收取 5 gas。
+    fn op_selfbalance(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let balance = self.balance(self.current_address())?;
+        self.machine.push(balance)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// SELFDESTRUCT: 销毁当前合约
+    ///
+    /// 把全部余额转给受益人并删除账户，随后停止执行。
+    /// 退款由规范决定，London（EIP-3529）起不再有销毁退款。
+    fn op_selfdestruct(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        // 只读上下文中禁止销毁合约
+        self.call_manager.check_permissions("modify_state")?;
+
+        let beneficiary = as_address(self.machine.pop()?);
+        // EIP-2929：受益人是冷地址时额外收取冷访问成本（静态成本中不含热访问成本）
+        if self.cold_account_surcharge(beneficiary) > 0 {
+            self.machine.use_gas(GAS_COLD_ACCOUNT_ACCESS)?;
+        }
+        let address = self.current_address();
+        let balance = self.balance(address)?;
+
+        // 受益人是自己时余额随账户一起销毁
+        if beneficiary != address {
+            let beneficiary_balance = self.balance(beneficiary)?;
+            self.call_manager
+                .record_state_change(StateChange::UpdateBalance {
+                    address: beneficiary,
+                    balance: beneficiary_balance.saturating_add(balance),
+                });
+        }
+        self.call_manager
+            .record_state_change(StateChange::DeleteAccount { address });
+
+        // 同一合约在一笔交易中多次销毁只退款一次
+        if self.destructed.insert(address) {
+            self.machine.refund += SPEC::GAS_SELFDESTRUCT_REFUND;
+        }
+        Ok(InstructionResult::SelfDestruct)
+    }
+
+    /// 读取存储槽，优先读取本次交易中尚未提交的写入
+    fn storage_value(&mut self, address: Address, index: U256) -> Result<U256, Error> {
+        match self.call_manager.pending_storage(address, index) {
+            Some(value) => Ok(value),
+            None => self
+                .database
+                .storage(address, index)
+                .map_err(Error::database),
+        }
+    }
+
+    /// SLOAD: 读取当前合约的存储槽
+    fn op_sload(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        let index = self.machine.pop()?;
+        let address = self.current_address();
+        // EIP-2929：静态成本为热访问成本，首次访问的槽位补足冷访问成本
+        if self.warm_slots.insert((address, index)) && SPEC::ENABLE_EIP2929 {
+            self.machine.use_gas(GAS_COLD_SLOAD - GAS_WARM_ACCESS)?;
+        }
+        let value = self.storage_value(address, index)?;
+        if let Some(inspector) = self.inspector.as_mut() {
+            inspector.sload(address, index, value);
+        }
+        self.machine.push(value)?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// LOG0..LOG4: 以当前合约地址发出带 0 到 4 个主题的日志
+    ///
+    /// 日志随所在调用帧一起确认或回滚，只读上下文中禁止发出日志。
+    fn op_log(&mut self, code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.call_manager.check_permissions("emit_log")?;
+        let topic_count = (code.bytes[self.machine.pc - 1] - op::LOG0) as usize;
+
+        let (offset, size) = self.memory_region()?;
+        self.machine
+            .use_gas(GAS_LOG_DATA.saturating_mul(size as u64))?;
+        let mut topics = Vec::with_capacity(topic_count);
+        for _ in 0..topic_count {
+            let mut topic = H256::zero();
+            self.machine.pop()?.to_big_endian(topic.as_bytes_mut());
+            topics.push(topic);
+        }
+        let data = self.machine.memory_read(offset, size)?;
+
+        self.call_manager.add_log(Log {
+            address: self.current_address(),
+            topics,
+            data,
+        })?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// SSTORE: 写入当前合约的存储槽
+    fn op_sstore(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        // 只读上下文（STATICCALL）中禁止修改状态
+        self.call_manager.check_permissions("modify_state")?;
+
+        let index = self.machine.pop()?;
+        let value = self.machine.pop()?;
+        let address = self.current_address();
+
+        let current = self.storage_value(address, index)?;
+        let (mut gas, refund) = if SPEC::ENABLE_EIP2929 {
+            // EIP-2200：剩余 gas 不超过调用补贴时直接失败，补贴只够记日志，不能写存储。
+            // 静态成本已在分发之前扣除，这里按扣除前的剩余量判断
+            if self.machine.gas + SPEC::opcode_gas(Opcode::Sstore) <= SPEC::CALL_STIPEND {
+                return Err(Error::OutOfGas);
+            }
+            // 数据库中尚未包含本交易的写入，读到的就是原始值
+            let original = self
+                .database
+                .storage(address, index)
+                .map_err(Error::database)?;
+            sstore_net_cost::<SPEC>(original, current, value)
+        } else if current.is_zero() && !value.is_zero() {
+            // 零值写为非零值按 SET 计费，其余按 RESET 计费
+            (SPEC::GAS_SSTORE_SET, 0)
+        } else if !current.is_zero() && value.is_zero() {
+            (SPEC::GAS_SSTORE_RESET, SPEC::GAS_SSTORE_CLEAR_REFUND)
+        } else {
+            (SPEC::GAS_SSTORE_RESET, 0)
+        };
+        // EIP-2929：冷存储槽额外收费，之后该槽位变为热
+        if SPEC::ENABLE_EIP2929 && self.warm_slots.insert((address, index)) {
+            gas += GAS_COLD_SLOAD;
+        }
+        // 静态成本已在分发之前扣除
+        self.machine
+            .use_gas(gas - SPEC::opcode_gas(Opcode::Sstore))?;
+
+        // 退款在交易结束时统一封顶
+        self.machine.refund += refund;
+
+        if let Some(inspector) = self.inspector.as_mut() {
+            inspector.sstore(address, index, current, value);
+        }
+        self.call_manager
+            .record_state_change(StateChange::UpdateStorage {
+                address,
+                index,
+                value,
+            });
+        Ok(InstructionResult::Continue)
+    }
+
+    fn op_call(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.call_opcode(CallType::Call)
+    }
+
+    fn op_callcode(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.call_opcode(CallType::CallCode)
+    }
+
+    fn op_delegatecall(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.call_opcode(CallType::DelegateCall)
+    }
+
+    fn op_staticcall(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.call_opcode(CallType::StaticCall)
+    }
+
+    /// CALL / CALLCODE / DELEGATECALL / STATICCALL: 子调用
+    ///
+    /// 都按 63/64 规则转发 gas，只有 CALL 和 CALLCODE 的栈参数中带有 value，
+    /// value 非零时调用方多付 9000 gas，被调用方额外获得 `SPEC::CALL_STIPEND`：
+    /// - CALL 在目标合约自己的上下文中执行，只读上下文中不能携带 value
+    /// - CALLCODE 在调用方的存储上下文中执行目标代码，caller 为调用方自己
+    /// - STATICCALL 价值固定为 0，子帧及其内部的所有调用都禁止修改状态
+    /// - DELEGATECALL 在调用方的上下文中执行目标代码，
+    ///   保留调用方的 caller、value 和存储地址
+    fn call_opcode(&mut self, call_type: CallType) -> Result<InstructionResult, Error> {
+        let gas = self.machine.pop()?;
+        let to = as_address(self.machine.pop()?);
+        // EIP-2929：目标地址在深度检查之前按冷/热访问收费并加入热集合，调用失败也不会移出
+        let gas_cost = self.cold_account_surcharge(to);
+        self.machine.use_gas(gas_cost)?;
+        let value = match call_type {
+            CallType::Call | CallType::CallCode => self.machine.pop()?,
+            _ => U256::zero(),
+        };
+        // EIP-214：只读上下文中只禁止携带 value 的 CALL，CALLCODE 不转出余额
+        if call_type == CallType::Call && !value.is_zero() {
+            self.call_manager.check_permissions("modify_state")?;
+        }
+        let (args_offset, args_size) = self.memory_region()?;
+        let (ret_offset, ret_size) = self.memory_region()?;
+        let input = self.machine.memory_read(args_offset, args_size)?;
+        if !value.is_zero() {
+            self.machine.use_gas(GAS_CALL_VALUE)?;
+        }
+
+        // 转发的 gas 不超过可用 gas 的 63/64；
+        // 携带 value 时被调用方额外获得一笔补贴，这部分不由调用方支付
+        let gas_limit = gas.min(U256::from(max_call_gas(self.machine.gas))).as_u64();
+        self.machine.use_gas(gas_limit)?;
+        let gas_limit = if value.is_zero() {
+            gas_limit
+        } else {
+            gas_limit + SPEC::CALL_STIPEND
+        };
+
+        let depth = self.call_manager.stack().depth();
+        let frame = match call_type {
+            CallType::DelegateCall => {
+                let parent = self
+                    .call_manager
+                    .stack()
+                    .current_frame()
+                    .cloned()
+                    .ok_or(Error::InvalidOpcode)?;
+                CallFrame::new_delegate_call(
+                    parent.caller,
+                    to,
+                    parent.to_address,
+                    parent.value,
+                    input,
+                    gas_limit,
+                    depth,
+                )
+            }
+            CallType::CallCode => {
+                let current = self.current_address();
+                let mut frame = CallFrame::new_call(
+                    current,
+                    to,
+                    value,
+                    input,
+                    gas_limit,
+                    CallType::CallCode,
+                    depth,
+                );
+                frame.to_address = current;
+                frame
+            }
+            // CallType::StaticCall 会将子帧设为只读
+            _ => CallFrame::new_call(
+                self.current_address(),
+                to,
+                value,
+                input,
+                gas_limit,
+                call_type,
+                depth,
+            ),
+        };
+        let result = self.call_frame(frame)?;
+        self.machine.gas += result.gas_left();
+        let success = result.is_success();
+        let output = result.into_output();
+
+        // 只把返回数据中 ret_size 以内的部分写回内存
+        let copy_size = ret_size.min(output.len());
+        self.machine
+            .memory_write(ret_offset, &output[..copy_size])?;
+        self.machine.return_data = output;
+
+        self.machine.push(U256::from(success as u8))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// 弹出 (offset, size) 并扩展内存；size 为 0 时不扩展
+    fn memory_region(&mut self) -> Result<(usize, usize), Error> {
+        let offset = as_usize(self.machine.pop()?)?;
+        let size = as_usize(self.machine.pop()?)?;
+        if size == 0 {
+            return Ok((0, 0));
+        }
+        self.machine.expand_memory(offset, size)?;
+        Ok((offset, size))
+    }
+
+    /// 在新的调用帧中执行子调用
+    ///
+    /// 子调用失败不会中断父调用：异常终止会消耗掉转发的全部 gas，
+    /// 状态变更由 CallManager 回滚。
+    fn call_frame(&mut self, mut frame: CallFrame) -> Result<CallResult, Error> {
+        // 只读属性向下传递给所有嵌套调用
+        if self.call_manager.stack().is_in_static_context() {
+            frame.read_only = true;
+        }
+
+        let gas_limit = frame.gas_limit;
+
+        // 代码地址和存储地址不能混淆：只有 CALL/STATICCALL 切换到目标的存储
+        debug_assert!(
+            match frame.call_type {
+                CallType::CallCode | CallType::DelegateCall => {
+                    frame.to_address == self.current_address()
+                }
+                _ => frame.to_address == frame.code_address,
+            },
+            "调用帧的存储地址错误: {:?}",
+            frame.call_type
+        );
+
+        // DELEGATECALL 沿用父帧的 value，不发生转账；余额不足时调用失败并退回 gas
+        let value = match frame.call_type {
+            CallType::DelegateCall => U256::zero(),
+            _ => frame.value,
+        };
+        let transfer = match self.transfer_changes(frame.caller, frame.to_address, value) {
+            Err(Error::InsufficientBalance) => {
+                return Ok(CallResult::Halt {
+                    reason: Error::InsufficientBalance,
+                    gas_left: gas_limit,
+                })
+            }
+            result => result?,
+        };
+
+        // 预编译合约：成功返回剩余 gas，失败消耗全部 gas
+        if let Some(id) =
+            precompile_id(frame.code_address).filter(|id| SPEC::precompiles().contains(id))
+        {
+            return Ok(match self.precompiles.run(id, &frame.data, gas_limit) {
+                Ok((output, gas_used)) => {
+                    for change in transfer {
+                        self.call_manager.record_state_change(change);
+                    }
+                    CallResult::Success {
+                        output,
+                        gas_left: gas_limit - gas_used,
+                    }
+                }
+                Err(reason) => CallResult::Halt {
+                    reason,
+                    gas_left: 0,
+                },
+            });
+        }
+
+        let code = self
+            .database
+            .code(frame.code_address)
+            .map_err(Error::database)?;
+
+        // 超过调用深度限制：调用直接失败，转发的 gas 原样退回
+        if let Err(reason) = self.call_manager.begin_call(frame) {
+            return Ok(CallResult::Halt {
+                reason,
+                gas_left: gas_limit,
+            });
+        }
+        // 转账记录在子帧中，子调用失败时一并回滚
+        for change in transfer {
+            self.call_manager.record_state_change(change);
+        }
+
+        let self_address = self
+            .call_manager
+            .stack()
+            .current_frame()
+            .map_or(Address::zero(), |frame| frame.to_address);
+        let (result, child) = self.execute_in_child(&code, gas_limit, self_address);
+        let gas_left = child.gas;
+
+        match result {
+            Ok((true, output)) => {
+                // 只有成功的子帧才把退款并入父帧
+                self.machine.refund += child.refund;
+                self.call_manager.end_call(true, output.clone());
+                Ok(CallResult::Success { output, gas_left })
+            }
+            Ok((false, output)) => {
+                // REVERT：回滚状态变更，但退回剩余 gas
+                self.call_manager.end_call(false, output.clone());
+                Ok(CallResult::Revert { output, gas_left })
+            }
+            Err(reason) => {
+                self.call_manager.end_call(false, Vec::new());
+                Ok(CallResult::Halt {
+                    reason,
+                    gas_left: 0,
+                })
+            }
+        }
+    }
+
+    fn op_create(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.create_opcode(CallType::Create)
+    }
+
+    fn op_create2(&mut self, _code: &Bytecode) -> Result<InstructionResult, Error> {
+        self.create_opcode(CallType::Create2)
+    }
+
+    /// CREATE / CREATE2: 在合约内部创建新合约
+    ///
+    /// CREATE 的地址由创建者地址和 nonce 决定，CREATE2 (EIP-1014) 由创建者地址、
+    /// salt 和初始化代码的哈希决定，并额外按初始化代码的字数收取哈希成本。
+    /// 初始化代码最多获得可用 gas 的 63/64。创建成功时压入新合约地址，
+    /// 失败时压入 0；REVERT 或代码存储失败时子帧未用完的 gas 退回调用方，
+    /// 硬错误（如 INVALID、栈溢出）则耗尽转发的 gas。
+    fn create_opcode(&mut self, call_type: CallType) -> Result<InstructionResult, Error> {
+        // 只读上下文中禁止创建合约
+        self.call_manager.check_permissions("modify_state")?;
+
+        let value = self.machine.pop()?;
+        let (offset, size) = self.memory_region()?;
+        let salt = match call_type {
+            CallType::Create2 => {
+                let mut salt = H256::zero();
+                self.machine.pop()?.to_big_endian(salt.as_bytes_mut());
+                Some(salt)
+            }
+            _ => None,
+        };
+        if size > SPEC::MAX_INITCODE_SIZE {
+            return Err(Error::CodeTooLarge);
+        }
+        let words = (size as u64).div_ceil(32);
+        if SPEC::ENABLE_INITCODE_METERING {
+            self.machine.use_gas(words * GAS_INITCODE_WORD)?;
+        }
+        if salt.is_some() {
+            self.machine.use_gas(words * GAS_KECCAK256_WORD)?;
+        }
+        let init_code = self.machine.memory_read(offset, size)?;
+
+        let gas_limit = max_call_gas(self.machine.gas);
+        self.machine.use_gas(gas_limit)?;
+
+        let (address, result) = self.create_frame(value, init_code, gas_limit, salt)?;
+        self.machine.gas += result.gas_left();
+        self.machine.return_data = result.into_output();
+
+        self.machine
+            .push(address.map_or(U256::zero(), address_word))?;
+        Ok(InstructionResult::Continue)
+    }
+
+    /// 执行一个创建帧，`salt` 为 Some 时按 CREATE2 计算地址
+    ///
+    /// 返回新合约地址和创建帧的执行结果，创建失败时地址为 None。
+    /// 创建成功时结果中的返回数据为空。
+    /// 创建者的 nonce 在通过深度和余额检查后递增，即使创建本身失败也不回退。
+    fn create_frame(
+        &mut self,
+        value: U256,
+        init_code: Vec<u8>,
+        gas_limit: u64,
+        salt: Option<H256>,
+    ) -> Result<(Option<Address>, CallResult), Error> {
+        let creator = self.current_address();
+        let nonce = self.nonce(creator)?;
+        let (address, call_type) = match salt {
+            Some(salt) => {
+                let code_hash = keccak_hash::keccak(&init_code);
+                let address = self.calculate_create2_address(creator, salt, code_hash);
+                (address, CallType::Create2)
+            }
+            None => (
+                self.calculate_create_address(creator, nonce),
+                CallType::Create,
+            ),
+        };
+
+        // 超过调用深度限制或余额不足：创建直接失败，转发的 gas 原样退回
+        if self.call_manager.stack().depth() >= self.call_depth_limit {
+            let result = CallResult::Halt {
+                reason: Error::CallDepthExceeded,
+                gas_left: gas_limit,
+            };
+            return Ok((None, result));
+        }
+        let transfer = match self.transfer_changes(creator, address, value) {
+            Err(Error::InsufficientBalance) => {
+                let result = CallResult::Halt {
+                    reason: Error::InsufficientBalance,
+                    gas_left: gas_limit,
+                };
+                return Ok((None, result));
+            }
+            result => result?,
+        };
+
+        self.call_manager
+            .record_state_change(StateChange::UpdateNonce {
+                address: creator,
+                nonce: nonce + 1,
+            });
+        // EIP-2929：新合约地址在创建开始时变为热地址
+        if SPEC::ENABLE_EIP2929 {
+            self.warm_addresses.insert(address);
+        }
+
+        // 地址已有代码或 nonce（包括本次交易中刚创建的合约）：创建冲突，转发的 gas 全部消耗
+        let has_code = self
+            .database
+            .basic(address)
+            .map_err(Error::database)?
+            .is_some_and(|account| !account.code_hash.is_zero());
+        if has_code || self.nonce(address)? != 0 {
+            let result = CallResult::Halt {
+                reason: Error::CreateCollision,
+                gas_left: 0,
+            };
+            return Ok((None, result));
+        }
+
+        let depth = self.call_manager.stack().depth();
+        let mut frame = CallFrame::new_create(
+            creator,
+            value,
+            init_code.clone(),
+            gas_limit,
+            call_type,
+            depth,
+        );
+        frame.code_address = address;
+        frame.to_address = address;
+        self.call_manager.begin_call(frame)?;
+        // 转账记录在子帧中，创建失败时一并回滚
+        for change in transfer {
+            self.call_manager.record_state_change(change);
+        }
+
+        let init_code = Bytecode::new(init_code);
+        let (result, child) = self.execute_in_child(&init_code, gas_limit, address);
+        let gas_left = child.gas;
+
+        match result {
+            Ok((true, code)) => {
+                // 代码存储费用从子帧剩余的 gas 中扣除
+                let deposit = code.len() as u64 * SPEC::GAS_CODE_DEPOSIT;
+                let reason = if code.len() > SPEC::MAX_CODE_SIZE {
+                    Some(Error::CodeTooLarge)
+                } else if deposit > gas_left {
+                    Some(Error::OutOfGas)
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    self.call_manager.end_call(false, Vec::new());
+                    let result = CallResult::Halt {
+                        reason,
+                        gas_left: 0,
+                    };
+                    return Ok((None, result));
+                }
+
+                let balance = self.balance(address)?;
+                let code = Bytecode::new(code);
+                self.call_manager
+                    .record_state_change(StateChange::CreateAccount {
+                        address,
+                        info: AccountInfo {
+                            balance,
+                            nonce: 1,
+                            code_hash: code.hash,
+                            code: Some(code.bytes),
+                        },
+                    });
+                self.machine.refund += child.refund;
+                self.call_manager.end_call(true, Vec::new());
+                let result = CallResult::Success {
+                    output: Vec::new(),
+                    gas_left: gas_left - deposit,
+                };
+                Ok((Some(address), result))
+            }
+            Ok((false, output)) => {
+                // 初始化代码 REVERT：回滚状态变更，退回剩余 gas
+                self.call_manager.end_call(false, output.clone());
+                Ok((None, CallResult::Revert { output, gas_left }))
+            }
+            Err(reason) => {
+                self.call_manager.end_call(false, Vec::new());
+                let result = CallResult::Halt {
+                    reason,
+                    gas_left: 0,
+                };
+                Ok((None, result))
+            }
+        }
+    }
+
+    /// 换入一个新的 machine 执行子帧代码，结束后换回父帧的 machine
+    ///
+    /// 返回执行结果和执行完毕的子帧 machine（剩余 gas、退款等）。
+    /// 子帧失败时其中的瞬时存储写入一并撤销。
+    fn execute_in_child(
+        &mut self,
+        code: &Bytecode,
+        gas_limit: u64,
+        self_address: Address,
+    ) -> (Result<(bool, Vec<u8>), Error>, Machine) {
+        let mut machine = self.new_machine(gas_limit);
+        machine.self_address = self_address;
+        let parent = std::mem::replace(&mut self.machine, machine);
+        let transient_storage = self.transient_storage.clone();
+        let result = self.execute_bytecode(code);
+        let child = std::mem::replace(&mut self.machine, parent);
+        if !matches!(result, Ok((true, _))) {
+            self.transient_storage = transient_storage;
+        }
+
+        // 子帧的 gas 报告并入父帧
+        for (&opcode, &gas) in &child.gas_report() {
+            self.machine.record_gas(opcode, gas);
+            self.machine.nested_gas += gas;
+        }
+        (result, child)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{DatabaseCommit, InMemoryDB};
+    use crate::evm::{
+        check_gas_accounting, create_berlin_evm, create_frontier_evm, create_london_evm,
+        memory_gas, ExecutionLimits, Machine,
+    };
+    use crate::spec::{Berlin, London};
+    use crate::test_utils::{addr, call_tx, deploy};
+
+    /// 在一个新的 Berlin EVM 上执行字节码，返回执行后的机器状态
+    fn run(code: &[u8]) -> Result<Machine, Error> {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.machine = Machine::new(1_000_000);
+        evm.execute_bytecode(&Bytecode::new(code.to_vec()))?;
+        Ok(evm.machine)
+    }
+
+    /// PUSH32 指令
+    fn push32(value: U256) -> Vec<u8> {
+        let mut code = vec![op::PUSH32];
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        code.extend_from_slice(&bytes);
+        code
+    }
+
+    #[test]
+    fn test_sdiv_opcode() {
+        // -10 / 3 = -3：先压入除数，再压入被除数
+        let minus_ten = arithmetic::twos_complement(U256::from(10));
+        let mut code = vec![0x60, 0x03];
+        code.extend(push32(minus_ten));
+        code.push(op::SDIV);
+
+        let machine = run(&code).unwrap();
+        assert_eq!(
+            machine.stack.data(),
+            vec![arithmetic::twos_complement(U256::from(3))]
+        );
+    }
+
+    #[test]
+    fn test_sdiv_int_min_by_minus_one() {
+        let mut code = push32(U256::MAX);
+        code.extend(push32(arithmetic::I256_MIN));
+        code.push(op::SDIV);
+
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), vec![arithmetic::I256_MIN]);
+    }
+
+    #[test]
+    fn test_smod_and_sar_opcodes() {
+        // SMOD(-8, -3) = -2
+        let mut code = push32(arithmetic::twos_complement(U256::from(3)));
+        code.extend(push32(arithmetic::twos_complement(U256::from(8))));
+        code.push(op::SMOD);
+        // SAR(4, -16) = -1
+        code.extend(push32(arithmetic::twos_complement(U256::from(16))));
+        code.extend([0x60, 0x04, op::SAR]);
+
+        let machine = run(&code).unwrap();
+        assert_eq!(
+            machine.stack.data(),
+            vec![arithmetic::twos_complement(U256::from(2)), U256::MAX]
+        );
+    }
+
+    #[test]
+    fn test_comparison_opcodes() {
+        let minus_one = arithmetic::twos_complement(U256::one());
+        let compare = |opcode: u8, a: U256, b: U256| {
+            // 栈顶是 a
+            let mut code = push32(b);
+            code.extend(push32(a));
+            code.push(opcode);
+            run(&code).unwrap().stack.data()[0]
+        };
+
+        // -1 按无符号解释是最大值，按有符号解释小于 1
+        assert_eq!(compare(op::SLT, minus_one, U256::one()), U256::one());
+        assert_eq!(compare(op::LT, minus_one, U256::one()), U256::zero());
+        assert_eq!(compare(op::SGT, U256::one(), minus_one), U256::one());
+        assert_eq!(compare(op::GT, U256::one(), minus_one), U256::zero());
+        assert_eq!(compare(op::EQ, minus_one, U256::MAX), U256::one());
+        assert_eq!(compare(op::EQ, minus_one, U256::one()), U256::zero());
+    }
+
+    #[test]
+    fn test_bitwise_opcodes() {
+        // ISZERO(0), AND(0b1100, 0b1010), OR(0b1100, 0b1010), XOR(0b1100, 0b1010), NOT(0)
+        let code = assemble(
+            "
+            PUSH1 0 ISZERO
+            PUSH1 0x0a PUSH1 0x0c AND
+            PUSH1 0x0a PUSH1 0x0c OR
+            PUSH1 0x0a PUSH1 0x0c XOR
+            PUSH1 0 NOT
+            ",
+        )
+        .unwrap();
+
+        let machine = run(&code).unwrap();
+        assert_eq!(
+            machine.stack.data(),
+            vec![
+                U256::one(),
+                U256::from(0b1000),
+                U256::from(0b1110),
+                U256::from(0b0110),
+                U256::MAX,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_byte_and_shift_opcodes() {
+        let value = U256::from_big_endian(&(1..=32).collect::<Vec<u8>>());
+        // BYTE(31, value)：最低位字节
+        let mut code = push32(value);
+        code.extend([0x60, 0x1f, op::BYTE]);
+        // SHL(256, 1) = 0
+        code.extend([0x60, 0x01, 0x61, 0x01, 0x00, op::SHL]);
+        // SHR(1, 6) = 3
+        code.extend([0x60, 0x06, 0x60, 0x01, op::SHR]);
+
+        let machine = run(&code).unwrap();
+        assert_eq!(
+            machine.stack.data(),
+            vec![U256::from(32), U256::zero(), U256::from(3)]
+        );
+        assert_eq!(1_000_000 - machine.gas, 6 * 3 + 3 * 3);
+    }
+
+    #[test]
+    fn test_exp_gas_scales_with_exponent_size() {
+        // 2 ** 256：指数占 2 字节
+        let code = [0x61, 0x01, 0x00, 0x60, 0x02, op::EXP];
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), vec![U256::zero()]);
+        let two_byte_gas = 1_000_000 - machine.gas;
+
+        // 2 ** 255：指数占 1 字节
+        let code = [0x60, 0xff, 0x60, 0x02, op::EXP];
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), vec![arithmetic::I256_MIN]);
+        let one_byte_gas = 1_000_000 - machine.gas;
+
+        // 两次 PUSH 各 3 gas，EXP 为 10 + 50 * 字节数
+        assert_eq!(one_byte_gas, 6 + 10 + 50);
+        assert_eq!(two_byte_gas, 6 + 10 + 100);
+    }
+
+    #[test]
+    fn test_static_gas_follows_spec_opcode_gas() {
+        // PUSH1 2; PUSH1 3; MUL; PUSH1 1; ADD：每一步只扣除规范给出的静态成本
+        let code = vec![0x60, 0x02, 0x60, 0x03, op::MUL, 0x60, 0x01, op::ADD];
+        let mut evm = create_london_evm(InMemoryDB::new());
+        evm.load_code(addr(0xaa), code.clone(), 1_000);
+
+        for opcode in [
+            Opcode::Push1,
+            Opcode::Push1,
+            Opcode::Mul,
+            Opcode::Push1,
+            Opcode::Add,
+        ] {
+            let gas_before = evm.machine().gas;
+            evm.step().unwrap();
+            assert_eq!(gas_before - evm.machine().gas, London::opcode_gas(opcode));
+        }
+        assert_eq!(evm.machine().stack.data(), vec![U256::from(7)]);
+    }
+
+    #[test]
+    fn test_division_by_zero_yields_zero() {
+        // SDIV(5, 0) 和 DIV(5, 0)
+        let code = [
+            0x60,
+            0x00,
+            0x60,
+            0x05,
+            op::SDIV,
+            0x60,
+            0x00,
+            0x60,
+            0x05,
+            op::DIV,
+        ];
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), vec![U256::zero(), U256::zero()]);
+    }
+
+    #[test]
+    fn test_mulmod_opcode_does_not_wrap() {
+        // MULMOD(MAX, MAX, 12)：先压入模数，最后压入 a
+        let mut code = vec![0x60, 0x0c];
+        code.extend(push32(U256::MAX));
+        code.extend(push32(U256::MAX));
+        code.push(op::MULMOD);
+        // ADDMOD(MAX, 2, 3)
+        code.extend([0x60, 0x03, 0x60, 0x02]);
+        code.extend(push32(U256::MAX));
+        code.push(op::ADDMOD);
+
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), vec![U256::from(9), U256::from(2)]);
+    }
+
+    /// 执行一笔调用 `to` 的交易
+    fn call(db: InMemoryDB, to: Address) -> ExecutionResult {
+        call_with(db, to, U256::zero(), Vec::new())
+    }
+
+    /// 携带 value 和调用数据执行一笔调用 `to` 的交易
+    fn call_with(db: InMemoryDB, to: Address, value: U256, data: Vec<u8>) -> ExecutionResult {
+        let mut evm = create_berlin_evm(db);
+        evm.transact(Transaction {
+            value,
+            data,
+            ..call_tx(to)
+        })
+        .unwrap()
+    }
+
+    /// STATICCALL(gas, target, 0, 0, 0, 0)，并把结果作为 32 字节返回
+    fn staticcall_and_return(target: Address, gas: u16) -> Vec<u8> {
+        let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+        code.extend_from_slice(target.as_bytes());
+        code.push(0x61);
+        code.extend_from_slice(&gas.to_be_bytes());
+        code.push(op::STATICCALL);
+        code.extend([0x60, 0x00, op::MSTORE, 0x60, 0x20, 0x60, 0x00, op::RETURN]);
+        code
+    }
+
+    #[test]
+    fn test_revert_keeps_reason_and_unused_gas() {
+        let contract = addr(0xaa);
+        // MSTORE(0, 0x2a); REVERT(0, 32)
+        let code = vec![
+            0x60,
+            0x2a,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::REVERT,
+        ];
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code);
+
+        let result = call(db, contract);
+
+        assert!(!result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::from(0x2a));
+        // 未用完的 gas 退回给交易发送者
+        assert!(result.gas_used < GAS_TX_BASE + 1_000);
+    }
+
+    #[test]
+    fn test_intrinsic_gas_is_charged_before_execution() {
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        // PUSH1 1 STOP
+        deploy(&mut db, contract, vec![0x60, 0x01, op::STOP]);
+        let data = vec![0x00, 0x01, 0x02, 0x03];
+        let tx = Transaction {
+            data: data.clone(),
+            ..call_tx(contract)
+        };
+
+        let result = create_berlin_evm(db.clone()).transact(tx.clone()).unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + 4 + 3 * 16 + 3);
+
+        // gas_limit 低于固有成本的交易无效
+        let too_low = Transaction {
+            gas_limit: GAS_TX_BASE + 4 + 3 * 16 - 1,
+            ..tx
+        };
+        assert_eq!(
+            create_berlin_evm(db).transact(too_low).unwrap_err(),
+            Error::IntrinsicGasTooLow
+        );
+    }
+
+    #[test]
+    fn test_plain_transfer_costs_only_base_gas() {
+        let tx = Transaction {
+            gas_limit: GAS_TX_BASE,
+            ..call_tx(addr(2))
+        };
+        let result = create_london_evm(InMemoryDB::new())
+            .transact(tx.clone())
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.gas_used, GAS_TX_BASE);
+
+        // 混合字节的调用数据：Frontier 的非零字节按 68 gas 计费
+        let tx = Transaction {
+            data: vec![0x00, 0xff, 0x00],
+            gas_limit: 100_000,
+            ..tx
+        };
+        let result = create_frontier_evm(InMemoryDB::new()).transact(tx).unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + 2 * 4 + 68);
+    }
+
+    #[test]
+    fn test_sequential_creates_use_sender_nonce() {
+        let caller = addr(1);
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            caller,
+            AccountInfo {
+                nonce: 3,
+                ..Default::default()
+            },
+        );
+        let mut evm = create_berlin_evm(db);
+        let create = |nonce| Transaction {
+            caller,
+            data: vec![0x60, 0x00],
+            gas_limit: 1_000_000,
+            nonce,
+            ..Default::default()
+        };
+
+        let mut addresses = Vec::new();
+        for nonce in [Some(3), None] {
+            let result = evm.transact(create(nonce)).unwrap();
+            assert!(result.success);
+            addresses.push(Address::from_slice(&result.return_data));
+            let changes = evm.call_manager.take_committed_changes();
+            evm.database_mut().commit(changes).unwrap();
+        }
+
+        assert_ne!(addresses[0], addresses[1]);
+        assert_eq!(addresses[0], evm.calculate_create_address(caller, 3));
+        assert_eq!(evm.database_mut().basic(caller).unwrap().unwrap().nonce, 5);
+
+        // nonce 不匹配的交易被拒绝，账户 nonce 不变
+        assert_eq!(
+            evm.transact(create(Some(4))).unwrap_err(),
+            Error::NonceMismatch {
+                expected: 5,
+                got: 4
+            }
+        );
+        assert!(evm.call_manager.take_committed_changes().is_empty());
+    }
+
+    #[test]
+    fn test_return_larger_than_limit_fails() {
+        // RETURN(0, 0x1000)
+        let code = vec![0x61, 0x10, 0x00, 0x60, 0x00, op::RETURN];
+        let limits = ExecutionLimits {
+            max_return_size: Some(1024),
+        };
+
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.set_limits(limits);
+        evm.load_code(addr(0xaa), code.clone(), 50_000);
+        evm.step().unwrap();
+        evm.step().unwrap();
+        assert_eq!(evm.step(), Err(Error::OutOfMemory));
+        // 超限时不会为返回数据分配内存
+        assert!(evm.machine().memory.is_empty());
+
+        // 不设限制时正常返回
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.load_code(addr(0xaa), code, 50_000);
+        evm.step().unwrap();
+        evm.step().unwrap();
+        assert!(evm.step().is_ok());
+        assert_eq!(evm.machine().memory.len(), 0x1000);
+    }
+
+    #[test]
+    fn test_zero_length_return_and_revert() {
+        // 偏移量远超内存，但 size 为 0 时不会被使用
+        for (opcode, success) in [(op::RETURN, true), (op::REVERT, false)] {
+            let code = vec![0x60, 0x00, 0x61, 0xff, 0xff, opcode];
+            let mut evm = create_berlin_evm(InMemoryDB::new());
+            evm.machine = Machine::new(1_000_000);
+            let (ok, output) = evm.execute_bytecode(&Bytecode::new(code)).unwrap();
+            assert_eq!(ok, success);
+            assert!(output.is_empty());
+            assert!(evm.machine.memory.is_empty());
+            assert_eq!(evm.machine.gas, 1_000_000 - 6);
+        }
+    }
+
+    #[test]
+    fn test_mstore_beyond_memory_limit_fails() {
+        use crate::evm::MEMORY_LIMIT;
+
+        // MSTORE(2^40, 1)：超出上限，在分配之前失败
+        let mut code = vec![0x60, 0x01, 0x65, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00];
+        code.push(op::MSTORE);
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.load_code(addr(0xaa), code, 50_000);
+        evm.step().unwrap();
+        evm.step().unwrap();
+        assert_eq!(evm.step(), Err(Error::OutOfMemory));
+        assert!(evm.machine().memory.is_empty());
+
+        // 偏移量加长度溢出
+        let mut machine = Machine::new(0);
+        assert_eq!(
+            machine.expand_memory(usize::MAX, 32),
+            Err(Error::OutOfMemory)
+        );
+        assert_eq!(
+            machine.expand_memory(MEMORY_LIMIT - 31, 32),
+            Err(Error::OutOfMemory)
+        );
+    }
+
+    #[test]
+    fn test_codecopy_and_msize() {
+        // CODECOPY(0, 0, 40); MSIZE
+        let code = [0x60, 0x28, 0x60, 0x00, 0x60, 0x00, op::CODECOPY, op::MSIZE];
+        let machine = run(&code).unwrap();
+
+        assert_eq!(&machine.memory[..code.len()], &code);
+        // 超出代码末尾的部分补 0，内存按 32 字节对齐
+        assert!(machine.memory[code.len()..].iter().all(|b| *b == 0));
+        assert_eq!(machine.stack.data(), [U256::from(64)]);
+    }
+
+    #[test]
+    fn test_mcopy_overlapping_regions() {
+        use crate::spec::Cancun;
+
+        let run_cancun = |mcopy: [u8; 6]| {
+            // MSTORE(0, 0x0102..20) 后执行 MCOPY
+            let word: Vec<u8> = (1..=32).collect();
+            let mut code = push32(U256::from_big_endian(&word));
+            code.extend([0x60, 0x00, op::MSTORE]);
+            code.extend(mcopy);
+            code.push(op::MCOPY);
+
+            let mut evm = EVM::<Cancun, _>::new(InMemoryDB::new(), Environment::default());
+            evm.machine = Machine::new(1_000_000);
+            evm.execute_bytecode(&Bytecode::new(code))
+                .map(|_| evm.machine)
+        };
+
+        // 向后复制：MCOPY(dest = 4, src = 0, size = 32)
+        let machine = run_cancun([0x60, 0x20, 0x60, 0x00, 0x60, 0x04]).unwrap();
+        let expected: Vec<u8> = (1..=4).chain(1..=32).collect();
+        assert_eq!(&machine.memory[..36], &expected[..]);
+        assert_eq!(machine.memory.len(), 64);
+        // PUSH32、PUSH1、MSTORE（含首字内存）和三次 PUSH1 共 21，
+        // MCOPY 为 3 + 3 * 1 字，再加扩展到第二个字的 3
+        let mcopy_gas = 3 + GAS_COPY + (memory_gas(2) - memory_gas(1));
+        assert_eq!(1_000_000 - machine.gas, 21 + mcopy_gas);
+
+        // 向前复制：MCOPY(dest = 0, src = 4, size = 28)
+        let machine = run_cancun([0x60, 0x1c, 0x60, 0x04, 0x60, 0x00]).unwrap();
+        let expected: Vec<u8> = (5..=32).chain(29..=32).collect();
+        assert_eq!(&machine.memory[..32], &expected[..]);
+
+        // Cancun 之前 MCOPY 是未定义指令
+        let code = [0x60, 0x01, 0x60, 0x00, 0x60, 0x00, op::MCOPY];
+        assert_eq!(run(&code).unwrap_err(), Error::InvalidOpcode);
+    }
+
+    #[test]
+    fn test_run_bytecode() {
+        use crate::evm::run_bytecode;
+
+        let code =
+            assemble("PUSH1 2 PUSH1 3 ADD PUSH1 0 MSTORE PUSH1 0x20 PUSH1 0 RETURN").unwrap();
+        let result = run_bytecode(&code, 100_000);
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::from(5));
+
+        // gas 低于固有成本时交易无效
+        let result = run_bytecode(&code, 20_000);
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 0);
+    }
+
+    #[test]
+    fn test_bytecode_runner_does_not_persist_state() {
+        use crate::evm::BytecodeRunner;
+
+        // SSTORE(0, 1)：状态不提交，每次执行都是首次写入
+        let mut runner = BytecodeRunner::new(&[0x60, 0x01, 0x60, 0x00, op::SSTORE]);
+        let first = runner.run(100_000);
+        let second = runner.run(100_000);
+        assert!(first.success && second.success);
+        assert_eq!(first.gas_used, GAS_TX_BASE + 6 + 20000 + 2100);
+        assert_eq!(second.gas_used, first.gas_used);
+    }
+
+    #[test]
+    fn test_database_error_message_is_preserved() {
+        use std::io;
+
+        /// 读取账户总是失败的数据库后端
+        struct FailingDB;
+
+        impl Database for FailingDB {
+            type Error = io::Error;
+
+            fn basic(&mut self, _address: Address) -> Result<Option<AccountInfo>, io::Error> {
+                Err(io::Error::other(
+                    "read /var/lib/evm/accounts: disk I/O error",
+                ))
+            }
+
+            fn code_by_hash(&mut self, _code_hash: H256) -> Result<Bytecode, io::Error> {
+                Ok(Bytecode::new(Vec::new()))
+            }
+
+            fn storage(&mut self, _address: Address, _index: U256) -> Result<U256, io::Error> {
+                Ok(U256::zero())
+            }
+        }
+
+        let mut evm = EVM::<London, _>::new(FailingDB, Environment::default());
+        let error = evm
+            .transact(Transaction {
+                gas_limit: 100_000,
+                ..call_tx(addr(0xaa))
+            })
+            .unwrap_err();
+
+        match &error {
+            Error::Database(message) => {
+                assert!(message.contains("read /var/lib/evm/accounts: disk I/O error"))
+            }
+            other => panic!("应当是数据库错误: {:?}", other),
+        }
+        assert!(error.to_string().starts_with("Database error: "));
+    }
+
+    #[test]
+    fn test_undefined_opcode_is_invalid() {
+        use crate::evm::run_bytecode;
+
+        // 0x0c 在任何规范中都没有定义
+        assert_eq!(run(&[0x0c]).unwrap_err(), Error::InvalidOpcode);
+        let result = run_bytecode(&[op::PUSH1, 1, 0x0c], 50_000);
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 50_000);
+
+        // 当前规范未启用的指令同样无效：Berlin 中没有 BASEFEE
+        assert_eq!(run(&[op::BASEFEE]).unwrap_err(), Error::InvalidOpcode);
+    }
+
+    #[test]
+    fn test_random_bytecode_never_panics() {
+        use crate::evm::run_bytecode;
+
+        // xorshift64，固定种子保证结果可复现
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for round in 0..3000 {
+            let len = (next() % 96) as usize;
+            // 四分之一的字节取 0xff，让 PUSH 的立即数更容易成为超大的偏移或长度
+            let code: Vec<u8> = (0..len)
+                .map(|_| match next() {
+                    r if r % 4 == 0 => 0xff,
+                    r => (r >> 8) as u8,
+                })
+                .collect();
+
+            let result = std::panic::catch_unwind(|| run_bytecode(&code, 1_000_000));
+            assert!(
+                result.is_ok(),
+                "第 {} 轮 panic，代码 {}",
+                round,
+                hex::encode(&code)
+            );
+        }
+
+        // 偏移与长度相加溢出时返回错误而不是 panic
+        let mut machine = Machine::new(1_000_000);
+        assert_eq!(machine.memory_read(usize::MAX, 2), Err(Error::OutOfMemory));
+        assert_eq!(
+            machine.memory_write(usize::MAX, &[1]),
+            Err(Error::OutOfMemory)
+        );
+        assert_eq!(
+            padded_slice(&[1, 2], U256::one(), usize::MAX >> 40).len(),
+            usize::MAX >> 40
+        );
+    }
+
+    #[test]
+    fn test_transient_storage_is_cleared_between_transactions() {
+        use crate::spec::Cancun;
+
+        let contract = addr(0xaa);
+        // 返回 [交易开始时的 TLOAD(0), TSTORE(0, 0x2a) 之后的 TLOAD(0)]
+        let code = assemble(
+            "
+            PUSH1 0 TLOAD PUSH1 0 MSTORE
+            PUSH1 0x2a PUSH1 0 TSTORE
+            PUSH1 0 TLOAD PUSH1 0x20 MSTORE
+            PUSH1 0x40 PUSH1 0 RETURN
+            ",
+        )
+        .unwrap();
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code.clone());
+
+        let mut evm = EVM::<Cancun, _>::new(db, Environment::default());
+        for _ in 0..2 {
+            let result = evm
+                .transact(Transaction {
+                    gas_limit: 100_000,
+                    ..call_tx(contract)
+                })
+                .unwrap();
+            assert!(result.success);
+            let words: Vec<U256> = result
+                .return_data
+                .chunks(32)
+                .map(U256::from_big_endian)
+                .collect();
+            assert_eq!(words, [U256::zero(), U256::from(0x2a)]);
+        }
+
+        // Cancun 之前 TLOAD/TSTORE 是未定义指令
+        assert_eq!(run(&code).unwrap_err(), Error::InvalidOpcode);
+    }
+
+    #[test]
+    fn test_infinite_loop_stops_at_step_limit() {
+        let contract = addr(0xaa);
+        let code = assemble("start: @start JUMP").unwrap();
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code.clone());
+
+        // gas 足够循环很久，只有步数上限能让它停下
+        let mut evm = create_berlin_evm(db);
+        assert_eq!(evm.max_steps(), u64::MAX);
+        evm.set_max_steps(1000);
+        let result = evm.transact(Transaction {
+            gas_limit: u64::MAX / 2,
+            ..call_tx(contract)
+        });
+        assert_eq!(result.unwrap_err(), Error::StepLimitExceeded);
+        assert_eq!(evm.step_count(), 1000);
+
+        // 单步执行同样受限，load_code 会重置计数
+        evm.set_max_steps(3);
+        evm.load_code(contract, code, 1_000_000);
+        for _ in 0..3 {
+            assert!(evm.step().unwrap().should_continue);
+        }
+        assert_eq!(evm.step().unwrap_err(), Error::StepLimitExceeded);
+    }
+
+    #[test]
+    fn test_memory_expansion_gas_and_limit_boundary() {
+        // 线性部分 3/字，超过 512 个字后二次方部分开始占主导
+        assert_eq!(memory_gas(1), 3);
+        assert_eq!(memory_gas(32), 96 + 2);
+        assert_eq!(memory_gas(1024), 3072 + 2048);
+
+        let mut machine = Machine::new(1_000_000);
+        machine.memory_limit = 1024;
+
+        // 恰好到达上限：成功并按差值收费
+        machine.expand_memory(0, 32).unwrap();
+        assert_eq!(1_000_000 - machine.gas, 3);
+        machine.expand_memory(1000, 24).unwrap();
+        assert_eq!(machine.memory.len(), 1024);
+        assert_eq!(1_000_000 - machine.gas, memory_gas(32));
+
+        // 已扩展的区域不再收费
+        machine.expand_memory(0, 1024).unwrap();
+        assert_eq!(1_000_000 - machine.gas, memory_gas(32));
+
+        // 超过上限 1 字节
+        assert_eq!(machine.expand_memory(1000, 25), Err(Error::OutOfMemory));
+        assert_eq!(machine.memory.len(), 1024);
+
+        // gas 不足时不分配内存
+        let mut machine = Machine::new(5);
+        assert_eq!(machine.expand_memory(0, 64), Err(Error::OutOfGas));
+        assert!(machine.memory.is_empty());
+    }
+
+    #[test]
+    fn test_call_function_dispatches_on_selector() {
+        const SELECTOR: [u8; 4] = [0x12, 0x34, 0x56, 0x78];
+
+        // 取调用数据的前 4 字节作为选择器
+        let mut code = vec![0x7c, 0x01];
+        code.extend([0u8; 28]);
+        code.extend([0x60, 0x00, op::CALLDATALOAD, op::DIV]);
+        // 选择器不匹配时跳转到 0x39 处 REVERT
+        code.push(0x63);
+        code.extend(SELECTOR);
+        code.extend([op::SUB, 0x60, 0x39, op::JUMPI]);
+        // 返回第一个参数 + 1
+        code.extend([0x60, 0x01, 0x60, 0x04, op::CALLDATALOAD, op::ADD]);
+        code.extend([0x60, 0x00, op::MSTORE, 0x60, 0x20, 0x60, 0x00, op::RETURN]);
+        assert_eq!(code.len(), 0x39);
+        code.extend([op::JUMPDEST, 0x60, 0x00, 0x60, 0x00, op::REVERT]);
+
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code);
+        let mut evm = create_berlin_evm(db);
+
+        let mut arg = [0u8; 32];
+        U256::from(41).to_big_endian(&mut arg);
+        let output = evm
+            .call_function(contract, SELECTOR, arg.to_vec(), 100_000)
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&output), U256::from(42));
+
+        assert_eq!(
+            evm.call_function(contract, [0xde, 0xad, 0xbe, 0xef], arg.to_vec(), 100_000),
+            Err(Error::Reverted(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_and_undefined_opcodes_burn_gas() {
+        for opcode in [op::INVALID, 0x0c, 0xef] {
+            let mut evm = create_berlin_evm(InMemoryDB::new());
+            evm.load_code(addr(0xaa), vec![0x60, 0x01, opcode], 50_000);
+
+            evm.step().unwrap();
+            assert_eq!(evm.step(), Err(Error::InvalidOpcode));
+            assert_eq!(evm.machine().gas, 0);
+        }
+    }
+
+    #[test]
+    fn test_revert_rolls_back_storage() {
+        let contract = addr(0xaa);
+        // SSTORE(0, 1); MSTORE(0, 0x2a); 结尾为 STOP 或 REVERT(0, 32)
+        let store_then = |last: &[u8]| {
+            let mut code = vec![0x60, 0x01, 0x60, 0x00, op::SSTORE];
+            code.extend([0x60, 0x2a, 0x60, 0x00, op::MSTORE]);
+            code.extend_from_slice(last);
+            code
+        };
+        let transact = |code: Vec<u8>| {
+            let mut db = InMemoryDB::new();
+            deploy(&mut db, contract, code);
+            let mut evm = create_berlin_evm(db);
+            let result = evm.transact(call_tx(contract)).unwrap();
+            let changes = evm.call_manager.take_committed_changes();
+            (result, changes)
+        };
+
+        // 发送者 nonce 的递增不受执行结果影响
+        let nonce_bump = StateChange::UpdateNonce {
+            address: addr(1),
+            nonce: 1,
+        };
+
+        // 正常结束时写入被提交
+        let (result, changes) = transact(store_then(&[op::STOP]));
+        assert!(result.success);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0], nonce_bump);
+        assert!(matches!(changes[1], StateChange::UpdateStorage { .. }));
+
+        // REVERT 回滚写入，但 revert 数据仍返回给调用方
+        let (result, changes) = transact(store_then(&[0x60, 0x20, 0x60, 0x00, op::REVERT]));
+        assert!(!result.success);
+        assert_eq!(changes, vec![nonce_bump]);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::from(0x2a));
+    }
+
+    #[test]
+    fn test_invalid_opcode_consumes_all_gas() {
+        let contract = addr(0xaa);
+        // MSTORE(0, 0x2a); INVALID
+        let code = vec![0x60, 0x2a, 0x60, 0x00, op::MSTORE, op::INVALID];
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code);
+
+        let result = call(db, contract);
+
+        assert!(!result.success);
+        assert!(result.return_data.is_empty());
+        assert_eq!(result.gas_used, 1_000_000);
+    }
+
+    #[test]
+    fn test_staticcall_sstore_fails_but_parent_continues() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, staticcall_and_return(child, 0xffff));
+        // 子合约尝试 SSTORE(0, 1)
+        deploy(
+            &mut db,
+            child,
+            vec![0x60, 0x01, 0x60, 0x00, op::SSTORE, op::STOP],
+        );
+
+        let result = call(db, parent);
+
+        // 父合约继续执行并返回 STATICCALL 的结果 0
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+        // 异常终止的子调用消耗了转发的全部 gas
+        assert!(result.gas_used > 0xffff);
+    }
+
+    #[test]
+    fn test_call_return_data() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        // CALL(0xffff, child, 0, 0, 0, 0, 32)，返回 [子调用输出, success, RETURNDATASIZE]
+        let mut code = vec![
+            0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+        ];
+        code.extend_from_slice(child.as_bytes());
+        code.extend([0x61, 0xff, 0xff, op::CALL]);
+        code.extend([0x60, 0x20, op::MSTORE]);
+        code.extend([op::RETURNDATASIZE, 0x60, 0x40, op::MSTORE]);
+        code.extend([0x60, 0x60, 0x60, 0x00, op::RETURN]);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, code);
+        // 子合约：MSTORE(0, 0x2a); RETURN(0, 32)
+        deploy(
+            &mut db,
+            child,
+            vec![
+                0x60,
+                0x2a,
+                0x60,
+                0x00,
+                op::MSTORE,
+                0x60,
+                0x20,
+                0x60,
+                0x00,
+                op::RETURN,
+            ],
+        );
+
+        let result = call(db, parent);
+
+        assert!(result.success);
+        let words: Vec<U256> = result
+            .return_data
+            .chunks(32)
+            .map(U256::from_big_endian)
+            .collect();
+        assert_eq!(words, [0x2a, 1, 32].map(U256::from));
+    }
+
+    #[test]
+    fn test_call_output_truncated_to_ret_size() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        // MSTORE(0x20, 0x77) 作为哨兵；CALL(0xffff, child, 0, 0, 0, 0, 32)
+        let mut code = vec![0x60, 0x77, 0x60, 0x20, op::MSTORE];
+        code.extend([
+            0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+        ]);
+        code.extend_from_slice(child.as_bytes());
+        code.extend([0x61, 0xff, 0xff, op::CALL]);
+        // MSTORE(0x40, RETURNDATASIZE); RETURNDATACOPY(0x60, 0x20, 0x20); RETURN(0, 0x80)
+        code.extend([op::RETURNDATASIZE, 0x60, 0x40, op::MSTORE]);
+        code.extend([0x60, 0x20, 0x60, 0x20, 0x60, 0x60, op::RETURNDATACOPY]);
+        code.extend([0x60, 0x80, 0x60, 0x00, op::RETURN]);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, code);
+        // 子合约：MSTORE(0, 0x11); MSTORE(0x20, 0x22); RETURN(0, 64)
+        let child_code = vec![
+            0x60,
+            0x11,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x22,
+            0x60,
+            0x20,
+            op::MSTORE,
+            0x60,
+            0x40,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        deploy(&mut db, child, child_code);
+
+        let result = call(db, parent);
+
+        assert!(result.success);
+        let words: Vec<U256> = result
+            .return_data
+            .chunks(32)
+            .map(U256::from_big_endian)
+            .collect();
+        // 只有前 32 字节写入内存，哨兵未被覆盖；完整的 64 字节仍可通过 RETURNDATACOPY 读取
+        assert_eq!(words, [0x11, 0x77, 64, 0x22].map(U256::from));
+    }
+
+    #[test]
+    fn test_call_echo_contract_with_capped_gas() {
+        let parent = addr(0xaa);
+        let echo = addr(0xbb);
+
+        // MSTORE(0, 0x2a); CALL(U256::MAX, echo, 0, 0, 32, 32, 32)
+        let mut code = vec![0x60, 0x2a, 0x60, 0x00, op::MSTORE];
+        code.extend([
+            0x60, 0x20, 0x60, 0x20, 0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x73,
+        ]);
+        code.extend_from_slice(echo.as_bytes());
+        code.push(0x7f);
+        code.extend([0xff; 32]);
+        code.push(op::CALL);
+        // MSTORE(0x40, success); RETURN(0x20, 0x40)
+        code.extend([0x60, 0x40, op::MSTORE, 0x60, 0x40, 0x60, 0x20, op::RETURN]);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, code);
+        // 回显合约：CALLDATACOPY(0, 0, CALLDATASIZE); RETURN(0, CALLDATASIZE)
+        let echo_code = vec![
+            op::CALLDATASIZE,
+            0x60,
+            0x00,
+            0x60,
+            0x00,
+            op::CALLDATACOPY,
+            op::CALLDATASIZE,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        deploy(&mut db, echo, echo_code);
+
+        let result = call(db, parent);
+
+        // 请求的 gas 远超可用值，按 63/64 规则截断后调用仍然成功
+        assert!(result.success);
+        let words: Vec<U256> = result
+            .return_data
+            .chunks(32)
+            .map(U256::from_big_endian)
+            .collect();
+        assert_eq!(words, [0x2a, 1].map(U256::from));
+        assert!(result.gas_used < 100_000);
+    }
+
+    #[test]
+    fn test_returndatacopy_out_of_bounds() {
+        // 没有子调用时返回数据为空，读取 1 字节越界
+        let code = [0x60, 0x01, 0x60, 0x00, 0x60, 0x00, op::RETURNDATACOPY];
+        assert_eq!(run(&code).unwrap_err(), Error::ReturnDataOutOfBounds);
+
+        // 长度为 0 且偏移为 0 时合法
+        let code = [0x60, 0x00, 0x60, 0x00, 0x60, 0x00, op::RETURNDATACOPY];
+        assert!(run(&code).is_ok());
+    }
+
+    #[test]
+    fn test_call_with_value_in_static_context_fails() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+        let grandchild = addr(0xcc);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, staticcall_and_return(child, 0xffff));
+        // 子合约：CALL(0xffff, grandchild, 1, 0, 0, 0, 0)
+        let mut code = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x73,
+        ];
+        code.extend_from_slice(grandchild.as_bytes());
+        code.extend([0x61, 0xff, 0xff, op::CALL, op::STOP]);
+        deploy(&mut db, child, code);
+        deploy(&mut db, grandchild, vec![op::STOP]);
+
+        let result = call(db, parent);
+
+        // 只读上下文中携带 value 的 CALL 使子帧异常终止，STATICCALL 返回 0
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+    }
+
+    #[test]
+    fn test_staticcall_returns_unused_gas() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, staticcall_and_return(child, 0xffff));
+        // 子合约只读取存储
+        deploy(&mut db, child, vec![0x60, 0x00, op::SLOAD, op::STOP]);
+
+        let result = call(db, parent);
+
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::one());
+        assert!(result.gas_used < 0xffff);
+    }
+
+    #[test]
+    fn test_address_inside_delegatecall_is_storage_context() {
+        let proxy = addr(0xaa);
+        let implementation = addr(0xbb);
+
+        // 代理合约：DELEGATECALL(0xffff, implementation, 0, 0, 0, 32)，返回内存 [0, 32)
+        let mut proxy_code = vec![0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+        proxy_code.extend_from_slice(implementation.as_bytes());
+        proxy_code.extend([0x61, 0xff, 0xff, op::DELEGATECALL]);
+        proxy_code.extend([0x60, 0x20, 0x60, 0x00, op::RETURN]);
+
+        // 实现合约：返回 ADDRESS
+        let implementation_code = vec![
+            op::ADDRESS,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, proxy, proxy_code);
+        deploy(&mut db, implementation, implementation_code);
+
+        let result = call(db, proxy);
+
+        assert!(result.success);
+        assert_eq!(result.return_data[12..], proxy.as_bytes()[..]);
+    }
+
+    /// 代理合约用 `opcode` 调用目标合约，目标合约执行 SSTORE(0, CALLER) SSTORE(1, CALLVALUE)
+    ///
+    /// 交易由 addr(1) 携带 5 wei 发给代理合约，CALL/CALLCODE 再携带 3 wei。
+    /// 返回提交了全部状态变更的数据库，以及 (代理地址, 目标地址)。
+    fn store_via(opcode: u8) -> (InMemoryDB, Address, Address) {
+        let proxy = addr(0xaa);
+        let target = addr(0xbb);
+
+        let mut proxy_code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00];
+        if opcode == op::CALL || opcode == op::CALLCODE {
+            proxy_code.extend([0x60, 0x03]);
+        }
+        push_address(&mut proxy_code, target);
+        proxy_code.extend([0x61, 0xff, 0xff, opcode, op::STOP]);
+        let target_code = vec![
+            op::CALLER,
+            0x60,
+            0x00,
+            op::SSTORE,
+            op::CALLVALUE,
+            0x60,
+            0x01,
+            op::SSTORE,
+        ];
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, proxy, proxy_code);
+        deploy(&mut db, target, target_code);
+        db.insert_account(
+            addr(1),
+            AccountInfo {
+                balance: U256::from(5),
+                ..Default::default()
+            },
+        );
+
+        let mut evm = create_berlin_evm(db.clone());
+        let result = evm
+            .transact(Transaction {
+                value: U256::from(5),
+                ..call_tx(proxy)
+            })
+            .unwrap();
+        assert!(result.success);
+        db.commit(evm.call_manager.take_committed_changes())
+            .unwrap();
+        (db, proxy, target)
+    }
+
+    /// 账户的存储内容，按槽位排序
+    fn sorted_storage(db: &InMemoryDB, address: Address) -> Vec<(U256, U256)> {
+        let mut storage = db.get_account_storage(address);
+        storage.sort();
+        storage
+    }
+
+    #[test]
+    fn test_call_writes_target_storage() {
+        let (db, proxy, target) = store_via(op::CALL);
+
+        assert!(sorted_storage(&db, proxy).is_empty());
+        assert_eq!(
+            sorted_storage(&db, target),
+            vec![
+                (U256::zero(), address_word(proxy)),
+                (U256::one(), U256::from(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_callcode_writes_caller_storage() {
+        let (db, proxy, target) = store_via(op::CALLCODE);
+
+        // 目标代码在代理的存储上执行，msg.sender 是代理自己
+        assert!(sorted_storage(&db, target).is_empty());
+        assert_eq!(
+            sorted_storage(&db, proxy),
+            vec![
+                (U256::zero(), address_word(proxy)),
+                (U256::one(), U256::from(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delegatecall_writes_caller_storage_with_original_context() {
+        let (db, proxy, target) = store_via(op::DELEGATECALL);
+
+        // 目标代码在代理的存储上执行，并保留原始交易的 sender 和 value
+        assert!(sorted_storage(&db, target).is_empty());
+        assert_eq!(
+            sorted_storage(&db, proxy),
+            vec![
+                (U256::zero(), address_word(addr(1))),
+                (U256::one(), U256::from(5)),
+            ]
+        );
+    }
+
+    /// 最小的 ERC20 风格合约：余额以持有者地址为存储槽，只支持 transfer(address,uint256)
+    fn erc20_code() -> Vec<u8> {
+        assemble(&format!(
+            "
+            ; 选择器不是 transfer(address,uint256) 时回滚
+            PUSH4 0xa9059cbb
+            PUSH1 0xe0 PUSH1 2 EXP PUSH1 0 CALLDATALOAD DIV
+            SUB @fail JUMPI
+
+            ; amount / (balances[caller] + 1) 不为 0 即余额不足
+            PUSH1 1 CALLER SLOAD ADD
+            PUSH1 0x24 CALLDATALOAD DIV
+            @fail JUMPI
+
+            ; balances[caller] -= amount
+            PUSH1 0x24 CALLDATALOAD CALLER SLOAD SUB CALLER SSTORE
+            ; balances[to] += amount
+            PUSH1 0x24 CALLDATALOAD PUSH1 0x04 CALLDATALOAD SLOAD ADD
+            PUSH1 0x04 CALLDATALOAD SSTORE
+
+            ; emit Transfer(caller, to, amount)
+            PUSH1 0x24 CALLDATALOAD PUSH1 0 MSTORE
+            PUSH1 0x04 CALLDATALOAD CALLER PUSH32 {:#x} PUSH1 0x20 PUSH1 0 LOG3
+            STOP
+
+            fail:
+            PUSH1 0 PUSH1 0 REVERT
+            ",
+            transfer_topic()
+        ))
+        .unwrap()
+    }
+
+    fn transfer_topic() -> H256 {
+        keccak_hash::keccak(b"Transfer(address,address,uint256)")
+    }
+
+    /// 执行 transfer(to, amount)，返回执行结果和提交状态后的数据库
+    fn erc20_transfer(
+        sender_balance: u64,
+        to: Address,
+        amount: u64,
+    ) -> (ExecutionResult, InMemoryDB) {
+        let token = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, token, erc20_code());
+        db.insert_storage(token, address_word(addr(1)), U256::from(sender_balance));
+
+        let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+        data.extend_from_slice(H256::from(to).as_bytes());
+        data.extend_from_slice(H256::from_low_u64_be(amount).as_bytes());
+
+        let mut evm = create_berlin_evm(db.clone());
+        let result = evm
+            .transact(Transaction {
+                data,
+                ..call_tx(token)
+            })
+            .unwrap();
+        db.commit(evm.call_manager.take_committed_changes())
+            .unwrap();
+        (result, db)
+    }
+
+    #[test]
+    fn test_erc20_transfer_end_to_end() {
+        let token = addr(0xaa);
+        let recipient = addr(0xbb);
+
+        let (result, db) = erc20_transfer(100, recipient, 30);
+
+        assert!(result.success);
+        assert_eq!(
+            sorted_storage(&db, token),
+            vec![
+                (address_word(addr(1)), U256::from(70)),
+                (address_word(recipient), U256::from(30)),
+            ]
+        );
+        assert_eq!(
+            result.logs,
+            vec![Log {
+                address: token,
+                topics: vec![transfer_topic(), addr(1).into(), recipient.into()],
+                data: H256::from_low_u64_be(30).as_bytes().to_vec(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_erc20_transfer_reverts_on_insufficient_balance() {
+        let token = addr(0xaa);
+
+        let (result, db) = erc20_transfer(20, addr(0xbb), 30);
+
+        // 回滚后余额不变，也不会留下日志
+        assert!(!result.success);
+        assert!(result.logs.is_empty());
+        assert_eq!(
+            sorted_storage(&db, token),
+            vec![(address_word(addr(1)), U256::from(20))]
+        );
+    }
+
+    #[test]
+    fn test_state_changes_in_static_frame_are_invalid() {
+        let contract = addr(0xaa);
+        let static_frame = CallFrame::new_call(
+            addr(1),
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            CallType::StaticCall,
+            0,
+        );
+
+        // SSTORE(0, 1) 与 CREATE(0, 0, 0) 在只读帧中都以 InvalidOpcode 失败
+        for code in [
+            vec![0x60, 0x01, 0x60, 0x00, op::SSTORE],
+            vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, op::CREATE],
+        ] {
+            let pushes = code.len() / 2;
+            let mut evm = create_berlin_evm(InMemoryDB::new());
+            evm.load_code(contract, code, 100_000);
+            evm.call_manager.begin_call(static_frame.clone()).unwrap();
+            for _ in 0..pushes {
+                evm.step().unwrap();
+            }
+            assert_eq!(evm.step().unwrap_err(), Error::InvalidOpcode);
+        }
+    }
+
+    /// 工厂合约：用 5 字节的初始化代码执行 CREATE，并返回 CREATE 的结果
+    fn create_and_return(init_code: [u8; 5]) -> ExecutionResult {
+        let factory = addr(0xcc);
+
+        // PUSH5 init_code PUSH1 0 MSTORE：初始化代码位于内存 [27, 32)
+        let mut code = vec![0x64];
+        code.extend(init_code);
+        code.extend([0x60, 0x00, op::MSTORE]);
+        // CREATE(value = 0, offset = 27, size = 5)
+        code.extend([0x60, 0x05, 0x60, 0x1b, 0x60, 0x00, op::CREATE]);
+        code.extend([0x60, 0x00, op::MSTORE, 0x60, 0x20, 0x60, 0x00, op::RETURN]);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, factory, code);
+        call(db, factory)
+    }
+
+    #[test]
+    fn test_create_revert_returns_gas_to_caller() {
+        // 初始化代码：PUSH1 0 PUSH1 0 REVERT
+        let result = create_and_return([0x60, 0x00, 0x60, 0x00, op::REVERT]);
+
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+        // 只消耗交易固有成本、CREATE 的基础成本和少量指令 gas，转发的 gas 已退回
+        assert!(result.gas_used < GAS_TX_BASE + 32_000 + 100);
+
+        // 对比：初始化代码遇到无效指令会耗尽转发的 gas
+        let result = create_and_return([0x60, 0x00, 0x60, 0x00, 0xEF]);
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+        assert!(result.gas_used > 900_000);
+    }
+
+    #[test]
+    fn test_create_address_derivation() {
+        let evm = create_berlin_evm(InMemoryDB::new());
+        let address = |hex: &str| hex.parse::<Address>().unwrap();
+
+        let sender = address("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(
+            evm.calculate_create_address(sender, 0),
+            address("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+        );
+        assert_eq!(
+            evm.calculate_create_address(sender, 1),
+            address("343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+        );
+
+        // EIP-1014 中的示例
+        assert_eq!(
+            evm.calculate_create2_address(Address::zero(), H256::zero(), keccak_hash::keccak([0])),
+            address("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38")
+        );
+        assert_eq!(
+            evm.calculate_create2_address(
+                address("00000000000000000000000000000000deadbeef"),
+                H256::from_low_u64_be(0xcafebabe),
+                keccak_hash::keccak([0xde, 0xad, 0xbe, 0xef]),
+            ),
+            address("60f3f640a8508fc6a86d45df051962668e1e8ac7")
+        );
+    }
+
+    #[test]
+    fn test_factory_deploys_child_with_create2() {
+        let factory = addr(0xcc);
+        // 子合约运行时代码返回 0x2a；初始化代码把它复制到内存并返回
+        let runtime = assemble("PUSH1 0x2a PUSH1 0 MSTORE PUSH1 0x20 PUSH1 0 RETURN").unwrap();
+        let init_code = assemble(&format!(
+            "PUSH10 0x{} PUSH1 0 MSTORE PUSH1 10 PUSH1 22 RETURN",
+            hex::encode(&runtime)
+        ))
+        .unwrap();
+        assert_eq!(init_code.len(), 19);
+
+        // 初始化代码位于内存 [13, 32)，以相同的 salt 执行两次 CREATE2，
+        // 返回 [第一次的地址, 第二次的地址]
+        let code = assemble(&format!(
+            "
+            PUSH19 0x{} PUSH1 0 MSTORE
+            PUSH1 7 PUSH1 19 PUSH1 13 PUSH1 0 CREATE2 PUSH1 0x20 MSTORE
+            PUSH1 7 PUSH1 19 PUSH1 13 PUSH1 0 CREATE2 PUSH1 0x40 MSTORE
+            PUSH1 0x40 PUSH1 0x20 RETURN
+            ",
+            hex::encode(&init_code)
+        ))
+        .unwrap();
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, factory, code);
+
+        let mut evm = create_berlin_evm(db);
+        let transact = |evm: &mut EVM<_, InMemoryDB>, to| {
+            let result = evm.transact(call_tx(to)).unwrap();
+            let changes = evm.call_manager.take_committed_changes();
+            evm.database_mut().commit(changes).unwrap();
+            result
+        };
+
+        // 地址只取决于工厂地址、salt 和初始化代码；第二次创建发生地址冲突，压入 0
+        let result = transact(&mut evm, factory);
+        assert!(result.success);
+        let expected = evm.calculate_create2_address(
+            factory,
+            H256::from_low_u64_be(7),
+            keccak_hash::keccak(&init_code),
+        );
+        assert_eq!(Address::from_slice(&result.return_data[12..32]), expected);
+        assert_eq!(
+            U256::from_big_endian(&result.return_data[32..]),
+            U256::zero()
+        );
+
+        // 子合约已部署，工厂的 nonce 在两次创建中都递增
+        let child = evm.database_mut().basic(expected).unwrap().unwrap();
+        assert_eq!(child.code, Some(runtime));
+        assert_eq!(child.nonce, 1);
+        assert_eq!(evm.database_mut().basic(factory).unwrap().unwrap().nonce, 3);
+        let result = transact(&mut evm, expected);
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::from(0x2a));
+
+        // CREATE2 在 Frontier 中未定义
+        let mut frontier = create_frontier_evm(InMemoryDB::new());
+        frontier.machine = Machine::new(100_000);
+        let create2 = Bytecode::new(vec![op::CREATE2]);
+        assert_eq!(
+            frontier.execute_bytecode(&create2).unwrap_err(),
+            Error::InvalidOpcode
+        );
+    }
+
+    #[test]
+    fn test_create_pushes_new_address() {
+        // 初始化代码：PUSH1 0 PUSH1 0 RETURN（部署空代码）
+        let result = create_and_return([0x60, 0x00, 0x60, 0x00, op::RETURN]);
+
+        assert!(result.success);
+        assert_ne!(U256::from_big_endian(&result.return_data), U256::zero());
+    }
+
+    #[test]
+    fn test_calldataload_reads_selector() {
+        let contract = addr(0xaa);
+        // PUSH1 0 CALLDATALOAD PUSH1 0 MSTORE，返回内存 [0, 32)
+        let code = vec![
+            0x60,
+            0x00,
+            op::CALLDATALOAD,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code);
+
+        // transfer(address,uint256) 的函数选择器
+        let selector = vec![0xa9, 0x05, 0x9c, 0xbb];
+        let result = call_with(db, contract, U256::zero(), selector.clone());
+
+        assert!(result.success);
+        assert_eq!(result.return_data[..4], selector[..]);
+        // 超出调用数据末尾的部分补 0
+        assert_eq!(result.return_data[4..], [0u8; 28]);
+    }
+
+    #[test]
+    fn test_caller_callvalue_and_calldata() {
+        let contract = addr(0xaa);
+        let code = vec![
+            // CALLER -> [0, 32)
+            op::CALLER,
+            0x60,
+            0x00,
+            op::MSTORE,
+            // CALLVALUE -> [32, 64)
+            op::CALLVALUE,
+            0x60,
+            0x20,
+            op::MSTORE,
+            // CALLDATASIZE -> [64, 96)
+            op::CALLDATASIZE,
+            0x60,
+            0x40,
+            op::MSTORE,
+            // CALLDATACOPY(dest = 96, offset = 2, size = 4)
+            0x60,
+            0x04,
+            0x60,
+            0x02,
+            0x60,
+            0x60,
+            op::CALLDATACOPY,
+            // RETURN(0, 100)
+            0x60,
+            0x64,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code);
+        db.insert_account(
+            addr(1),
+            AccountInfo {
+                balance: U256::from(7),
+                ..Default::default()
+            },
+        );
+
+        let result = call_with(db, contract, U256::from(7), vec![0xde, 0xad, 0xbe, 0xef]);
+
+        assert!(result.success);
+        let output = result.return_data;
+        assert_eq!(output[12..32], [1u8; 20]);
+        assert_eq!(U256::from_big_endian(&output[32..64]), U256::from(7));
+        assert_eq!(U256::from_big_endian(&output[64..96]), U256::from(4));
+        assert_eq!(output[96..100], [0xbe, 0xef, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_call_result_distinguishes_revert_from_halt() {
+        let reverter = addr(0xcc);
+        let burner = addr(0xdd);
+        let mut db = InMemoryDB::new();
+        // MSTORE(0, 0x2a)，然后 REVERT(0, 32)：共消耗 18 gas
+        deploy(
+            &mut db,
+            reverter,
+            vec![
+                0x60,
+                0x2a,
+                0x60,
+                0x00,
+                op::MSTORE,
+                0x60,
+                0x20,
+                0x60,
+                0x00,
+                op::REVERT,
+            ],
+        );
+        // 无限循环，直到耗尽 gas
+        deploy(&mut db, burner, vec![op::JUMPDEST, 0x60, 0x00, op::JUMP]);
+
+        let mut evm = create_berlin_evm(db);
+        evm.machine = Machine::new(100_000);
+        let frame = |to| {
+            CallFrame::builder()
+                .caller(addr(1))
+                .to(to)
+                .gas_limit(10_000)
+                .build()
+        };
+
+        // REVERT 携带返回数据并退还剩余 gas
+        let result = evm.call_frame(frame(reverter)).unwrap();
+        let mut output = vec![0u8; 32];
+        output[31] = 0x2a;
+        assert_eq!(
+            result,
+            CallResult::Revert {
+                output,
+                gas_left: 10_000 - 18
+            }
+        );
+
+        // 耗尽 gas 属于异常终止，转发的 gas 全部消耗
+        let result = evm.call_frame(frame(burner)).unwrap();
+        assert_eq!(
+            result,
+            CallResult::Halt {
+                reason: Error::OutOfGas,
+                gas_left: 0
+            }
+        );
+        assert!(result.output().is_empty());
+    }
+
+    /// CALL(0xffff, to, value, 0, 0, 0, 0)，并把结果作为 32 字节返回
+    fn call_with_value(to: Address, value: u8) -> Vec<u8> {
+        let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, value];
+        push_address(&mut code, to);
+        code.extend([0x61, 0xff, 0xff, op::CALL]);
+        code.extend([0x60, 0x00, op::MSTORE, 0x60, 0x20, 0x60, 0x00, op::RETURN]);
+        code
+    }
+
+    /// CALL(gas = 0, callee, value, 0, 0, 0, 0) 并返回 CALL 的结果，被调用方只能依靠补贴执行
+    fn stipend_call(callee_code: Vec<u8>, value: u8) -> ExecutionResult {
+        let parent = addr(0xaa);
+        let callee = addr(0xbb);
+        let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, value];
+        push_address(&mut code, callee);
+        code.extend([0x60, 0x00, op::CALL]);
+        code.extend([0x60, 0x00, op::MSTORE, 0x60, 0x20, 0x60, 0x00, op::RETURN]);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, code);
+        db.insert_account(
+            parent,
+            AccountInfo {
+                balance: U256::from(100),
+                ..db.get_all_accounts()[&parent].clone()
+            },
+        );
+        deploy(&mut db, callee, callee_code);
+        call(db, parent)
+    }
+
+    #[test]
+    fn test_value_call_forwards_stipend() {
+        // 被调用方把 GAS 的结果写进内存，再用 LOG0 记下来：648 gas，低于补贴
+        let callee = vec![
+            op::GAS,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::LOG0,
+        ];
+
+        // 不携带 value：被调用方没有 gas，调用失败
+        let result = stipend_call(callee.clone(), 0);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+        assert!(result.logs.is_empty());
+
+        // 携带 value：被调用方收到的正好是补贴，执行 GAS 之后还剩 2298
+        let with_value = stipend_call(callee, 1);
+        assert_eq!(U256::from_big_endian(&with_value.return_data), U256::one());
+        assert_eq!(with_value.logs.len(), 1);
+        assert_eq!(
+            U256::from_big_endian(&with_value.logs[0].data),
+            U256::from(Berlin::CALL_STIPEND - 2)
+        );
+        // 调用方额外支付 9000 gas，补贴中用掉的部分计入总消耗
+        assert_eq!(
+            with_value.gas_used,
+            result.gas_used + GAS_CALL_VALUE + 648 - Berlin::CALL_STIPEND
+        );
+    }
+
+    #[test]
+    fn test_sstore_fails_within_call_stipend() {
+        // SSTORE(0, 0) 不改变存储，冷槽位只需 2200 gas，补贴足够支付，
+        // 但 EIP-2200 要求剩余 gas 不超过补贴时 SSTORE 失败
+        let result = stipend_call(vec![0x60, 0x00, 0x60, 0x00, op::SSTORE], 1);
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+    }
+
+    #[test]
+    fn test_call_transfers_value() {
+        let parent = addr(0xaa);
+        let recipient = addr(0xbb);
+        let reverter = addr(0xcc);
+        let run = |code: Vec<u8>| {
+            let mut db = InMemoryDB::new();
+            deploy(&mut db, parent, code);
+            db.insert_account(
+                parent,
+                AccountInfo {
+                    balance: U256::from(100),
+                    ..db.get_all_accounts()[&parent].clone()
+                },
+            );
+            // REVERT(0, 0)
+            deploy(&mut db, reverter, vec![0x60, 0x00, 0x60, 0x00, op::REVERT]);
+            let mut evm = create_berlin_evm(db.clone());
+            let result = evm.transact(call_tx(parent)).unwrap();
+            assert!(result.success);
+            db.commit(evm.call_manager.take_committed_changes())
+                .unwrap();
+            (U256::from_big_endian(&result.return_data), db)
+        };
+        let balance = |db: &mut InMemoryDB, address| {
+            db.basic(address)
+                .unwrap()
+                .map_or(U256::zero(), |account| account.balance)
+        };
+
+        // 转账成功：接收方账户被创建
+        let (success, mut db) = run(call_with_value(recipient, 30));
+        assert_eq!(success, U256::one());
+        assert_eq!(balance(&mut db, parent), U256::from(70));
+        assert_eq!(balance(&mut db, recipient), U256::from(30));
+
+        // 余额不足：CALL 返回 0，父调用继续执行，余额不变
+        let (success, mut db) = run(call_with_value(recipient, 200));
+        assert_eq!(success, U256::zero());
+        assert_eq!(balance(&mut db, parent), U256::from(100));
+        assert!(db.basic(recipient).unwrap().is_none());
+
+        // 子调用 REVERT：转账随子帧一起回滚
+        let (success, mut db) = run(call_with_value(reverter, 30));
+        assert_eq!(success, U256::zero());
+        assert_eq!(balance(&mut db, parent), U256::from(100));
+        assert_eq!(balance(&mut db, reverter), U256::zero());
+    }
+
+    #[test]
+    fn test_transact_value_exceeding_balance() {
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            addr(1),
+            AccountInfo {
+                balance: U256::from(10),
+                ..Default::default()
+            },
+        );
+        let tx = Transaction {
+            value: U256::from(11),
+            gas_limit: 100_000,
+            ..call_tx(addr(2))
+        };
+
+        let mut evm = create_berlin_evm(db);
+        assert_eq!(
+            evm.transact(tx.clone()).unwrap_err(),
+            Error::InsufficientFunds
+        );
+
+        // 余额足够时转给外部账户
+        let result = evm
+            .transact(Transaction {
+                value: U256::from(10),
+                ..tx
+            })
+            .unwrap();
+        assert!(result.success);
+        let changes = evm.call_manager.take_committed_changes();
+        assert!(changes.contains(&StateChange::UpdateBalance {
+            address: addr(2),
+            balance: U256::from(10),
+        }));
+    }
+
+    #[test]
+    fn test_transact_prepays_gas_and_transfers_value() {
+        let caller = addr(1);
+        let recipient = addr(2);
+        // 预付 100_000 * 2 的 gas 费用外加 500 的转账
+        let required = U256::from(200_500);
+        let tx = Transaction {
+            caller,
+            to: Some(recipient),
+            value: U256::from(500),
+            gas_limit: 100_000,
+            gas_price: U256::from(2),
+            ..Default::default()
+        };
+        let funded = |balance: U256| {
+            let mut db = InMemoryDB::new();
+            db.insert_account(
+                caller,
+                AccountInfo {
+                    balance,
+                    ..Default::default()
+                },
+            );
+            create_berlin_evm(db)
+        };
+
+        let mut evm = funded(required - 1);
+        assert_eq!(
+            evm.transact(tx.clone()).unwrap_err(),
+            Error::InsufficientFunds
+        );
+
+        let mut evm = funded(required);
+        let result = evm.transact(tx.clone()).unwrap();
+        assert!(result.success);
+        let changes = evm.call_manager.take_committed_changes();
+        evm.database_mut().commit(changes).unwrap();
+
+        // 发送者只支付实际消耗的 gas
+        let balance = |evm: &mut EVM<_, InMemoryDB>, address| {
+            evm.database_mut().basic(address).unwrap().unwrap().balance
+        };
+        let fee = U256::from(result.gas_used) * 2;
+        assert_eq!(balance(&mut evm, caller), required - 500 - fee);
+        assert_eq!(balance(&mut evm, recipient), U256::from(500));
+
+        // 执行失败时转账回滚，只扣除 gas 费用
+        let contract = addr(0xaa);
+        let mut evm = funded(required);
+        deploy(evm.database_mut(), contract, vec![op::INVALID]);
+        let result = evm
+            .transact(Transaction {
+                to: Some(contract),
+                ..tx
+            })
+            .unwrap();
+        assert!(!result.success);
+        let changes = evm.call_manager.take_committed_changes();
+        evm.database_mut().commit(changes).unwrap();
+        assert_eq!(balance(&mut evm, caller), required - 200_000);
+        assert_eq!(balance(&mut evm, contract), U256::zero());
+    }
+
+    #[test]
+    fn test_jump_skips_code() {
+        // PUSH1 5 JUMP INVALID INVALID JUMPDEST PUSH1 1
+        let code = [0x60, 0x05, op::JUMP, 0xEF, 0xEF, op::JUMPDEST, 0x60, 0x01];
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), [U256::one()]);
+    }
+
+    #[test]
+    fn test_jumpi_condition() {
+        // 条件为 0 时不跳转，继续执行下一条指令
+        // PUSH1 0 PUSH1 8 JUMPI PUSH1 2 STOP JUMPDEST PUSH1 1
+        let code = [
+            0x60,
+            0x00,
+            0x60,
+            0x08,
+            op::JUMPI,
+            0x60,
+            0x02,
+            op::STOP,
+            op::JUMPDEST,
+            0x60,
+            0x01,
+        ];
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), [U256::from(2)]);
+
+        // 条件非 0 时跳转
+        let mut code = code;
+        code[1] = 0x01;
+        let machine = run(&code).unwrap();
+        assert_eq!(machine.stack.data(), [U256::one()]);
+    }
+
+    #[test]
+    fn test_jump_into_push_data_is_invalid() {
+        // PUSH1 4 JUMP PUSH32 [0x5b; 32]：位置 4 的 0x5b 是 PUSH32 的立即数
+        let mut code = vec![0x60, 0x04, op::JUMP, op::PUSH32];
+        code.extend([op::JUMPDEST; 32]);
+        assert_eq!(run(&code).unwrap_err(), Error::InvalidJump);
+
+        // 跳到非 JUMPDEST 指令或超出代码范围同样非法
+        assert_eq!(
+            run(&[0x60, 0x00, op::JUMP]).unwrap_err(),
+            Error::InvalidJump
+        );
+        let mut code = push32(U256::MAX);
+        code.push(op::JUMP);
+        assert_eq!(run(&code).unwrap_err(), Error::InvalidJump);
+    }
+
+    /// 父合约写入槽 3、清零槽 1，再 DELEGATECALL 子合约清零槽 2（子合约按需 REVERT）
+    ///
+    /// 写入新槽位的 20000 gas 让总消耗足够大，使 Berlin 的 1/2 上限不会截断单次退款。
+    fn clear_two_slots<SPEC: crate::spec::Spec>(
+        mut evm: EVM<SPEC, InMemoryDB>,
+        child_reverts: bool,
+    ) -> ExecutionResult {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        // SSTORE(3, 1) SSTORE(1, 0) DELEGATECALL(0xffff, child, 0, 0, 0, 0) STOP
+        let mut parent_code = vec![0x60, 0x01, 0x60, 0x03, op::SSTORE];
+        parent_code.extend([0x60, 0x00, 0x60, 0x01, op::SSTORE]);
+        parent_code.extend([0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73]);
+        parent_code.extend_from_slice(child.as_bytes());
+        parent_code.extend([0x61, 0xff, 0xff, op::DELEGATECALL, op::STOP]);
+
+        // SSTORE(2, 0)，然后 STOP 或 REVERT(0, 0)
+        let mut child_code = vec![0x60, 0x00, 0x60, 0x02, op::SSTORE];
+        if child_reverts {
+            child_code.extend([0x60, 0x00, 0x60, 0x00, op::REVERT]);
+        }
+
+        let db = evm.database_mut();
+        deploy(db, parent, parent_code);
+        deploy(db, child, child_code);
+        db.insert_storage(parent, U256::one(), U256::from(7));
+        db.insert_storage(parent, U256::from(2), U256::from(7));
+
+        evm.transact(call_tx(parent)).unwrap()
+    }
+
+    #[test]
+    fn test_refund_aggregates_across_frames_and_is_capped() {
+        let result = clear_two_slots(create_berlin_evm(InMemoryDB::new()), false);
+        assert!(result.success);
+
+        // 两次清零共 30000 退款，超过 1/2 上限，按上限发放
+        let gas_before_refund = result.gas_used + result.gas_refunded;
+        assert!(30000 > gas_before_refund / 2);
+        assert_eq!(result.gas_refunded, gas_before_refund / 2);
+
+        // London：两次清零共 9600 退款，低于 1/5 上限，全额发放
+        let result = clear_two_slots(create_london_evm(InMemoryDB::new()), false);
+        let gas_before_refund = result.gas_used + result.gas_refunded;
+        assert!(9600 <= gas_before_refund / 5);
+        assert_eq!(result.gas_refunded, 9600);
+    }
+
+    #[test]
+    fn test_reverted_frame_contributes_no_refund() {
+        // Berlin：上限为 1/2，单次清零的 15000 退款不会被封顶
+        let result = clear_two_slots(create_berlin_evm(InMemoryDB::new()), true);
+        assert!(result.success);
+        assert_eq!(result.gas_refunded, 15000);
+    }
+
+    #[test]
+    fn test_create_transaction_refund_is_capped() {
+        let caller = addr(1);
+        let mut evm = create_london_evm(InMemoryDB::new());
+        let contract = evm.calculate_create_address(caller, 0);
+
+        // 新合约地址上预置四个存储槽，初始化代码将其全部清零
+        let mut init_code = Vec::new();
+        for slot in 0..4u8 {
+            evm.database_mut()
+                .insert_storage(contract, U256::from(slot), U256::one());
+            init_code.extend([0x60, 0x00, 0x60, slot, op::SSTORE]);
+        }
+        init_code.push(op::STOP);
+
+        let result = evm
+            .transact(Transaction {
+                caller,
+                data: init_code,
+                gas_limit: 1_000_000,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(Address::from_slice(&result.return_data), contract);
+
+        // 四次清零共 19200 退款，超过 1/5 上限，按上限发放
+        let gas_before_refund = result.gas_used + result.gas_refunded;
+        assert!(19200 > gas_before_refund / 5);
+        assert_eq!(result.gas_refunded, gas_before_refund / 5);
+    }
+
+    /// 以 London 为基础定义测试规范，只写出与 London 不同的常量
+    ///
+    /// 规范定义在 `$module` 中：未覆盖的常量从 `defaults` 批量导入，
+    /// 显式定义的同名常量会遮蔽批量导入的版本。
+    macro_rules! london_variant {
+        (
+            $(#[$meta:meta])*
+            mod $module:ident: $spec:ident { $($overrides:tt)* }
+        ) => {
+            london_variant!(@define [$(#[$meta])*] $module $spec { $($overrides)* };
+                GAS_CALL: u64, GAS_SLOAD: u64, GAS_SSTORE_SET: u64, GAS_SSTORE_RESET: u64,
+                GAS_SSTORE_CLEAR_REFUND: i64, GAS_SELFDESTRUCT_REFUND: i64, MAX_REFUND_QUOTIENT: u64,
+                GAS_CREATE: u64, GAS_CODE_DEPOSIT: u64, GAS_EXP_BYTE: u64, GAS_TX_DATA_NONZERO: u64,
+                ENABLE_CREATE2: bool, ENABLE_BITWISE_SHIFTING: bool, ENABLE_CHAINID: bool,
+                ENABLE_SELFBALANCE: bool, ENABLE_EXTCODEHASH: bool, ENABLE_ACCESS_LISTS: bool,
+                ENABLE_EIP2929: bool, ENABLE_EIP1559: bool, ENABLE_EIP2565: bool,
+                ENABLE_INITCODE_METERING: bool, ENABLE_MCOPY: bool, ENABLE_TRANSIENT_STORAGE: bool,
+                STACK_LIMIT: usize, MEMORY_LIMIT: usize, CALL_DEPTH_LIMIT: usize, MAX_CODE_SIZE: usize,
+                MAX_INITCODE_SIZE: usize);
+        };
+        (
+            @define [$(#[$meta:meta])*] $module:ident $spec:ident
+            { $(const $item:ident: $item_ty:ty = $value:expr;)* };
+            $($name:ident: $ty:ty),*
+        ) => {
+            mod $module {
+                use crate::spec::{London, Spec};
+
+                #[allow(dead_code)]
+                mod defaults {
+                    use crate::spec::{London, Spec};
+                    $(pub const $name: $ty = London::$name;)*
+                }
+                use defaults::*;
+
+                $(const $item: $item_ty = $value;)*
+
+                $(#[$meta])*
+                #[derive(Clone, Debug)]
+                pub struct $spec;
+
+                impl Spec for $spec {
+                    const NAME: &'static str = stringify!($spec);
+                    $(const $name: $ty = $name;)*
+
+                    fn precompiles() -> &'static [u8] {
+                        London::precompiles()
+                    }
+                }
+            }
+            use $module::$spec;
+        };
+    }
+
+    london_variant! {
+        /// 在 London 基础上启用 EIP-3860 的测试规范
+        mod shanghai: Shanghai {
+            const ENABLE_INITCODE_METERING: bool = true;
+            const MAX_INITCODE_SIZE: usize = 2 * London::MAX_CODE_SIZE;
+        }
+    }
+
+    fn create_tx(data: Vec<u8>) -> Transaction {
+        Transaction {
+            caller: addr(1),
+            data,
+            gas_limit: 10_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_oversized_initcode_is_rejected() {
+        let init_code = vec![op::STOP; Shanghai::MAX_INITCODE_SIZE + 1];
+
+        // 启用 EIP-3860 的规范拒绝整笔交易
+        let mut evm = EVM::<Shanghai, _>::new(InMemoryDB::new(), Environment::default());
+        assert_eq!(
+            evm.transact(create_tx(init_code.clone())).unwrap_err(),
+            Error::CodeTooLarge
+        );
+        assert!(evm.call_manager.take_committed_changes().is_empty());
+
+        // London 没有初始化代码上限
+        let mut evm = create_london_evm(InMemoryDB::new());
+        assert!(evm.transact(create_tx(init_code.clone())).unwrap().success);
+
+        // 初始化代码按字计费
+        let words = init_code.len().div_ceil(32) as u64;
+        let tx = create_tx(init_code);
+        assert_eq!(
+            tx.intrinsic_gas::<Shanghai>(),
+            tx.intrinsic_gas::<London>() + words * GAS_INITCODE_WORD
+        );
+    }
+
+    london_variant! {
+        /// 栈深度上限只有 4 的测试规范
+        mod tiny_stack: TinyStack {
+            const STACK_LIMIT: usize = 4;
+        }
+    }
+
+    #[test]
+    fn test_stack_limit_follows_spec() {
+        let mut evm = EVM::<TinyStack, _>::new(InMemoryDB::new(), Environment::default());
+        evm.load_code(addr(0xaa), [0x60, 0x01].repeat(5), 1_000);
+        for _ in 0..TinyStack::STACK_LIMIT {
+            evm.step().unwrap();
+        }
+        assert_eq!(evm.step().unwrap_err(), Error::StackOverflow);
+        assert_eq!(evm.machine().stack.len(), TinyStack::STACK_LIMIT);
+    }
+
+    #[test]
+    fn test_oversized_deployed_code_fails_creation() {
+        // PUSH2 size; PUSH1 0; RETURN：部署 size 字节的零代码
+        let deploy = |size: usize| {
+            let [hi, lo] = (size as u16).to_be_bytes();
+            vec![0x61, hi, lo, 0x60, 0x00, op::RETURN]
+        };
+
+        let mut evm = create_london_evm(InMemoryDB::new());
+        let result = evm
+            .transact(create_tx(deploy(London::MAX_CODE_SIZE)))
+            .unwrap();
+        assert!(result.success);
+
+        let mut evm = create_london_evm(InMemoryDB::new());
+        let result = evm
+            .transact(create_tx(deploy(London::MAX_CODE_SIZE + 1)))
+            .unwrap();
+        assert!(!result.success);
+        assert!(evm
+            .call_manager
+            .take_committed_changes()
+            .iter()
+            .all(|change| !matches!(change, StateChange::CreateAccount { .. })));
+    }
+
+    #[test]
+    fn test_effective_refund_cap() {
+        use crate::evm::effective_refund;
+        use crate::spec::{Berlin, London};
+
+        assert_eq!(effective_refund::<London>(50_000, 20_000), 10_000);
+        assert_eq!(effective_refund::<Berlin>(50_000, 20_000), 20_000);
+        assert_eq!(effective_refund::<London>(50_000, -100), 0);
+    }
+
+    #[test]
+    fn test_check_gas_accounting() {
+        assert!(check_gas_accounting(100, 60, 40, 21).is_ok());
+        // 超过 gas limit
+        assert!(check_gas_accounting(100, 101, 0, 21).is_err());
+        // 低于固有成本
+        assert!(check_gas_accounting(100, 20, 80, 21).is_err());
+        // 消耗与剩余之和不等于 gas limit
+        assert!(check_gas_accounting(100, 60, 39, 21).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "gas 记账错误")]
+    fn test_gas_miscount_is_caught() {
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, vec![0x60, 0x01, op::STOP]);
+
+        let mut evm = create_berlin_evm(db);
+        evm.gas_used_skew = 1;
+        let _ = evm.transact(Transaction {
+            gas_limit: 100_000,
+            ..call_tx(contract)
+        });
+    }
+
+    #[test]
+    fn test_run_with_fork_for_block() {
+        use crate::evm::run_with_fork_for_block;
+        use crate::spec::{SpecId, BERLIN_BLOCK, CANCUN_TIMESTAMP, LONDON_BLOCK};
+
+        assert_eq!(SpecId::for_block(1, 0), SpecId::Frontier);
+        assert_eq!(SpecId::for_block(BERLIN_BLOCK, 0), SpecId::Berlin);
+        assert_eq!(SpecId::for_block(LONDON_BLOCK + 1, 0), SpecId::London);
+        assert_eq!(
+            SpecId::for_block(19_426_587, CANCUN_TIMESTAMP),
+            SpecId::Cancun
+        );
+
+        let contract = addr(0xaa);
+        // SLOAD(0); NUMBER; RETURN 区块号
+        let code = vec![
+            0x60,
+            0x00,
+            op::SLOAD,
+            op::NUMBER,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        let tx = Transaction {
+            gas_limit: 100_000,
+            ..call_tx(contract)
+        };
+        let run_at = |block| {
+            let mut db = InMemoryDB::new();
+            deploy(&mut db, contract, code.clone());
+            run_with_fork_for_block(block, 1_700_000_000, tx.clone(), db).unwrap()
+        };
+
+        let frontier = run_at(1_000_000);
+        let london = run_at(LONDON_BLOCK + 1);
+
+        assert!(frontier.success && london.success);
+        assert_eq!(
+            U256::from_big_endian(&london.return_data),
+            U256::from(LONDON_BLOCK + 1)
+        );
+        // 交易固有成本相同，差异来自 London 冷访问的 SLOAD
+        assert_eq!(london.gas_used - frontier.gas_used, 2100 - 200);
+    }
+
+    #[test]
+    fn test_with_spec_id_dispatch() {
+        use crate::evm::{with_spec_id, WithSpec};
+        use crate::spec::SpecId;
+
+        /// 执行交易并返回 (规范名称, 消耗的 gas)
+        struct Run(Transaction);
+
+        impl WithSpec<InMemoryDB> for Run {
+            type Output = (&'static str, u64);
+
+            fn run<SPEC: Spec>(self, mut evm: EVM<SPEC, InMemoryDB>) -> Self::Output {
+                (SPEC::NAME, evm.transact(self.0).unwrap().gas_used)
+            }
+        }
+
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        // SLOAD(0)
+        deploy(&mut db, contract, vec![0x60, 0x00, op::SLOAD]);
+        let tx = Transaction {
+            gas_limit: 100_000,
+            ..call_tx(contract)
+        };
+
+        let run = |name| {
+            let id = SpecId::from_name(name).unwrap();
+            with_spec_id(id, db.clone(), Run(tx.clone()))
+        };
+        let (frontier_name, frontier_gas) = run("frontier");
+        let (berlin_name, berlin_gas) = run("berlin");
+
+        assert_eq!((frontier_name, berlin_name), ("Frontier", "Berlin"));
+        // 同一笔交易在两个规范下 SLOAD 的成本不同
+        assert_eq!(frontier_gas, GAS_TX_BASE + 3 + 200);
+        assert_eq!(berlin_gas, GAS_TX_BASE + 3 + 2100);
+    }
+
+    /// 执行 SELFDESTRUCT(beneficiary)，返回结果和提交的状态变更
+    fn selfdestruct<SPEC: Spec>(
+        mut evm: EVM<SPEC, InMemoryDB>,
+        beneficiary: Address,
+    ) -> (ExecutionResult, Vec<StateChange>) {
+        let contract = addr(0xaa);
+        let mut code = vec![0x73];
+        code.extend_from_slice(beneficiary.as_bytes());
+        code.push(op::SELFDESTRUCT);
+        evm.database_mut().insert_account(
+            contract,
+            AccountInfo {
+                balance: U256::from(100),
+                nonce: 1,
+                code_hash: keccak_hash::keccak(&code),
+                code: Some(code),
+            },
+        );
+
+        let result = evm
+            .transact(Transaction {
+                gas_limit: 100_000,
+                ..call_tx(contract)
+            })
+            .unwrap();
+        (result, evm.call_manager.take_committed_changes())
+    }
+
+    #[test]
+    fn test_selfdestruct_transfers_balance_and_refund_by_spec() {
+        let beneficiary = addr(0xbb);
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            beneficiary,
+            AccountInfo {
+                balance: U256::from(5),
+                ..Default::default()
+            },
+        );
+
+        // Berlin：24000 的退款被 gas_used / 2 封顶
+        let (berlin, changes) = selfdestruct(create_berlin_evm(db.clone()), beneficiary);
+        assert!(berlin.success);
+        // 受益人是冷地址，额外收取 2600
+        let gas_before_refund = GAS_TX_BASE + 3 + 5000 + 2600;
+        assert_eq!(berlin.gas_refunded, gas_before_refund / 2);
+
+        let mut committed = db.clone();
+        committed.commit(changes).unwrap();
+        assert_eq!(
+            committed.basic(beneficiary).unwrap().unwrap().balance,
+            U256::from(105)
+        );
+        assert!(committed.basic(addr(0xaa)).unwrap().is_none());
+
+        // London：EIP-3529 取消了销毁退款
+        let (london, _) = selfdestruct(create_london_evm(db), beneficiary);
+        assert!(london.success);
+        assert_eq!(london.gas_refunded, 0);
+        assert_eq!(london.gas_used, gas_before_refund);
+    }
+
+    #[test]
+    fn test_selfdestruct_refunds_each_address_once() {
+        let parent = addr(0xaa);
+        let child = addr(0xcc);
+        let beneficiary = addr(0xbb);
+
+        // 两次 CALL(gas, child, 0, 0, 0, 0, 0)，子合约每次都执行 SELFDESTRUCT
+        let mut code = Vec::new();
+        for _ in 0..2 {
+            code.extend([0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00]);
+            push_address(&mut code, child);
+            code.extend([op::GAS, op::CALL, op::POP]);
+        }
+        let mut selfdestruct_code = vec![0x73];
+        selfdestruct_code.extend_from_slice(beneficiary.as_bytes());
+        selfdestruct_code.push(op::SELFDESTRUCT);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, code);
+        deploy(&mut db, child, selfdestruct_code);
+
+        let mut evm = create_berlin_evm(db);
+        assert!(evm.transact(call_tx(parent)).unwrap().success);
+        // 两次都执行了：受益人只在第一次是冷地址
+        assert_eq!(
+            evm.machine().gas_report()[&op::SELFDESTRUCT],
+            2 * 5000 + 2600
+        );
+        assert_eq!(evm.machine().refund, Berlin::GAS_SELFDESTRUCT_REFUND);
+    }
+
+    #[test]
+    fn test_selfdestruct_fails_in_static_context() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, parent, staticcall_and_return(child, 0xffff));
+        deploy(&mut db, child, vec![0x60, 0x00, op::SELFDESTRUCT]);
+
+        let result = call(db, parent);
+
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.return_data), U256::zero());
+    }
+
+    /// PUSH20 address
+    fn push_address(code: &mut Vec<u8>, address: Address) {
+        code.push(0x73);
+        code.extend_from_slice(address.as_bytes());
+    }
+
+    #[test]
+    fn test_external_account_opcodes() {
+        let parent = addr(0xaa);
+        let contract = addr(2);
+
+        // MSTORE(0, EXTCODESIZE(contract))
+        let mut code = Vec::new();
+        push_address(&mut code, contract);
+        code.extend([op::EXTCODESIZE, 0x60, 0x00, op::MSTORE]);
+        // EXTCODECOPY(contract, 0x20, 0, 32)
+        code.extend([0x60, 0x20, 0x60, 0x00, 0x60, 0x20]);
+        push_address(&mut code, contract);
+        code.push(op::EXTCODECOPY);
+        // MSTORE(0x40, EXTCODEHASH(contract)); RETURN(0, 0x60)
+        push_address(&mut code, contract);
+        code.extend([op::EXTCODEHASH, 0x60, 0x40, op::MSTORE]);
+        code.extend([0x60, 0x60, 0x60, 0x00, op::RETURN]);
+
+        let mut db = InMemoryDB::with_test_data();
+        deploy(&mut db, parent, code);
+
+        let result = call(db, parent);
+
+        assert!(result.success);
+        let contract_code = [0x60, 0x80, 0x60, 0x40, 0x52];
+        assert_eq!(
+            U256::from_big_endian(&result.return_data[..32]),
+            U256::from(5)
+        );
+        assert_eq!(&result.return_data[32..37], &contract_code);
+        assert!(result.return_data[37..64].iter().all(|byte| *byte == 0));
+        assert_eq!(
+            &result.return_data[64..],
+            keccak_hash::keccak(contract_code).as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_extcodehash_of_missing_and_codeless_accounts() {
+        let hash_of = |address: Address| {
+            let mut code = Vec::new();
+            push_address(&mut code, address);
+            code.push(op::EXTCODEHASH);
+            let mut evm = create_berlin_evm(InMemoryDB::with_test_data());
+            evm.load_code(addr(0xaa), code, 100_000);
+            evm.step().unwrap();
+            evm.step().unwrap();
+            evm.machine().stack.peek(0).unwrap()
+        };
+
+        assert_eq!(hash_of(addr(9)), U256::zero());
+        let empty = keccak_hash::keccak([]);
+        assert_eq!(hash_of(addr(1)), U256::from_big_endian(empty.as_bytes()));
+    }
+
+    /// 连续两次 BALANCE(user)，返回两次的 gas 成本
+    fn balance_costs<SPEC: Spec>(mut evm: EVM<SPEC, InMemoryDB>) -> Vec<u64> {
+        let user = addr(1);
+        let mut code = Vec::new();
+        push_address(&mut code, user);
+        code.push(op::BALANCE);
+        push_address(&mut code, user);
+        code.push(op::BALANCE);
+
+        evm.load_code(addr(0xaa), code, 100_000);
+        let mut costs = Vec::new();
+        for _ in 0..4 {
+            let step = evm.step().unwrap();
+            if step.opcode == op::BALANCE {
+                costs.push(step.gas_used);
+            }
+        }
+        assert_eq!(evm.machine().stack.peek(0), Ok(U256::from(1000)));
+        costs
+    }
+
+    #[test]
+    fn test_balance_cold_then_warm() {
+        assert_eq!(
+            balance_costs(create_berlin_evm(InMemoryDB::with_test_data())),
+            [2600, 100]
+        );
+        assert_eq!(
+            balance_costs(create_frontier_evm(InMemoryDB::with_test_data())),
+            [20, 20]
+        );
+    }
+
+    #[test]
+    fn test_transaction_addresses_start_warm() {
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        // BALANCE(CALLER)、BALANCE(ADDRESS)、BALANCE(0x04)：都按热访问收费
+        deploy(
+            &mut db,
+            contract,
+            vec![
+                op::CALLER,
+                op::BALANCE,
+                op::ADDRESS,
+                op::BALANCE,
+                0x60,
+                0x04,
+                op::BALANCE,
+            ],
+        );
+
+        let result = call(db, contract);
+        assert!(result.success);
+        assert_eq!(result.gas_used, GAS_TX_BASE + 2 * (2 + 100) + 3 + 100);
+    }
+
+    #[test]
+    fn test_depth_limited_call_still_warms_target() {
+        let target = addr(0xbb);
+
+        // CALL(0xffff, target, 0, 0, 0, 0, 0); BALANCE(target)
+        let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00];
+        push_address(&mut code, target);
+        code.extend([0x61, 0xff, 0xff, op::CALL]);
+        push_address(&mut code, target);
+        code.push(op::BALANCE);
+
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.load_code(addr(0xaa), code, 100_000);
+        // 填满调用栈，使下一次 CALL 超过深度限制
+        let frame = CallFrame::new_call(
+            addr(0xaa),
+            addr(0xaa),
+            U256::zero(),
+            Vec::new(),
+            0,
+            CallType::Call,
+            0,
+        );
+        while evm.call_manager.begin_call(frame.clone()).is_ok() {}
+
+        let call = (0..8).map(|_| evm.step().unwrap()).last().unwrap();
+        assert_eq!(call.opcode, op::CALL);
+        assert_eq!(evm.machine().stack.data(), [U256::zero()]);
+
+        evm.step().unwrap();
+        let balance = evm.step().unwrap();
+        assert_eq!(balance.opcode, op::BALANCE);
+        assert_eq!(balance.gas_used, GAS_WARM_ACCESS);
+    }
+
+    #[test]
+    fn test_chainid_gated_by_spec() {
+        use crate::spec::{Berlin, Frontier};
+
+        let env = Environment {
+            chain_id: U256::from(5),
+            ..Default::default()
+        };
+        let code = Bytecode::new(vec![op::CHAINID]);
+
+        let mut berlin = EVM::<Berlin, _>::new(InMemoryDB::new(), env.clone());
+        berlin.machine = Machine::new(1_000);
+        berlin.execute_bytecode(&code).unwrap();
+        assert_eq!(berlin.machine.stack.data(), [U256::from(5)]);
+
+        let mut frontier = EVM::<Frontier, _>::new(InMemoryDB::new(), env);
+        frontier.machine = Machine::new(1_000);
+        assert_eq!(
+            frontier.execute_bytecode(&code).unwrap_err(),
+            Error::InvalidOpcode
+        );
+    }
+
+    #[test]
+    fn test_shift_opcodes_gated_by_spec() {
+        use crate::spec::{Berlin, Frontier};
+
+        // SHL(1, 1) / SHR(1, 1) / SAR(1, 1)
+        for opcode in [op::SHL, op::SHR, op::SAR] {
+            let code = Bytecode::new(vec![0x60, 0x01, 0x60, 0x01, opcode]);
+
+            let mut berlin = create_berlin_evm(InMemoryDB::new());
+            berlin.machine = Machine::new(1_000);
+            assert!(berlin.execute_bytecode(&code).unwrap().0);
+
+            let mut frontier = EVM::<Frontier, _>::new(InMemoryDB::new(), Environment::default());
+            frontier.machine = Machine::new(1_000);
+            assert_eq!(
+                frontier.execute_bytecode(&code).unwrap_err(),
+                Error::InvalidOpcode
+            );
+        }
+        assert_eq!(Frontier::opcode_gas(Opcode::Shl), 0);
+        assert_eq!(Berlin::opcode_gas(Opcode::Shl), 3);
+
+        // BYTE 从 Frontier 起就存在
+        let code = Bytecode::new(vec![0x60, 0xff, 0x60, 0x1f, op::BYTE]);
+        let mut frontier = EVM::<Frontier, _>::new(InMemoryDB::new(), Environment::default());
+        frontier.machine = Machine::new(1_000);
+        frontier.execute_bytecode(&code).unwrap();
+        assert_eq!(frontier.machine.stack.data(), [U256::from(0xff)]);
+    }
+
+    #[test]
+    fn test_block_context_opcodes() {
+        let code = [op::TIMESTAMP, op::NUMBER, op::PREVRANDAO, op::GASLIMIT];
+        let machine = run(&code).unwrap();
+        let env = Environment::default();
+        assert_eq!(
+            machine.stack.data(),
+            [
+                env.block_timestamp,
+                env.block_number,
+                env.block_difficulty,
+                U256::from(env.block_gas_limit),
+            ]
+        );
+
+        // BASEFEE 只在 London 之后可用
+        assert_eq!(run(&[op::BASEFEE]).unwrap_err(), Error::InvalidOpcode);
+        let mut london = create_london_evm(InMemoryDB::new());
+        london.machine = Machine::new(1_000);
+        london
+            .execute_bytecode(&Bytecode::new(vec![op::BASEFEE]))
+            .unwrap();
+        assert_eq!(london.machine.stack.data(), [env.base_fee]);
+    }
+
+    #[test]
+    fn test_selfbalance_gated_by_spec() {
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            contract,
+            AccountInfo {
+                balance: U256::from(1234),
+                ..Default::default()
+            },
+        );
+        let code = Bytecode::new(vec![op::SELFBALANCE]);
+
+        let mut berlin = create_berlin_evm(db.clone());
+        berlin.machine = Machine::new(1_000);
+        berlin.machine.self_address = contract;
+        berlin.execute_bytecode(&code).unwrap();
+        assert_eq!(berlin.machine.stack.data(), [U256::from(1234)]);
+        assert_eq!(berlin.machine.gas, 1_000 - 5);
+
+        let mut frontier = create_frontier_evm(db);
+        frontier.machine = Machine::new(1_000);
+        frontier.machine.self_address = contract;
+        assert_eq!(
+            frontier.execute_bytecode(&code).unwrap_err(),
+            Error::InvalidOpcode
+        );
+    }
+
+    #[test]
+    fn test_sstore_cold_surcharge() {
+        let contract = addr(0xaa);
+        // PUSH1 1 PUSH1 0 SSTORE
+        let store = [0x60, 0x01, 0x60, 0x00, op::SSTORE];
+        let db_with = |times: usize| {
+            let mut db = InMemoryDB::new();
+            deploy(&mut db, contract, store.repeat(times));
+            db
+        };
+
+        // 首次写入：SET 成本 + 冷访问附加成本
+        let result = call(db_with(1), contract);
+        assert_eq!(result.gas_used, GAS_TX_BASE + 6 + 20000 + 2100);
+
+        // 第二次写入同一槽位：已是热槽位且本交易改过，只收热访问成本
+        let result = call(db_with(2), contract);
+        assert_eq!(result.gas_used, GAS_TX_BASE + 6 + 22100 + 6 + 100);
+
+        // Frontier 没有冷/热之分
+        let result = create_frontier_evm(db_with(1))
+            .transact(call_tx(contract))
+            .unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + 6 + 20000);
+    }
+
+    #[test]
+    fn test_sstore_net_metering() {
+        let cost = |original: u64, current: u64, new: u64| {
+            sstore_net_cost::<London>(U256::from(original), U256::from(current), U256::from(new))
+        };
+
+        // 值不变只收热访问成本
+        assert_eq!(cost(1, 1, 1), (100, 0));
+        // 首次修改：SET，或扣除冷访问成本后的 RESET
+        assert_eq!(cost(0, 0, 1), (20000, 0));
+        assert_eq!(cost(1, 1, 2), (2900, 0));
+        assert_eq!(cost(1, 1, 0), (2900, 4800));
+        // 本交易改过的槽位只收热访问成本
+        assert_eq!(cost(1, 2, 3), (100, 0));
+        // 清零后又写入非零值，收回清除退款
+        assert_eq!(cost(1, 0, 2), (100, -4800));
+        // 改回原始值，退还首次修改多收的部分
+        assert_eq!(cost(0, 1, 0), (100, 19900));
+        assert_eq!(cost(1, 2, 1), (100, 2800));
+        assert_eq!(cost(1, 0, 1), (100, -4800 + 2800));
+    }
+
+    #[test]
+    fn test_access_list_slots_start_warm() {
+        let contract = addr(0xaa);
+        let mut db = InMemoryDB::new();
+        // PUSH1 1 PUSH1 0 SSTORE
+        deploy(&mut db, contract, vec![0x60, 0x01, 0x60, 0x00, op::SSTORE]);
+        let tx = |access_list| Transaction {
+            access_list,
+            ..call_tx(contract)
+        };
+        let access_list = vec![(contract, vec![U256::zero()])];
+        let list_gas = GAS_ACCESS_LIST_ADDRESS + GAS_ACCESS_LIST_STORAGE_KEY;
+
+        // 列出的槽位首次写入不收冷访问附加成本，访问列表本身按固有成本收费
+        let result = create_berlin_evm(db.clone())
+            .transact(tx(access_list.clone()))
+            .unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + list_gas + 6 + 20000);
+
+        // 不支持访问列表的规范忽略该字段
+        let result = create_frontier_evm(db)
+            .transact(tx(access_list.clone()))
+            .unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + 6 + 20000);
+
+        // PUSH1 0 SLOAD：列出的槽位按热访问读取，未列出的按冷访问读取
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, vec![0x60, 0x00, op::SLOAD]);
+        let result = create_berlin_evm(db.clone())
+            .transact(tx(access_list))
+            .unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + list_gas + 3 + 100);
+        let result = create_berlin_evm(db).transact(tx(Vec::new())).unwrap();
+        assert_eq!(result.gas_used, GAS_TX_BASE + 3 + 2100);
+    }
+
+    #[test]
+    fn test_step_through_program() {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        // PUSH1 2 PUSH1 3 MUL
+        evm.load_code(
+            Address::zero(),
+            vec![0x60, 0x02, 0x60, 0x03, op::MUL],
+            1_000,
+        );
+
+        let step = evm.step().unwrap();
+        assert_eq!(
+            step,
+            StepResult {
+                opcode: op::PUSH1,
+                gas_used: 3,
+                should_continue: true,
+            }
+        );
+        assert_eq!(evm.machine().stack.data(), [U256::from(2)]);
+        assert_eq!(evm.machine().pc, 2);
+
+        evm.step().unwrap();
+        assert_eq!(evm.machine().stack.data(), [U256::from(2), U256::from(3)]);
+
+        let step = evm.step().unwrap();
+        assert_eq!((step.opcode, step.gas_used), (op::MUL, 5));
+        assert_eq!(evm.machine().stack.data(), [U256::from(6)]);
+
+        // 代码末尾视为 STOP
+        let step = evm.step().unwrap();
+        assert_eq!(step.opcode, op::STOP);
+        assert!(!step.should_continue);
+    }
+
+    #[test]
+    fn test_instruction_results() {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.machine = Machine::new(1_000);
+
+        // PUSH1 1 STOP：先继续执行，然后正常停止
+        let code = Bytecode::new(vec![0x60, 0x01, op::STOP]);
+        assert_eq!(evm.execute_opcode(&code), InstructionResult::Continue);
+        assert_eq!(evm.execute_opcode(&code), InstructionResult::Stop);
+
+        // REVERT 不是故障
+        evm.machine = Machine::new(1_000);
+        let code = Bytecode::new(vec![0x60, 0x00, 0x60, 0x00, op::REVERT]);
+        let results: Vec<_> = (0..3).map(|_| evm.execute_opcode(&code)).collect();
+        assert_eq!(results[2], InstructionResult::Revert(Vec::new()));
+
+        // 未定义的操作码是故障
+        evm.machine = Machine::new(1_000);
+        let code = Bytecode::new(vec![0x0c]);
+        assert_eq!(
+            evm.execute_opcode(&code),
+            InstructionResult::Fatal(Error::InvalidOpcode)
+        );
+    }
+
+    #[test]
+    fn test_step_reports_error() {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.load_code(Address::zero(), vec![op::ADD], 1_000);
+        assert_eq!(evm.step(), Err(Error::StackUnderflow));
+    }
+
+    #[test]
+    fn test_stack_height_checked_before_dispatch() {
+        // 只有一个元素时 ADD 在弹栈之前就失败，栈保持不变
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.load_code(Address::zero(), vec![0x60, 0x01, op::ADD], 1_000);
+        evm.step().unwrap();
+        assert_eq!(evm.step(), Err(Error::StackUnderflow));
+        assert_eq!(evm.machine().stack.data(), [U256::one()]);
+
+        // POP 丢弃栈顶，空栈时下溢
+        let machine = run(&[0x60, 0x01, 0x60, 0x02, op::POP]).unwrap();
+        assert_eq!(machine.stack.data(), [U256::one()]);
+        assert_eq!(machine.gas, 1_000_000 - 8);
+        assert_eq!(run(&[op::POP]).unwrap_err(), Error::StackUnderflow);
+
+        // 栈满时 ADD 净减少一个元素，可以执行；PUSH 在分发前溢出
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.machine = Machine::with_stack_limit(1_000, 2);
+        let code = Bytecode::new(vec![
+            0x60,
+            0x01,
+            0x60,
+            0x02,
+            op::ADD,
+            0x60,
+            0x03,
+            0x60,
+            0x04,
+        ]);
+        assert_eq!(evm.execute_bytecode(&code), Err(Error::StackOverflow));
+        assert_eq!(evm.machine.stack.data(), [U256::from(3), U256::from(3)]);
+    }
+
+    #[test]
+    fn test_gas_opcode() {
+        let machine = run(&[op::GAS]).unwrap();
+        assert_eq!(machine.stack.data(), [U256::from(1_000_000 - 2)]);
+    }
+
+    #[test]
+    fn test_pc_msize_and_gas_after_jump() {
+        // JUMPDEST 位于偏移 5，其后的 PC 位于偏移 6
+        let code = assemble(
+            "
+            @dest JUMP INVALID
+            dest: PC
+            PUSH1 0 PUSH1 64 MSTORE MSIZE
+            GAS
+            ",
+        )
+        .unwrap();
+        let machine = run(&code).unwrap();
+
+        // PUSH2 3 + JUMP 8 + JUMPDEST 1 + PC 2 + PUSH1 3 * 2 + MSTORE 3
+        // + 3 个字的内存 9 + MSIZE 2 + GAS 2
+        let spent = 3 + 8 + 1 + 2 + 3 * 2 + 3 + 9 + 2 + 2;
+        assert_eq!(
+            machine.stack.data(),
+            [U256::from(6), U256::from(96), U256::from(1_000_000 - spent)]
+        );
+    }
+
+    #[test]
+    fn test_gas_report_finds_mul_hotspot() {
+        // PUSH1 2，然后 10 次 PUSH1 3 MUL
+        let mut code = vec![0x60, 0x02];
+        for _ in 0..10 {
+            code.extend([0x60, 0x03, op::MUL]);
+        }
+
+        let report = run(&code).unwrap().gas_report();
+        assert_eq!(report[&op::MUL], 50);
+        assert_eq!(report[&op::PUSH1], 33);
+        let hottest = report.iter().max_by_key(|(_, gas)| **gas).unwrap();
+        assert_eq!(*hottest.0, op::MUL);
+    }
+
+    #[test]
+    fn test_call_charges_cold_then_warm_access() {
+        let contract = addr(0xaa);
+        let target = addr(0xbb);
+        // 两次 STATICCALL(0xffff, target, 0, 0, 0, 0)，目标没有代码
+        let mut code = Vec::new();
+        for _ in 0..2 {
+            code.extend([0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73]);
+            code.extend_from_slice(target.as_bytes());
+            code.extend([0x61, 0xff, 0xff, op::STATICCALL, op::POP]);
+        }
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, contract, code);
+
+        let mut evm = create_berlin_evm(db.clone());
+        evm.transact(call_tx(contract)).unwrap();
+        assert_eq!(evm.machine().gas_report()[&op::STATICCALL], 2600 + 100);
+
+        // Frontier 没有冷/热之分
+        let mut evm = create_frontier_evm(db);
+        evm.transact(call_tx(contract)).unwrap();
+        assert_eq!(evm.machine().gas_report()[&op::STATICCALL], 2 * 40);
+    }
+
+    #[test]
+    fn test_gas_report_includes_nested_calls_once() {
+        let proxy = addr(0xaa);
+        let implementation = addr(0xbb);
+
+        // DELEGATECALL(0xffff, implementation, 0, 0, 0, 0)
+        let mut proxy_code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+        proxy_code.extend_from_slice(implementation.as_bytes());
+        proxy_code.extend([0x61, 0xff, 0xff, op::DELEGATECALL]);
+
+        let mut db = InMemoryDB::new();
+        deploy(&mut db, proxy, proxy_code);
+        deploy(
+            &mut db,
+            implementation,
+            vec![0x60, 0x02, 0x60, 0x03, op::MUL],
+        );
+
+        let mut evm = create_berlin_evm(db);
+        let result = evm.transact(call_tx(proxy)).unwrap();
+
+        let report = evm.machine().gas_report();
+        assert_eq!(report[&op::MUL], 5);
+        // 实现合约是冷地址
+        assert_eq!(report[&op::DELEGATECALL], 2600);
+        // 报告总和等于解释器内消耗的 gas（交易的固有成本不经过解释器）
+        let total: u64 = report.values().sum();
+        assert_eq!(total, result.gas_used - GAS_TX_BASE);
+    }
+
+    #[test]
+    fn test_max_call_gas_keeps_one_64th() {
+        assert_eq!(max_call_gas(6400), 6300);
+        assert_eq!(max_call_gas(63), 63);
+        assert_eq!(max_call_gas(0), 0);
+    }
+
+    #[test]
+    fn test_static_gas_estimate_simple_sequence() {
+        use crate::spec::Berlin;
+
+        // PUSH1 1 PUSH1 2 ADD STOP：3 + 3 + 3 + 0
+        let code = [0x60, 0x01, 0x60, 0x02, op::ADD, op::STOP];
+        assert_eq!(static_gas_estimate::<Berlin>(&code), 9);
+
+        // 实际执行消耗的 gas 与估算一致
+        let machine = run(&code).unwrap();
+        assert_eq!(1_000_000 - machine.gas, 9);
+
+        // PUSH 的立即数不会被当作指令（0x55 = SSTORE）
+        assert_eq!(static_gas_estimate::<Berlin>(&[0x60, 0x55]), 3);
+    }
+
+    #[test]
+    fn test_static_gas_estimate_is_lower_bound() {
+        use crate::spec::{Berlin, Frontier};
+
+        // SSTORE(0, 1) 在 Berlin 按热访问成本、在 Frontier 按 RESET 计算，
+        // 实际执行为 SET + 冷访问附加费
+        let code = [0x60, 0x01, 0x60, 0x00, op::SSTORE];
+        assert_eq!(static_gas_estimate::<Berlin>(&code), 3 + 3 + 100);
+        assert_eq!(static_gas_estimate::<Frontier>(&code), 3 + 3 + 5000);
+
+        let machine = run(&code).unwrap();
+        assert!(1_000_000 - machine.gas > static_gas_estimate::<Berlin>(&code));
+
+        // 规范不支持的指令不计入
+        assert_eq!(static_gas_estimate::<Frontier>(&[op::CHAINID]), 0);
+        assert_eq!(static_gas_estimate::<Berlin>(&[op::CHAINID]), 2);
+    }
+
+    #[test]
+    fn test_return_memory() {
+        // MSTORE(0, 0x2a); RETURN(0, 32)
+        let code = [
+            0x60,
+            0x2a,
+            0x60,
+            0x00,
+            op::MSTORE,
+            0x60,
+            0x20,
+            0x60,
+            0x00,
+            op::RETURN,
+        ];
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        evm.machine = Machine::new(1_000_000);
+        let (success, output) = evm.execute_bytecode(&Bytecode::new(code.to_vec())).unwrap();
+        assert!(success);
+        assert_eq!(U256::from_big_endian(&output), U256::from(0x2a));
+    }
+}
@@ -1,5 +1,22 @@
 pub mod call_stack;
 pub mod engine;
+pub mod handler;
+pub mod inspector;
+pub mod interpreter;
+pub mod precompile;
+pub mod stack;
+pub mod tracer;
 
 pub use call_stack::*;
 pub use engine::*;
+pub use handler::{ExecutionContext, OpcodeHandler, OpcodeHandlerTable};
+pub use inspector::{Inspector, StorageAccess, StorageTracer};
+pub use interpreter::{static_gas_estimate, StepResult};
+pub use precompile::{
+    EcRecover, Identity, Modexp, Precompile, PrecompileRegistry, Ripemd160, Sha256,
+};
+pub use stack::Stack;
+pub use tracer::{
+    GasCategory, GasCategoryProfiler, JsonTracer, OpcodeSequenceRecorder, StackSnapshotTracer,
+    TraceStep, Tracer,
+};
@@ -0,0 +1,497 @@
+use crate::models::modexp::modexp;
+use crate::models::ripemd160::ripemd160;
+use crate::models::secp256k1;
+use crate::models::sha256::sha256;
+use crate::models::*;
+use crate::spec::Spec;
+use ethereum_types::{Address, U256};
+use std::collections::HashMap;
+
+/// ECRECOVER (0x01) 的固定 gas
+const ECRECOVER_GAS: u64 = 3000;
+
+/// SHA256 (0x02) 的 gas：60 + 12 * 字数
+const SHA256_BASE: u64 = 60;
+const SHA256_PER_WORD: u64 = 12;
+
+/// RIPEMD160 (0x03) 的 gas：600 + 120 * 字数
+const RIPEMD160_BASE: u64 = 600;
+const RIPEMD160_PER_WORD: u64 = 120;
+
+/// MODEXP (0x05) 在 EIP-2565 下的最低 gas
+const MODEXP_MIN_GAS: u64 = 200;
+
+/// IDENTITY (0x04) 的 gas：15 + 3 * 字数
+const IDENTITY_BASE: u64 = 15;
+const IDENTITY_PER_WORD: u64 = 3;
+
+/// 将地址解析为预编译合约编号
+///
+/// 预编译合约位于 0x00..00XX，前 19 字节必须全为 0。
+pub fn precompile_id(address: Address) -> Option<u8> {
+    let bytes = address.as_bytes();
+    if bytes[..19].iter().all(|b| *b == 0) && bytes[19] != 0 {
+        Some(bytes[19])
+    } else {
+        None
+    }
+}
+
+/// 预编译合约
+///
+/// 返回 (输出数据, 消耗的 gas)，gas 不足时返回 `Error::OutOfGas`。
+pub trait Precompile {
+    fn run(&self, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error>;
+}
+
+/// ECRECOVER (0x01)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EcRecover;
+
+impl Precompile for EcRecover {
+    fn run(&self, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+        ecrecover(input, gas_limit)
+    }
+}
+
+/// SHA256 (0x02)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256;
+
+impl Precompile for Sha256 {
+    fn run(&self, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+        let cost = word_cost(SHA256_BASE, SHA256_PER_WORD, input.len());
+        if cost > gas_limit {
+            return Err(Error::OutOfGas);
+        }
+        Ok((sha256(input).to_vec(), cost))
+    }
+}
+
+/// RIPEMD160 (0x03)：输出左侧补 0 到 32 字节
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ripemd160;
+
+impl Precompile for Ripemd160 {
+    fn run(&self, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+        let cost = word_cost(RIPEMD160_BASE, RIPEMD160_PER_WORD, input.len());
+        if cost > gas_limit {
+            return Err(Error::OutOfGas);
+        }
+        let mut output = vec![0u8; 12];
+        output.extend_from_slice(&ripemd160(input));
+        Ok((output, cost))
+    }
+}
+
+/// IDENTITY (0x04)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl Precompile for Identity {
+    fn run(&self, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+        identity(input, gas_limit)
+    }
+}
+
+/// MODEXP (0x05)：任意精度的模幂运算
+///
+/// 输入为三个 32 字节的长度 (base_len, exp_len, mod_len)，后接大端编码的
+/// base、exponent、modulus，不足部分补 0。输出长度为 mod_len。
+#[derive(Debug, Clone, Copy)]
+pub struct Modexp {
+    /// 使用 EIP-2565 (Berlin) 的 gas 公式，否则使用 EIP-198 的原始公式
+    pub eip2565: bool,
+}
+
+impl Precompile for Modexp {
+    fn run(&self, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+        let base_len = header_len(input, 0);
+        let exp_len = header_len(input, 1);
+        let mod_len = header_len(input, 2);
+
+        // 指数的前 32 字节决定调整后的指数长度
+        let exp_offset = 96u64.saturating_add(base_len);
+        let exp_head = U256::from_big_endian(&padded(input, exp_offset, exp_len.min(32)));
+        let cost = modexp_gas(base_len, exp_len, mod_len, exp_head, self.eip2565);
+        if cost > gas_limit {
+            return Err(Error::OutOfGas);
+        }
+        if mod_len == 0 {
+            return Ok((Vec::new(), cost));
+        }
+
+        // gas 检查之后各长度都已足够小
+        let base = padded(input, 96, base_len);
+        let exponent = padded(input, exp_offset, exp_len);
+        let modulus = padded(input, exp_offset + exp_len, mod_len);
+        Ok((modexp(&base, &exponent, &modulus), cost))
+    }
+}
+
+/// 读取 MODEXP 头部的第 `index` 个长度，超过 u64 时按 u64::MAX 处理
+fn header_len(input: &[u8], index: u64) -> u64 {
+    let value = U256::from_big_endian(&padded(input, index * 32, 32));
+    if value > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        value.as_u64()
+    }
+}
+
+/// 从 `offset` 处读取 `len` 字节，超出输入的部分补 0
+fn padded(input: &[u8], offset: u64, len: u64) -> Vec<u8> {
+    let mut data = vec![0u8; len as usize];
+    if offset < input.len() as u64 {
+        let available = &input[offset as usize..];
+        let n = available.len().min(data.len());
+        data[..n].copy_from_slice(&available[..n]);
+    }
+    data
+}
+
+/// MODEXP 的 gas（EIP-198 / EIP-2565）
+fn modexp_gas(base_len: u64, exp_len: u64, mod_len: u64, exp_head: U256, eip2565: bool) -> u64 {
+    let head_bits = exp_head.bits().saturating_sub(1) as u128;
+    let adjusted_exp_len = if exp_len <= 32 {
+        head_bits
+    } else {
+        8 * (exp_len as u128 - 32) + head_bits
+    };
+    let iterations = adjusted_exp_len.max(1);
+
+    let max_len = base_len.max(mod_len) as u128;
+    let gas = if eip2565 {
+        let words = max_len.div_ceil(8);
+        (words.saturating_mul(words).saturating_mul(iterations) / 3).max(MODEXP_MIN_GAS as u128)
+    } else {
+        let complexity = if max_len <= 64 {
+            max_len * max_len
+        } else if max_len <= 1024 {
+            max_len * max_len / 4 + 96 * max_len - 3072
+        } else {
+            (max_len.saturating_mul(max_len) / 16).saturating_add(480 * max_len) - 199_680
+        };
+        complexity.saturating_mul(iterations) / 20
+    };
+    gas.min(u64::MAX as u128) as u64
+}
+
+/// 预编译合约注册表：编号 -> 实现
+///
+/// 地址是否为预编译合约由 `SPEC::precompiles()` 决定，注册表只负责执行。
+#[derive(Default)]
+pub struct PrecompileRegistry {
+    precompiles: HashMap<u8, Box<dyn Precompile>>,
+}
+
+impl PrecompileRegistry {
+    /// 空注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 包含本 crate 已实现的全部预编译合约，按最新规范计费
+    pub fn standard() -> Self {
+        let mut registry = Self::new();
+        registry.register(0x01, EcRecover);
+        registry.register(0x02, Sha256);
+        registry.register(0x03, Ripemd160);
+        registry.register(0x04, Identity);
+        registry.register(0x05, Modexp { eip2565: true });
+        registry
+    }
+
+    /// 按规范调整 gas 公式的标准注册表
+    pub fn for_spec<SPEC: Spec>() -> Self {
+        let mut registry = Self::standard();
+        registry.register(
+            0x05,
+            Modexp {
+                eip2565: SPEC::ENABLE_EIP2565,
+            },
+        );
+        registry
+    }
+
+    /// 注册预编译合约，替换同一编号已有的实现
+    pub fn register<P: Precompile + 'static>(&mut self, id: u8, precompile: P) {
+        self.precompiles.insert(id, Box::new(precompile));
+    }
+
+    /// 查找预编译合约的实现
+    pub fn get(&self, id: u8) -> Option<&dyn Precompile> {
+        self.precompiles
+            .get(&id)
+            .map(|precompile| precompile.as_ref())
+    }
+
+    /// 执行预编译合约，尚未实现的编号返回 `Error::InvalidOpcode`
+    pub fn run(&self, id: u8, input: &[u8], gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+        self.get(id)
+            .ok_or(Error::InvalidOpcode)?
+            .run(input, gas_limit)
+    }
+}
+
+impl std::fmt::Debug for PrecompileRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ids: Vec<_> = self.precompiles.keys().copied().collect();
+        ids.sort_unstable();
+        f.debug_struct("PrecompileRegistry")
+            .field("ids", &ids)
+            .finish()
+    }
+}
+
+/// 用标准注册表执行预编译合约
+///
+/// 返回 (输出数据, 消耗的 gas)。gas 不足时返回 `Error::OutOfGas`，
+/// 尚未实现的预编译合约返回 `Error::InvalidOpcode`。
+pub fn run_precompile(address: u8, input: &[u8], gas: u64) -> Result<(Vec<u8>, u64), Error> {
+    PrecompileRegistry::standard().run(address, input, gas)
+}
+
+/// 按 32 字节字数计费
+fn word_cost(base: u64, per_word: u64, len: usize) -> u64 {
+    base + per_word * (len as u64).div_ceil(32)
+}
+
+/// ECRECOVER (0x01)：从签名中恢复签名者地址
+///
+/// 输入按 128 字节解析为 (hash, v, r, s)，不足部分补 0。
+/// 成功时输出左侧补 0 到 32 字节的地址；签名无效时输出为空，调用本身仍然成功。
+fn ecrecover(input: &[u8], gas: u64) -> Result<(Vec<u8>, u64), Error> {
+    if ECRECOVER_GAS > gas {
+        return Err(Error::OutOfGas);
+    }
+
+    let mut data = [0u8; 128];
+    let len = input.len().min(128);
+    data[..len].copy_from_slice(&input[..len]);
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&data[..32]);
+    let v = U256::from_big_endian(&data[32..64]);
+    let r = U256::from_big_endian(&data[64..96]);
+    let s = U256::from_big_endian(&data[96..128]);
+
+    // v 只能是 27 或 28
+    let recovery_id = if v == U256::from(27) {
+        0
+    } else if v == U256::from(28) {
+        1
+    } else {
+        return Ok((Vec::new(), ECRECOVER_GAS));
+    };
+
+    let output = match secp256k1::recover(&hash, recovery_id, r, s) {
+        Some(address) => {
+            let mut output = vec![0u8; 12];
+            output.extend_from_slice(address.as_bytes());
+            output
+        }
+        None => Vec::new(),
+    };
+    Ok((output, ECRECOVER_GAS))
+}
+
+/// IDENTITY (0x04)：原样返回输入数据
+fn identity(input: &[u8], gas: u64) -> Result<(Vec<u8>, u64), Error> {
+    let cost = word_cost(IDENTITY_BASE, IDENTITY_PER_WORD, input.len());
+    if cost > gas {
+        return Err(Error::OutOfGas);
+    }
+    Ok((input.to_vec(), cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDB;
+    use crate::evm::create_berlin_evm;
+    use crate::test_utils::{addr, call_tx};
+
+    /// 已知签名：签名者为 0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b
+    const SIGNED_INPUT: &str = concat!(
+        "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c",
+        "000000000000000000000000000000000000000000000000000000000000001c",
+        "73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f",
+        "eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549",
+    );
+
+    #[test]
+    fn test_precompile_id() {
+        assert_eq!(precompile_id(Address::from_low_u64_be(4)), Some(4));
+        assert_eq!(precompile_id(Address::zero()), None);
+        assert_eq!(precompile_id(addr(4)), None);
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = PrecompileRegistry::standard();
+        assert!(registry.get(0x01).is_some());
+        assert!(registry.get(0x04).is_some());
+        assert!(registry.get(0x02).is_some() && registry.get(0x03).is_some());
+        assert_eq!(registry.run(0x06, &[], 1000), Err(Error::InvalidOpcode));
+
+        // 自定义实现可以替换标准实现
+        struct Empty;
+        impl Precompile for Empty {
+            fn run(&self, _input: &[u8], _gas_limit: u64) -> Result<(Vec<u8>, u64), Error> {
+                Ok((Vec::new(), 1))
+            }
+        }
+        let mut registry = PrecompileRegistry::standard();
+        registry.register(0x04, Empty);
+        assert_eq!(registry.run(0x04, &[1, 2, 3], 100), Ok((Vec::new(), 1)));
+    }
+
+    #[test]
+    fn test_identity_gas() {
+        let (output, gas) = run_precompile(0x04, &[1, 2, 3], 100).unwrap();
+        assert_eq!(output, vec![1, 2, 3]);
+        assert_eq!(gas, 18);
+
+        // 33 字节需要 2 个字
+        let (_, gas) = run_precompile(0x04, &[0u8; 33], 100).unwrap();
+        assert_eq!(gas, 21);
+
+        assert_eq!(run_precompile(0x04, &[0u8; 33], 20), Err(Error::OutOfGas));
+    }
+
+    #[test]
+    fn test_ecrecover_known_signature() {
+        let input = hex::decode(SIGNED_INPUT).unwrap();
+        let (output, gas) = run_precompile(0x01, &input, 5000).unwrap();
+
+        assert_eq!(gas, 3000);
+        assert_eq!(
+            hex::encode(output),
+            "000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b"
+        );
+    }
+
+    #[test]
+    fn test_ecrecover_malformed_input_returns_empty() {
+        // v = 29 不是合法的恢复标识
+        let mut input = hex::decode(SIGNED_INPUT).unwrap();
+        input[63] = 0x1d;
+        assert_eq!(run_precompile(0x01, &input, 5000), Ok((Vec::new(), 3000)));
+
+        // 空输入：r = s = 0
+        assert_eq!(run_precompile(0x01, &[], 5000), Ok((Vec::new(), 3000)));
+
+        assert_eq!(run_precompile(0x01, &input, 2999), Err(Error::OutOfGas));
+    }
+
+    #[test]
+    fn test_hash_precompiles() {
+        let (output, gas) = run_precompile(0x02, b"abc", 1000).unwrap();
+        assert_eq!(gas, 60 + 12);
+        assert_eq!(
+            hex::encode(output),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let (output, gas) = run_precompile(0x03, b"abc", 1000).unwrap();
+        assert_eq!(gas, 600 + 120);
+        assert_eq!(
+            hex::encode(output),
+            "0000000000000000000000008eb208f7e05d987a9b044a8e98c6b087f15a0bfc"
+        );
+
+        // 33 字节按 2 个字计费
+        assert_eq!(run_precompile(0x02, &[0u8; 33], 84).unwrap().1, 84);
+        assert_eq!(run_precompile(0x02, &[0u8; 33], 83), Err(Error::OutOfGas));
+        assert_eq!(run_precompile(0x03, &[0u8; 33], 839), Err(Error::OutOfGas));
+    }
+
+    /// 编码 MODEXP 输入：三个 32 字节长度后接各操作数
+    fn modexp_input(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for operand in [base, exponent, modulus] {
+            let mut len = [0u8; 32];
+            U256::from(operand.len()).to_big_endian(&mut len);
+            input.extend(len);
+        }
+        for operand in [base, exponent, modulus] {
+            input.extend_from_slice(operand);
+        }
+        input
+    }
+
+    #[test]
+    fn test_modexp_precompile() {
+        use crate::spec::{Berlin, Frontier};
+
+        // 3^5 mod 7 = 5，EIP-2565 最低 200 gas
+        let input = modexp_input(&[3], &[5], &[7]);
+        assert_eq!(run_precompile(0x05, &input, 1000), Ok((vec![5], 200)));
+
+        // 指数为 0：结果为 1；模数长度为 0：输出为空
+        assert_eq!(
+            run_precompile(0x05, &modexp_input(&[3], &[0], &[0, 7]), 1000)
+                .unwrap()
+                .0,
+            vec![0, 1]
+        );
+        assert_eq!(
+            run_precompile(0x05, &modexp_input(&[3], &[5], &[]), 1000),
+            Ok((Vec::new(), 200))
+        );
+
+        // 64 字节的底数和模数，指数为 2^256 - 1：调整后的指数长度为 255
+        let input = modexp_input(&[2; 64], &[0xff; 32], &[0x7f; 64]);
+        let berlin = PrecompileRegistry::for_spec::<Berlin>();
+        assert_eq!(
+            berlin.run(0x05, &input, 100_000).unwrap().1,
+            8 * 8 * 255 / 3
+        );
+        let frontier = PrecompileRegistry::for_spec::<Frontier>();
+        assert_eq!(
+            frontier.run(0x05, &input, 100_000).unwrap().1,
+            64 * 64 * 255 / 20
+        );
+        assert_eq!(frontier.run(0x05, &input, 52_223), Err(Error::OutOfGas));
+    }
+
+    #[test]
+    fn test_call_identity_precompile() {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        let input = b"hello identity precompile, forty bytes!!".to_vec();
+
+        let result = evm
+            .transact(Transaction {
+                data: input.clone(),
+                gas_limit: 100_000,
+                ..call_tx(Address::from_low_u64_be(4))
+            })
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.return_data, input);
+        // 交易固有成本（40 个非零字节）+ 15 + 3 * 2
+        assert_eq!(result.gas_used, GAS_TX_BASE + 40 * 16 + 21);
+    }
+
+    #[test]
+    fn test_call_ecrecover_precompile() {
+        let mut evm = create_berlin_evm(InMemoryDB::new());
+        let input = hex::decode(SIGNED_INPUT).unwrap();
+        let tx = Transaction {
+            data: input,
+            gas_limit: 100_000,
+            ..call_tx(Address::from_low_u64_be(1))
+        };
+        let intrinsic = tx.intrinsic_gas::<crate::spec::Berlin>();
+
+        let result = evm.transact(tx).unwrap();
+        assert!(result.success);
+        assert_eq!(
+            Address::from_slice(&result.return_data[12..]),
+            Address::from_slice(&hex::decode("a94f5374fce5edbc8e2a8697c15331677e6ebf0b").unwrap())
+        );
+        assert_eq!(result.gas_used, intrinsic + 3000);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::models::*;
+use ethereum_types::U256;
+
+/// 默认栈深度上限（所有已知规范均为 1024）
+pub const STACK_LIMIT: usize = 1024;
+
+/// EVM 执行栈
+///
+/// 封装 `Vec<U256>`，在每次操作时统一检查上溢和下溢，
+/// 指令实现无需再手动判断栈深度。深度上限在构造时传入，
+/// 通常取 `SPEC::STACK_LIMIT`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    data: Vec<U256>,
+    limit: usize,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new(STACK_LIMIT)
+    }
+}
+
+impl Stack {
+    /// 创建指定深度上限的空栈
+    pub fn new(limit: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            limit,
+        }
+    }
+
+    /// 推入值，超过深度上限返回 `Error::StackOverflow`
+    pub fn push(&mut self, value: U256) -> Result<(), Error> {
+        if self.data.len() >= self.limit {
+            return Err(Error::StackOverflow);
+        }
+        self.data.push(value);
+        Ok(())
+    }
+
+    /// 弹出栈顶值
+    pub fn pop(&mut self) -> Result<U256, Error> {
+        self.data.pop().ok_or(Error::StackUnderflow)
+    }
+
+    /// 查看从栈顶数起第 n 个值（n = 0 为栈顶），不弹出
+    pub fn peek(&self, n: usize) -> Result<U256, Error> {
+        self.data
+            .len()
+            .checked_sub(n + 1)
+            .map(|index| self.data[index])
+            .ok_or(Error::StackUnderflow)
+    }
+
+    /// DUPn：复制从栈顶数起第 n 个值（n 从 1 开始）并推入栈顶
+    pub fn dup(&mut self, n: usize) -> Result<(), Error> {
+        let value = self.peek(n - 1)?;
+        self.push(value)
+    }
+
+    /// SWAPn：交换栈顶与其下方第 n 个值（n 从 1 开始）
+    pub fn swap(&mut self, n: usize) -> Result<(), Error> {
+        let top = self
+            .data
+            .len()
+            .checked_sub(1)
+            .ok_or(Error::StackUnderflow)?;
+        let other = top.checked_sub(n).ok_or(Error::StackUnderflow)?;
+        self.data.swap(top, other);
+        Ok(())
+    }
+
+    /// 当前栈深度
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// 栈是否为空
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 栈深度上限
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// 栈中的所有值（从栈底到栈顶）
+    pub fn data(&self) -> &[U256] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_stack() -> Stack {
+        let mut stack = Stack::default();
+        for i in 0..STACK_LIMIT {
+            stack.push(U256::from(i)).unwrap();
+        }
+        stack
+    }
+
+    #[test]
+    fn test_push_up_to_limit() {
+        let mut stack = full_stack();
+        assert_eq!(stack.len(), 1024);
+
+        // 第 1025 个值溢出，栈保持不变
+        assert_eq!(stack.push(U256::one()), Err(Error::StackOverflow));
+        assert_eq!(stack.len(), 1024);
+
+        // 弹出一个后可以再次推入
+        stack.pop().unwrap();
+        assert!(stack.push(U256::one()).is_ok());
+    }
+
+    #[test]
+    fn test_dup_at_limit_overflows() {
+        let mut stack = full_stack();
+        assert_eq!(stack.dup(1), Err(Error::StackOverflow));
+
+        // SWAP 不改变深度，满栈时仍然可用
+        stack.swap(16).unwrap();
+        assert_eq!(stack.peek(0), Ok(U256::from(1023 - 16)));
+        assert_eq!(stack.peek(16), Ok(U256::from(1023)));
+    }
+
+    #[test]
+    fn test_underflow() {
+        let mut stack = Stack::default();
+        assert_eq!(stack.pop(), Err(Error::StackUnderflow));
+        assert_eq!(stack.peek(0), Err(Error::StackUnderflow));
+        assert_eq!(stack.dup(1), Err(Error::StackUnderflow));
+
+        stack.push(U256::one()).unwrap();
+        assert_eq!(stack.swap(1), Err(Error::StackUnderflow));
+        assert_eq!(stack.dup(2), Err(Error::StackUnderflow));
+    }
+
+    #[test]
+    fn test_peek_dup_swap() {
+        let mut stack = Stack::default();
+        for i in 1..=3 {
+            stack.push(U256::from(i)).unwrap();
+        }
+
+        assert_eq!(stack.peek(0), Ok(U256::from(3)));
+        assert_eq!(stack.peek(2), Ok(U256::from(1)));
+
+        stack.dup(3).unwrap();
+        assert_eq!(stack.data(), [1, 2, 3, 1].map(U256::from));
+
+        stack.swap(2).unwrap();
+        assert_eq!(stack.data(), [1, 1, 3, 2].map(U256::from));
+    }
+
+    #[test]
+    fn test_custom_limit() {
+        let mut stack = Stack::new(2);
+        stack.push(U256::one()).unwrap();
+        stack.push(U256::one()).unwrap();
+        assert_eq!(stack.push(U256::one()), Err(Error::StackOverflow));
+        assert_eq!(stack.limit(), 2);
+    }
+}
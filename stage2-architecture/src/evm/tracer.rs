@@ -0,0 +1,522 @@
+use crate::models::op;
+use crate::models::Opcode;
+use ethereum_types::U256;
+use std::any::Any;
+use std::collections::HashMap;
+
+/// 逐步执行追踪器
+///
+/// 解释器在每条指令执行前调用 `step`，执行后调用 `step_end` 报告本步的 gas 成本。
+/// 子调用中的指令会嵌套在调用指令的 `step` 和 `step_end` 之间。
+pub trait Tracer: Any {
+    /// 指令执行前调用
+    ///
+    /// - `pc`：指令位置
+    /// - `opcode`：操作码
+    /// - `gas_remaining`：执行前的剩余 gas
+    /// - `stack`：执行前的栈（从栈底到栈顶）
+    /// - `depth`：调用深度，最外层为 1
+    fn step(&mut self, pc: usize, opcode: u8, gas_remaining: u64, stack: &[U256], depth: usize);
+
+    /// 指令执行后调用
+    ///
+    /// - `gas_cost`：本步消耗的 gas（调用指令包含子调用的消耗）
+    /// - `stack`：执行后的栈（从栈底到栈顶）
+    fn step_end(&mut self, _gas_cost: u64, _stack: &[U256]) {}
+}
+
+/// 一条追踪记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub pc: usize,
+    pub op: u8,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub stack: Vec<U256>,
+    pub depth: usize,
+}
+
+impl TraceStep {
+    /// 按 EIP-3155 的字段名输出一行 JSON
+    pub fn to_json(&self) -> String {
+        let stack = self
+            .stack
+            .iter()
+            .map(|value| format!("\"{:#x}\"", value))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"pc\":{},\"op\":{},\"gas\":\"{:#x}\",\"gasCost\":\"{:#x}\",\"stack\":[{}],\"depth\":{}}}",
+            self.pc, self.op, self.gas, self.gas_cost, stack, self.depth
+        )
+    }
+}
+
+/// EIP-3155 风格的 JSON 追踪器
+///
+/// 每条指令对应一个 JSON 对象，可以逐行与 go-ethereum 的
+/// `evm --json` 输出对比。
+#[derive(Debug, Clone, Default)]
+pub struct JsonTracer {
+    steps: Vec<TraceStep>,
+    /// 尚未收到 step_end 的记录下标（子调用会嵌套）
+    pending: Vec<usize>,
+}
+
+impl JsonTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 所有追踪记录
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    /// 每步一行 JSON
+    pub fn lines(&self) -> Vec<String> {
+        self.steps.iter().map(TraceStep::to_json).collect()
+    }
+}
+
+impl Tracer for JsonTracer {
+    fn step(&mut self, pc: usize, opcode: u8, gas_remaining: u64, stack: &[U256], depth: usize) {
+        self.pending.push(self.steps.len());
+        self.steps.push(TraceStep {
+            pc,
+            op: opcode,
+            gas: gas_remaining,
+            gas_cost: 0,
+            stack: stack.to_vec(),
+            depth,
+        });
+    }
+
+    fn step_end(&mut self, gas_cost: u64, _stack: &[U256]) {
+        if let Some(index) = self.pending.pop() {
+            self.steps[index].gas_cost = gas_cost;
+        }
+    }
+}
+
+/// 栈快照追踪器
+///
+/// 记录每条指令执行后的栈内容，供 `EVM::trace_stack_evolution` 使用。
+#[derive(Debug, Clone, Default)]
+pub struct StackSnapshotTracer {
+    snapshots: Vec<Vec<U256>>,
+}
+
+impl StackSnapshotTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 每步执行后的栈（从栈底到栈顶）
+    pub fn snapshots(&self) -> &[Vec<U256>] {
+        &self.snapshots
+    }
+
+    pub fn into_snapshots(self) -> Vec<Vec<U256>> {
+        self.snapshots
+    }
+}
+
+impl Tracer for StackSnapshotTracer {
+    fn step(&mut self, _pc: usize, _opcode: u8, _gas: u64, _stack: &[U256], _depth: usize) {}
+
+    fn step_end(&mut self, _gas_cost: u64, stack: &[U256]) {
+        self.snapshots.push(stack.to_vec());
+    }
+}
+
+/// 操作码序列记录器
+///
+/// 按执行顺序记录每条指令的 (pc, 操作码)，用于精确断言控制流。
+#[derive(Debug, Clone, Default)]
+pub struct OpcodeSequenceRecorder {
+    steps: Vec<(usize, u8)>,
+}
+
+impl OpcodeSequenceRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已执行的 (pc, 操作码)
+    pub fn steps(&self) -> &[(usize, u8)] {
+        &self.steps
+    }
+
+    /// 已执行的操作码
+    pub fn opcodes(&self) -> Vec<u8> {
+        self.steps.iter().map(|(_, opcode)| *opcode).collect()
+    }
+
+    /// 断言执行的操作码序列与 `expected` 完全一致，不一致时 panic 并给出两边的序列
+    pub fn assert_sequence(&self, expected: &[u8]) {
+        let actual = self.opcodes();
+        assert!(
+            actual == expected,
+            "操作码序列不一致\n  实际: {}\n  期望: {}",
+            hex::encode(&actual),
+            hex::encode(expected)
+        );
+    }
+}
+
+impl Tracer for OpcodeSequenceRecorder {
+    fn step(&mut self, pc: usize, opcode: u8, _gas: u64, _stack: &[U256], _depth: usize) {
+        self.steps.push((pc, opcode));
+    }
+}
+
+/// gas 消耗类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GasCategory {
+    /// 算术、比较和位运算（ADD..SIGNEXTEND、LT..SAR）
+    Arithmetic,
+    /// 存储槽和账户状态的读写
+    Storage,
+    /// 内存读写、复制与扩展（包括 RETURN/REVERT 的内存扩展）
+    Memory,
+    /// 子调用和合约创建自身的成本（不含子帧内部的消耗）
+    Call,
+    /// 栈操作、跳转、环境读取、日志等其余指令
+    Other,
+}
+
+impl GasCategory {
+    /// 操作码所属的类别
+    pub fn of(opcode: u8) -> Self {
+        let arithmetic = Opcode::from_u8(opcode).is_some_and(|opcode| {
+            (Opcode::Add..=Opcode::Signextend).contains(&opcode)
+                || (Opcode::Lt..=Opcode::Sar).contains(&opcode)
+        });
+        if arithmetic {
+            return GasCategory::Arithmetic;
+        }
+
+        match opcode {
+            op::SLOAD
+            | op::SSTORE
+            | op::BALANCE
+            | op::SELFBALANCE
+            | op::EXTCODESIZE
+            | op::EXTCODECOPY
+            | op::EXTCODEHASH
+            | op::SELFDESTRUCT => GasCategory::Storage,
+            op::MLOAD
+            | op::MSTORE
+            | op::MSIZE
+            | op::MCOPY
+            | op::CALLDATACOPY
+            | op::CODECOPY
+            | op::RETURNDATACOPY
+            | op::RETURN
+            | op::REVERT => GasCategory::Memory,
+            op::CALL
+            | op::CALLCODE
+            | op::DELEGATECALL
+            | op::STATICCALL
+            | op::CREATE
+            | op::CREATE2 => GasCategory::Call,
+            _ => GasCategory::Other,
+        }
+    }
+}
+
+/// 按类别汇总 gas 消耗的追踪器
+///
+/// 调用类指令只统计自身的成本，子帧内部的消耗按各自的指令归类，不会重复计算。
+#[derive(Debug, Clone, Default)]
+pub struct GasCategoryProfiler {
+    arithmetic: u64,
+    storage: u64,
+    memory: u64,
+    call: u64,
+    other: u64,
+    /// 尚未结束的指令：(类别, 其间子帧已统计的 gas)
+    pending: Vec<(GasCategory, u64)>,
+}
+
+impl GasCategoryProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 各类别的 gas 消耗，顺序固定为 Arithmetic、Storage、Memory、Call、Other
+    pub fn summary(&self) -> [(GasCategory, u64); 5] {
+        [
+            (GasCategory::Arithmetic, self.arithmetic),
+            (GasCategory::Storage, self.storage),
+            (GasCategory::Memory, self.memory),
+            (GasCategory::Call, self.call),
+            (GasCategory::Other, self.other),
+        ]
+    }
+
+    /// 按类别查询的 gas 消耗，包含消耗为 0 的类别
+    pub fn report(&self) -> HashMap<GasCategory, u64> {
+        self.summary().into_iter().collect()
+    }
+
+    fn bucket(&mut self, category: GasCategory) -> &mut u64 {
+        match category {
+            GasCategory::Arithmetic => &mut self.arithmetic,
+            GasCategory::Storage => &mut self.storage,
+            GasCategory::Memory => &mut self.memory,
+            GasCategory::Call => &mut self.call,
+            GasCategory::Other => &mut self.other,
+        }
+    }
+}
+
+impl Tracer for GasCategoryProfiler {
+    fn step(&mut self, _pc: usize, opcode: u8, _gas: u64, _stack: &[U256], _depth: usize) {
+        self.pending.push((GasCategory::of(opcode), 0));
+    }
+
+    fn step_end(&mut self, gas_cost: u64, _stack: &[U256]) {
+        if let Some((category, nested)) = self.pending.pop() {
+            *self.bucket(category) += gas_cost.saturating_sub(nested);
+            // 整条指令的消耗对外层指令而言都属于子帧
+            if let Some((_, parent_nested)) = self.pending.last_mut() {
+                *parent_nested += gas_cost;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InMemoryDB;
+    use crate::evm::{create_berlin_evm, EVM};
+    use crate::models::*;
+    use crate::spec::Berlin;
+    use crate::test_utils::{addr, call_tx};
+    use ethereum_types::Address;
+
+    fn evm_with_code(contract: Address, code: Vec<u8>) -> EVM<Berlin, InMemoryDB> {
+        let mut db = InMemoryDB::new();
+        db.insert_account(
+            contract,
+            AccountInfo {
+                code_hash: keccak_hash::keccak(&code),
+                code: Some(code),
+                ..Default::default()
+            },
+        );
+        create_berlin_evm(db)
+    }
+
+    fn tx_to(contract: Address) -> Transaction {
+        Transaction {
+            gas_limit: GAS_TX_BASE + 10_000,
+            ..call_tx(contract)
+        }
+    }
+
+    #[test]
+    fn test_json_tracer_lines() {
+        let contract = addr(0xaa);
+        // PUSH1 1 PUSH1 2 ADD STOP
+        let code = vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00];
+
+        let mut evm = evm_with_code(contract, code);
+        evm.set_tracer(JsonTracer::new());
+        evm.transact(tx_to(contract)).unwrap();
+
+        let lines = evm.tracer::<JsonTracer>().unwrap().lines();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"pc":0,"op":96,"gas":"0x2710","gasCost":"0x3","stack":[],"depth":1}"#,
+                r#"{"pc":2,"op":96,"gas":"0x270d","gasCost":"0x3","stack":["0x1"],"depth":1}"#,
+                r#"{"pc":4,"op":1,"gas":"0x270a","gasCost":"0x3","stack":["0x1","0x2"],"depth":1}"#,
+                r#"{"pc":5,"op":0,"gas":"0x2707","gasCost":"0x0","stack":["0x3"],"depth":1}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gas_category_profiler() {
+        let contract = addr(0xaa);
+        // SSTORE(0, 2 * 3) PUSH1 0 MLOAD
+        let code = vec![
+            0x60, 0x02, 0x60, 0x03, 0x02, 0x60, 0x00, 0x55, 0x60, 0x00, 0x51,
+        ];
+
+        let mut evm = evm_with_code(contract, code);
+        evm.set_tracer(GasCategoryProfiler::new());
+        evm.transact(Transaction {
+            gas_limit: 100_000,
+            ..tx_to(contract)
+        })
+        .unwrap();
+
+        let summary = evm.tracer::<GasCategoryProfiler>().unwrap().summary();
+        assert_eq!(
+            summary,
+            [
+                (GasCategory::Arithmetic, 5),
+                (GasCategory::Storage, 20000 + 2100),
+                // MLOAD 3 + 扩展 1 个字的内存 3
+                (GasCategory::Memory, 3 + 3),
+                (GasCategory::Call, 0),
+                (GasCategory::Other, 4 * 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gas_report_storage_dominates_arithmetic() {
+        let contract = addr(0xaa);
+        // SSTORE(0, 1 + 2 + 3)
+        let code = vec![
+            0x60, 0x01, 0x60, 0x02, 0x01, 0x60, 0x03, 0x01, 0x60, 0x00, 0x55,
+        ];
+
+        let mut evm = evm_with_code(contract, code);
+        evm.set_tracer(GasCategoryProfiler::new());
+        evm.transact(Transaction {
+            gas_limit: 100_000,
+            ..tx_to(contract)
+        })
+        .unwrap();
+
+        let report = evm.tracer::<GasCategoryProfiler>().unwrap().report();
+        assert_eq!(report.len(), 5);
+        assert_eq!(report[&GasCategory::Arithmetic], 2 * 3);
+        assert_eq!(report[&GasCategory::Storage], 20000 + 2100);
+        let others: u64 = report
+            .iter()
+            .filter(|(category, _)| **category != GasCategory::Storage)
+            .map(|(_, gas)| gas)
+            .sum();
+        assert!(report[&GasCategory::Storage] > others);
+    }
+
+    #[test]
+    fn test_gas_category_profiler_does_not_double_count_calls() {
+        let parent = addr(0xaa);
+        let child = addr(0xbb);
+
+        // STATICCALL(0xffff, child, 0, 0, 0, 0)
+        let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+        code.extend_from_slice(child.as_bytes());
+        code.extend([0x61, 0xff, 0xff, 0xfa]);
+
+        let mut evm = evm_with_code(parent, code);
+        // 子合约：SLOAD(0)
+        let child_code = vec![0x60, 0x00, 0x54];
+        evm.database_mut().insert_account(
+            child,
+            AccountInfo {
+                code_hash: keccak_hash::keccak(&child_code),
+                code: Some(child_code),
+                ..Default::default()
+            },
+        );
+        evm.set_tracer(GasCategoryProfiler::new());
+        let result = evm
+            .transact(Transaction {
+                gas_limit: 100_000,
+                ..tx_to(parent)
+            })
+            .unwrap();
+
+        let summary = evm.tracer::<GasCategoryProfiler>().unwrap().summary();
+        assert_eq!(summary[1], (GasCategory::Storage, 2100));
+        // 子合约是冷地址
+        assert_eq!(summary[3], (GasCategory::Call, 2600));
+        // 父合约的 6 条 PUSH 和子合约的 1 条 PUSH
+        assert_eq!(summary[4], (GasCategory::Other, 7 * 3));
+        // 各类别之和加上交易的固有成本等于总消耗
+        let total: u64 = summary.iter().map(|(_, gas)| gas).sum();
+        assert_eq!(total + GAS_TX_BASE, result.gas_used);
+    }
+
+    #[test]
+    fn test_gas_category_profiler_counts_create2_as_call() {
+        let contract = addr(0xaa);
+        // CREATE2(value 0, offset 0, size 0, salt 0)：空初始化代码
+        let code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, op::CREATE2];
+
+        let mut evm = evm_with_code(contract, code);
+        evm.set_tracer(GasCategoryProfiler::new());
+        evm.transact(Transaction {
+            gas_limit: 100_000,
+            ..tx_to(contract)
+        })
+        .unwrap();
+
+        let summary = evm.tracer::<GasCategoryProfiler>().unwrap().summary();
+        assert_eq!(summary[3], (GasCategory::Call, 32000));
+        assert_eq!(summary[4], (GasCategory::Other, 4 * 3));
+    }
+
+    #[test]
+    fn test_opcode_sequence_follows_taken_branch() {
+        let contract = addr(0xaa);
+        // if (cond) { PUSH1 0xaa } else { PUSH1 0xbb }
+        let code = |cond: u8| {
+            vec![
+                0x60, cond, 0x60, 0x0a, 0x57, // JUMPI 到 0x0a
+                0x60, 0xbb, 0x60, 0x0d, 0x56, // else 分支，JUMP 到 0x0d
+                0x5b, 0x60, 0xaa, // 0x0a: then 分支
+                0x5b, 0x00, // 0x0d: 汇合
+            ]
+        };
+
+        let mut evm = evm_with_code(contract, code(1));
+        evm.set_tracer(OpcodeSequenceRecorder::new());
+        evm.transact(tx_to(contract)).unwrap();
+        let recorder = evm.tracer::<OpcodeSequenceRecorder>().unwrap();
+        recorder.assert_sequence(&[0x60, 0x60, 0x57, 0x5b, 0x60, 0x5b, 0x00]);
+        let pcs: Vec<_> = recorder.steps().iter().map(|(pc, _)| *pc).collect();
+        assert_eq!(pcs, [0, 2, 4, 10, 11, 13, 14]);
+
+        let mut evm = evm_with_code(contract, code(0));
+        evm.set_tracer(OpcodeSequenceRecorder::new());
+        evm.transact(tx_to(contract)).unwrap();
+        evm.tracer::<OpcodeSequenceRecorder>()
+            .unwrap()
+            .assert_sequence(&[0x60, 0x60, 0x57, 0x60, 0x60, 0x56, 0x5b, 0x00]);
+    }
+
+    #[test]
+    #[should_panic(expected = "操作码序列不一致")]
+    fn test_opcode_sequence_mismatch_panics() {
+        let contract = addr(0xaa);
+        let mut evm = evm_with_code(contract, vec![0x60, 0x01, 0x00]);
+        evm.set_tracer(OpcodeSequenceRecorder::new());
+        evm.transact(tx_to(contract)).unwrap();
+        evm.tracer::<OpcodeSequenceRecorder>()
+            .unwrap()
+            .assert_sequence(&[0x60, 0x60]);
+    }
+
+    #[test]
+    fn test_trace_stack_evolution() {
+        let contract = addr(0xaa);
+        // PUSH1 1 PUSH1 2 ADD（代码末尾隐式 STOP，不计入步骤）
+        let code = vec![0x60, 0x01, 0x60, 0x02, 0x01];
+
+        let mut evm = evm_with_code(contract, code);
+        evm.set_tracer(JsonTracer::new());
+        let states = evm.trace_stack_evolution(tx_to(contract));
+        assert_eq!(
+            states,
+            vec![
+                vec![U256::from(1)],
+                vec![U256::from(1), U256::from(2)],
+                vec![U256::from(3)],
+            ]
+        );
+
+        // 原有的追踪器被恢复，且没有记录这次执行
+        assert!(evm.tracer::<JsonTracer>().unwrap().steps().is_empty());
+    }
+}
@@ -2,6 +2,8 @@ pub mod database;
 pub mod evm;
 pub mod models;
 pub mod spec;
+#[cfg(test)]
+pub(crate) mod test_utils;
 
 pub use database::*;
 pub use evm::*;
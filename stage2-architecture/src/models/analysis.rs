@@ -0,0 +1,146 @@
+use crate::evm::stack::STACK_LIMIT;
+use crate::models::{Error, Opcode};
+use std::collections::HashSet;
+
+/// 分析字节码中所有合法的跳转目标
+///
+/// 只有作为指令出现的 JUMPDEST (0x5B) 才是合法目标。
+/// PUSH 的立即数是数据而不是指令，扫描时必须整体跳过，
+/// 否则立即数中恰好等于 0x5B 的字节会被误认为跳转目标。
+pub fn valid_jumpdests(code: &[u8]) -> HashSet<usize> {
+    let mut jumpdests = HashSet::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        match Opcode::from_u8(code[pc]) {
+            Some(Opcode::Jumpdest) => {
+                jumpdests.insert(pc);
+            }
+            Some(opcode) => pc += opcode.immediate_size(),
+            None => {}
+        }
+        pc += 1;
+    }
+
+    jumpdests
+}
+
+/// 静态检查字节码中必然发生的栈下溢和栈上溢，不执行代码
+///
+/// 按基本块模拟栈高度：从代码开头或 JUMPI 之后顺序执行进入的块，入口高度确定，
+/// 下溢和上溢都能检出；以 JUMPDEST 开头的块可能从多个跳转来源进入，入口高度未知，
+/// 只按相对高度检查上溢；JUMP 和终止指令之后的代码只能通过跳转到达，同样视为未知。
+pub fn validate_stack(code: &[u8]) -> Result<(), Error> {
+    // 当前块入口之后的栈高度；入口高度未知时为相对高度
+    let mut height: i64 = 0;
+    let mut entry_known = true;
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let Some(opcode) = Opcode::from_u8(code[pc]) else {
+            // 未定义的指令使执行终止
+            height = 0;
+            entry_known = false;
+            pc += 1;
+            continue;
+        };
+
+        if opcode == Opcode::Jumpdest {
+            height = 0;
+            entry_known = false;
+        }
+
+        let (pops, pushes) = opcode.stack_io();
+        if entry_known && height < pops as i64 {
+            return Err(Error::StackUnderflow);
+        }
+        height += pushes as i64 - pops as i64;
+        // 实际高度不低于相对高度，相对高度超限时必然上溢
+        if height > STACK_LIMIT as i64 {
+            return Err(Error::StackOverflow);
+        }
+
+        if matches!(
+            opcode,
+            Opcode::Stop
+                | Opcode::Jump
+                | Opcode::Return
+                | Opcode::Revert
+                | Opcode::Invalid
+                | Opcode::Selfdestruct
+        ) {
+            height = 0;
+            entry_known = false;
+        }
+        pc += 1 + opcode.immediate_size();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_jumpdests() {
+        // JUMPDEST PUSH1 0x5b JUMPDEST
+        let code = [0x5b, 0x60, 0x5b, 0x5b];
+        assert_eq!(valid_jumpdests(&code), HashSet::from([0, 3]));
+    }
+
+    #[test]
+    fn test_jumpdest_inside_push32_is_skipped() {
+        // PUSH32 的立即数中全是 0x5b，之后才是真正的 JUMPDEST
+        let mut code = vec![0x7f];
+        code.extend([0x5b; 32]);
+        code.push(0x5b);
+        assert_eq!(valid_jumpdests(&code), HashSet::from([33]));
+    }
+
+    #[test]
+    fn test_truncated_push_at_end() {
+        // PUSH2 只剩一个字节的立即数
+        assert!(valid_jumpdests(&[0x61, 0x5b]).is_empty());
+    }
+
+    #[test]
+    fn test_validate_stack_flags_pop_on_empty_stack() {
+        assert_eq!(validate_stack(&[0x50]), Err(Error::StackUnderflow));
+        // PUSH1 1 POP POP
+        assert_eq!(
+            validate_stack(&[0x60, 0x01, 0x50, 0x50]),
+            Err(Error::StackUnderflow)
+        );
+        // PUSH1 1 PUSH1 2 ADD POP STOP
+        assert_eq!(
+            validate_stack(&[0x60, 0x01, 0x60, 0x02, 0x01, 0x50, 0x00]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_validate_stack_across_blocks() {
+        // JUMPI 之后顺序执行的块入口高度已知：PUSH1 0 PUSH1 6 JUMPI POP
+        assert_eq!(
+            validate_stack(&[0x60, 0x00, 0x60, 0x06, 0x57, 0x50]),
+            Err(Error::StackUnderflow)
+        );
+        // JUMPDEST 可能从任意高度跳入，无法判断下溢
+        assert_eq!(validate_stack(&[0x00, 0x5b, 0x50, 0x50]), Ok(()));
+        // 未定义指令之后的代码不可达
+        assert_eq!(validate_stack(&[0x0c, 0x50]), Ok(()));
+        // PUSH 的立即数不是指令
+        assert_eq!(validate_stack(&[0x61, 0x50, 0x50, 0x00]), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_stack_flags_overflow() {
+        let mut code = vec![0x5b];
+        code.extend([0x58; STACK_LIMIT]);
+        assert_eq!(validate_stack(&code), Ok(()));
+        // 即使入口高度未知，块内再压入一个也必然上溢
+        code.push(0x58);
+        assert_eq!(validate_stack(&code), Err(Error::StackOverflow));
+    }
+}
@@ -0,0 +1,5 @@
+//! 有符号运算和模运算辅助函数
+//!
+//! 实现位于与 stage1 共享的 `evm-arithmetic` crate，这里重新导出供解释器使用。
+
+pub use evm_arithmetic::*;
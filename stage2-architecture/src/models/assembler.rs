@@ -0,0 +1,158 @@
+//! 简单的 EVM 汇编器，用于在测试和示例中编写可读的字节码
+//!
+//! 源码由空白分隔的记号组成，`;` 之后到行尾为注释：
+//! - 助记符（不区分大小写），如 `ADD`、`sstore`
+//! - `PUSHn` 后跟一个十六进制（`0x` 前缀）或十进制立即数
+//! - `name:` 定义标签，并在该位置生成一条 JUMPDEST
+//! - `@name` 以 PUSH2 压入标签的偏移量
+
+use crate::models::{Error, Opcode};
+use ethereum_types::U256;
+use std::collections::HashMap;
+
+/// 解析后的一条汇编语句
+enum Item<'a> {
+    Opcode(Opcode),
+    Push(usize, U256),
+    Label(&'a str),
+    LabelRef(&'a str),
+}
+
+/// 把汇编源码翻译成字节码
+///
+/// 标签引用固定占 3 字节（PUSH2），因此可以先计算全部标签的偏移再生成代码。
+pub fn assemble(source: &str) -> Result<Vec<u8>, Error> {
+    let items = parse(source)?;
+
+    // 第一遍：计算每个标签的偏移量
+    let mut labels = HashMap::new();
+    let mut offset = 0;
+    for item in &items {
+        match item {
+            Item::Label(name) => {
+                if labels.insert(*name, offset).is_some() {
+                    return Err(error(format!("重复定义的标签 {}", name)));
+                }
+                offset += 1;
+            }
+            Item::Opcode(_) => offset += 1,
+            Item::Push(size, _) => offset += 1 + size,
+            Item::LabelRef(_) => offset += 3,
+        }
+    }
+
+    // 第二遍：生成字节码
+    let mut code = Vec::with_capacity(offset);
+    for item in items {
+        match item {
+            Item::Label(_) => code.push(Opcode::Jumpdest.as_u8()),
+            Item::Opcode(opcode) => code.push(opcode.as_u8()),
+            Item::Push(size, value) => push_bytes(&mut code, size, value),
+            Item::LabelRef(name) => {
+                let target = *labels
+                    .get(name)
+                    .ok_or_else(|| error(format!("未定义的标签 {}", name)))?;
+                if target > u16::MAX as usize {
+                    return Err(error(format!("标签 {} 的偏移超出 PUSH2 的范围", name)));
+                }
+                push_bytes(&mut code, 2, U256::from(target));
+            }
+        }
+    }
+    Ok(code)
+}
+
+fn parse(source: &str) -> Result<Vec<Item<'_>>, Error> {
+    let mut tokens = source
+        .lines()
+        .flat_map(|line| line.split(';').next().unwrap_or("").split_whitespace());
+
+    let mut items = Vec::new();
+    while let Some(token) = tokens.next() {
+        if let Some(name) = token.strip_suffix(':').filter(|name| !name.is_empty()) {
+            items.push(Item::Label(name));
+        } else if let Some(name) = token.strip_prefix('@').filter(|name| !name.is_empty()) {
+            items.push(Item::LabelRef(name));
+        } else {
+            let opcode = Opcode::ALL
+                .iter()
+                .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(token))
+                .copied()
+                .ok_or_else(|| error(format!("未知的助记符 {}", token)))?;
+            let size = opcode.immediate_size();
+            if size == 0 {
+                items.push(Item::Opcode(opcode));
+                continue;
+            }
+
+            let literal = tokens
+                .next()
+                .ok_or_else(|| error(format!("{} 缺少立即数", opcode)))?;
+            let value = parse_immediate(literal)?;
+            if value.bits() > size * 8 {
+                return Err(error(format!("立即数 {} 超出 {} 的范围", literal, opcode)));
+            }
+            items.push(Item::Push(size, value));
+        }
+    }
+    Ok(items)
+}
+
+/// 解析十六进制（`0x` 前缀）或十进制立即数
+fn parse_immediate(literal: &str) -> Result<U256, Error> {
+    let value = match literal.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(literal).ok(),
+    };
+    value.ok_or_else(|| error(format!("无法解析的立即数 {}", literal)))
+}
+
+fn push_bytes(code: &mut Vec<u8>, size: usize, value: U256) {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    code.push(Opcode::Push1.as_u8() + size as u8 - 1);
+    code.extend_from_slice(&word[32 - size..]);
+}
+
+fn error(message: String) -> Error {
+    Error::Assembly(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble_with_labels() {
+        let code = assemble(
+            "
+            push1 0x01 PUSH1 2 ADD   ; 1 + 2
+            @end JUMP
+            INVALID
+            end:
+            STOP
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            code,
+            [0x60, 0x01, 0x60, 0x02, 0x01, 0x61, 0x00, 0x0a, 0x56, 0xfe, 0x5b, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let message = |source| match assemble(source) {
+            Err(Error::Assembly(message)) => message,
+            other => panic!("应当汇编失败: {:?}", other),
+        };
+
+        assert!(message("PUSH1 0x100").contains("超出"));
+        assert!(message("FOO").contains("未知"));
+        assert!(message("@nowhere JUMP").contains("未定义"));
+        assert!(message("a: a:").contains("重复"));
+        assert!(message("PUSH2").contains("缺少"));
+        assert!(message("PUSH1 0xzz").contains("无法解析"));
+    }
+}
@@ -0,0 +1,95 @@
+//! EIP-1559 费用市场的基础费用调整
+//!
+//! 每个区块的基础费用由父区块决定：用量高于目标时上涨，低于目标时下降，
+//! 单个区块的变化幅度不超过 1/8。连续计算多个区块可以观察基础费用向均衡收敛。
+
+use crate::spec::Spec;
+use ethereum_types::U256;
+
+/// 单个区块基础费用最大变化比例的分母
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// 按 EIP-1559 由父区块计算下一个区块的基础费用
+///
+/// 用量高于目标时至少上涨 1；目标为 0 时基础费用保持不变。
+pub fn next_base_fee(parent_base_fee: U256, parent_gas_used: u64, parent_gas_target: u64) -> U256 {
+    if parent_gas_target == 0 || parent_gas_used == parent_gas_target {
+        return parent_base_fee;
+    }
+
+    let target = U256::from(parent_gas_target);
+    let change = |gas_delta: u64| {
+        parent_base_fee * U256::from(gas_delta)
+            / target
+            / U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR)
+    };
+
+    if parent_gas_used > parent_gas_target {
+        let delta = change(parent_gas_used - parent_gas_target).max(U256::one());
+        parent_base_fee.saturating_add(delta)
+    } else {
+        parent_base_fee.saturating_sub(change(parent_gas_target - parent_gas_used))
+    }
+}
+
+/// 在规范启用 EIP-1559 时计算下一个区块的基础费用，否则返回 `None`
+pub fn next_base_fee_for<SPEC: Spec>(
+    parent_base_fee: U256,
+    parent_gas_used: u64,
+    parent_gas_target: u64,
+) -> Option<U256> {
+    SPEC::ENABLE_EIP1559.then(|| next_base_fee(parent_base_fee, parent_gas_used, parent_gas_target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{Berlin, London};
+
+    const TARGET: u64 = 15_000_000;
+
+    #[test]
+    fn test_base_fee_adjusts_toward_target() {
+        let fee = U256::from(1_000_000_000u64);
+
+        // 满块：上涨 1/8
+        assert_eq!(
+            next_base_fee(fee, 2 * TARGET, TARGET),
+            U256::from(1_125_000_000u64)
+        );
+        // 恰好达到目标：不变
+        assert_eq!(next_base_fee(fee, TARGET, TARGET), fee);
+        // 空块：下降 1/8
+        assert_eq!(next_base_fee(fee, 0, TARGET), U256::from(875_000_000u64));
+        // 低于目标一半：下降 1/16
+        assert_eq!(
+            next_base_fee(fee, TARGET / 2, TARGET),
+            U256::from(937_500_000u64)
+        );
+    }
+
+    #[test]
+    fn test_base_fee_rounding_edges() {
+        // 上涨幅度向下取整为 0 时至少上涨 1
+        assert_eq!(
+            next_base_fee(U256::from(7), TARGET + 1, TARGET),
+            U256::from(8)
+        );
+        // 下降幅度可以取整为 0
+        assert_eq!(
+            next_base_fee(U256::from(7), TARGET - 1, TARGET),
+            U256::from(7)
+        );
+        assert_eq!(next_base_fee(U256::zero(), 0, TARGET), U256::zero());
+    }
+
+    #[test]
+    fn test_base_fee_gated_by_spec() {
+        let fee = U256::from(100);
+        assert_eq!(
+            next_base_fee_for::<London>(fee, 0, TARGET),
+            Some(U256::from(88))
+        );
+        assert_eq!(next_base_fee_for::<Berlin>(fee, 0, TARGET), None);
+    }
+}
@@ -1,3 +1,16 @@
+pub mod analysis;
+pub mod arithmetic;
+pub mod assembler;
+pub mod fee_market;
+pub mod modexp;
+pub(crate) mod op;
+pub mod opcode;
+pub mod ripemd160;
+pub mod secp256k1;
+pub mod sha256;
 pub mod types;
 
+pub use assembler::assemble;
+pub use fee_market::{next_base_fee, next_base_fee_for};
+pub use opcode::Opcode;
 pub use types::*;
@@ -0,0 +1,254 @@
+//! 任意精度的模幂运算，供 0x05 (MODEXP) 预编译合约使用
+//!
+//! 大整数用小端序的 u64 数组表示，乘法为竖式乘法，取模为 Knuth 算法 D。
+
+/// 计算 `base ** exponent % modulus`
+///
+/// 操作数均为大端字节序，结果左侧补 0 到 `modulus.len()` 字节。
+/// 模数为 0 时结果为 0。
+pub fn modexp(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; modulus.len()];
+    let modulus = from_be_bytes(modulus);
+    if is_zero(&modulus) {
+        return output;
+    }
+
+    let base = rem(&from_be_bytes(base), &modulus);
+    let mut result = rem(&[1], &modulus);
+    for byte in exponent {
+        for bit in (0..8).rev() {
+            result = rem(&mul(&result, &result), &modulus);
+            if (byte >> bit) & 1 == 1 {
+                result = rem(&mul(&result, &base), &modulus);
+            }
+        }
+    }
+
+    let bytes = to_be_bytes(&result);
+    let start = output.len() - bytes.len();
+    output[start..].copy_from_slice(&bytes);
+    output
+}
+
+fn from_be_bytes(bytes: &[u8]) -> Vec<u64> {
+    let mut limbs: Vec<u64> = bytes
+        .rchunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[8 - chunk.len()..].copy_from_slice(chunk);
+            u64::from_be_bytes(word)
+        })
+        .collect();
+    normalize(&mut limbs);
+    limbs
+}
+
+/// 去掉前导 0 字节的大端表示
+fn to_be_bytes(value: &[u64]) -> Vec<u8> {
+    let bytes: Vec<u8> = value
+        .iter()
+        .rev()
+        .flat_map(|limb| limb.to_be_bytes())
+        .collect();
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[first..].to_vec()
+}
+
+/// 去掉高位的 0
+fn normalize(value: &mut Vec<u64>) {
+    while value.last() == Some(&0) {
+        value.pop();
+    }
+}
+
+fn is_zero(value: &[u64]) -> bool {
+    value.iter().all(|limb| *limb == 0)
+}
+
+fn mul(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut product = vec![0u64; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u128 * y as u128 + product[i + j] as u128 + carry;
+            product[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        product[i + b.len()] = carry as u64;
+    }
+    normalize(&mut product);
+    product
+}
+
+/// 取模 `u % v`，要求 v 非 0 且没有高位的 0
+fn rem(u: &[u64], v: &[u64]) -> Vec<u64> {
+    let mut u = u.to_vec();
+    normalize(&mut u);
+    let n = v.len();
+    if u.len() < n {
+        return u;
+    }
+
+    // 单字除数：逐字做短除法
+    if n == 1 {
+        let divisor = v[0] as u128;
+        let remainder = u
+            .iter()
+            .rev()
+            .fold(0u128, |r, &limb| ((r << 64) | limb as u128) % divisor);
+        let mut result = vec![remainder as u64];
+        normalize(&mut result);
+        return result;
+    }
+
+    // 规格化：左移使除数最高字的最高位为 1，估商误差不超过 2
+    let shift = v[n - 1].leading_zeros();
+    let vn = shl(v, shift, n);
+    let mut un = shl(&u, shift, u.len() + 1);
+    let m = u.len() - n;
+
+    for j in (0..=m).rev() {
+        let num = ((un[j + n] as u128) << 64) | un[j + n - 1] as u128;
+        let mut qhat = num / vn[n - 1] as u128;
+        let mut rhat = num % vn[n - 1] as u128;
+        while qhat >> 64 != 0 || qhat * vn[n - 2] as u128 > ((rhat << 64) | un[j + n - 2] as u128) {
+            qhat -= 1;
+            rhat += vn[n - 1] as u128;
+            if rhat >> 64 != 0 {
+                break;
+            }
+        }
+
+        // un[j..j+n+1] -= qhat * vn
+        let mut carry = 0u128;
+        let mut borrow = 0u64;
+        for i in 0..n {
+            let p = qhat * vn[i] as u128 + carry;
+            carry = p >> 64;
+            let (t, b1) = un[i + j].overflowing_sub(p as u64);
+            let (t, b2) = t.overflowing_sub(borrow);
+            un[i + j] = t;
+            borrow = b1 as u64 + b2 as u64;
+        }
+        let (t, b1) = un[j + n].overflowing_sub(carry as u64);
+        let (t, b2) = t.overflowing_sub(borrow);
+        un[j + n] = t;
+
+        // 估商大了 1：加回一个除数
+        if b1 || b2 {
+            let mut carry = 0u128;
+            for i in 0..n {
+                let t = un[i + j] as u128 + vn[i] as u128 + carry;
+                un[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u64);
+        }
+    }
+
+    let mut remainder = shr(&un[..n], shift);
+    normalize(&mut remainder);
+    remainder
+}
+
+/// 左移 `shift` 位（小于 64），结果补足到 `len` 个字
+fn shl(value: &[u64], shift: u32, len: usize) -> Vec<u64> {
+    let mut result = vec![0u64; len];
+    for (i, &limb) in value.iter().enumerate() {
+        result[i] |= limb << shift;
+        if shift > 0 && i + 1 < len {
+            result[i + 1] = limb >> (64 - shift);
+        }
+    }
+    result
+}
+
+/// 右移 `shift` 位（小于 64）
+fn shr(value: &[u64], shift: u32) -> Vec<u64> {
+    if shift == 0 {
+        return value.to_vec();
+    }
+    (0..value.len())
+        .map(|i| {
+            let high = value.get(i + 1).map_or(0, |limb| limb << (64 - shift));
+            (value[i] >> shift) | high
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modexp_small() {
+        assert_eq!(modexp(&[3], &[5], &[7]), vec![5]);
+        // 结果左侧补 0 到模数长度
+        assert_eq!(modexp(&[3], &[5], &[0, 7]), vec![0, 5]);
+        // 指数为 0 时结果为 1 mod m
+        assert_eq!(modexp(&[9], &[], &[7]), vec![1]);
+        assert_eq!(modexp(&[9], &[0], &[1]), vec![0]);
+        // 模数为 0 时结果为 0
+        assert_eq!(modexp(&[3], &[5], &[0, 0]), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_modexp_multi_limb() {
+        let base = hex::decode(concat!(
+            "6f03671600a35a099950d836f675cc81e74ef5e8e25d940ed904759531985d5d",
+            "9dc9f81818e811892f902bd23f0824128b2f330c5c7fd0a6a3a4506513270e26",
+            "9e0d37f2a74de452e6b438",
+        ))
+        .unwrap();
+        let exponent = hex::decode(concat!(
+            "01fb90c192cfd3ac94af0f21ddb66cad4a268d116ece1738f7d93d9c172411e2",
+            "0b8f6b0d549b",
+        ))
+        .unwrap();
+        let modulus = hex::decode(concat!(
+            "0022dbc496cb8e81973e0becd7b03898d190f9ebdacc0cb1e29c658cda1495e6",
+            "0af593bd04cf0fd630f1f29d0da9953f48f1a09f76b5a170b33839263059f28c",
+            "105d",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            hex::encode(modexp(&base, &exponent, &modulus)),
+            concat!(
+                "0016bfc81cc05a23fb2b3720dcba583f77186325e88d63b180cef8d5f9024dc6",
+                "0715efd3fc0a4291a5948a3a3b61a1adeec0ffa4e71bc3b77cd78c4ef6805661",
+                "8041",
+            )
+        );
+    }
+
+    #[test]
+    fn test_modexp_matches_u256_mulmod() {
+        use crate::models::arithmetic::mulmod;
+        use ethereum_types::U256;
+
+        // 与 U256 上的平方-乘算法对照，覆盖 1 到 4 个字的模数
+        for seed in 0u64..64 {
+            let word = |tag: u64| U256::from(keccak_hash::keccak((seed * 3 + tag).to_be_bytes()).0);
+            let base = word(0);
+            let exponent = word(1) >> (seed * 4);
+            let modulus = (word(2) >> (seed * 3)) | U256::one();
+
+            let mut expected = U256::one() % modulus;
+            for bit in (0..256).rev() {
+                expected = mulmod(expected, expected, modulus);
+                if exponent.bit(bit) {
+                    expected = mulmod(expected, base, modulus);
+                }
+            }
+
+            let bytes = |value: U256| {
+                let mut out = [0u8; 32];
+                value.to_big_endian(&mut out);
+                out
+            };
+            let result = modexp(&bytes(base), &bytes(exponent), &bytes(modulus));
+            assert_eq!(result, bytes(expected), "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! 操作码常量，取自 `Opcode` 表，便于构建指令表和在 match 中使用
+
+use super::Opcode;
+
+pub const STOP: u8 = Opcode::Stop.as_u8();
+pub const ADD: u8 = Opcode::Add.as_u8();
+pub const MUL: u8 = Opcode::Mul.as_u8();
+pub const SUB: u8 = Opcode::Sub.as_u8();
+pub const DIV: u8 = Opcode::Div.as_u8();
+pub const SDIV: u8 = Opcode::Sdiv.as_u8();
+pub const MOD: u8 = Opcode::Mod.as_u8();
+pub const SMOD: u8 = Opcode::Smod.as_u8();
+pub const ADDMOD: u8 = Opcode::Addmod.as_u8();
+pub const MULMOD: u8 = Opcode::Mulmod.as_u8();
+pub const EXP: u8 = Opcode::Exp.as_u8();
+pub const LT: u8 = Opcode::Lt.as_u8();
+pub const GT: u8 = Opcode::Gt.as_u8();
+pub const SLT: u8 = Opcode::Slt.as_u8();
+pub const SGT: u8 = Opcode::Sgt.as_u8();
+pub const EQ: u8 = Opcode::Eq.as_u8();
+pub const ISZERO: u8 = Opcode::Iszero.as_u8();
+pub const AND: u8 = Opcode::And.as_u8();
+pub const OR: u8 = Opcode::Or.as_u8();
+pub const XOR: u8 = Opcode::Xor.as_u8();
+pub const NOT: u8 = Opcode::Not.as_u8();
+pub const BYTE: u8 = Opcode::Byte.as_u8();
+pub const SHL: u8 = Opcode::Shl.as_u8();
+pub const SHR: u8 = Opcode::Shr.as_u8();
+pub const SAR: u8 = Opcode::Sar.as_u8();
+pub const ADDRESS: u8 = Opcode::Address.as_u8();
+pub const BALANCE: u8 = Opcode::Balance.as_u8();
+pub const CALLER: u8 = Opcode::Caller.as_u8();
+pub const CALLVALUE: u8 = Opcode::Callvalue.as_u8();
+pub const CALLDATALOAD: u8 = Opcode::Calldataload.as_u8();
+pub const CALLDATASIZE: u8 = Opcode::Calldatasize.as_u8();
+pub const CALLDATACOPY: u8 = Opcode::Calldatacopy.as_u8();
+pub const CODECOPY: u8 = Opcode::Codecopy.as_u8();
+pub const EXTCODESIZE: u8 = Opcode::Extcodesize.as_u8();
+pub const EXTCODECOPY: u8 = Opcode::Extcodecopy.as_u8();
+pub const RETURNDATASIZE: u8 = Opcode::Returndatasize.as_u8();
+pub const RETURNDATACOPY: u8 = Opcode::Returndatacopy.as_u8();
+pub const EXTCODEHASH: u8 = Opcode::Extcodehash.as_u8();
+pub const TIMESTAMP: u8 = Opcode::Timestamp.as_u8();
+pub const NUMBER: u8 = Opcode::Number.as_u8();
+pub const PREVRANDAO: u8 = Opcode::Prevrandao.as_u8();
+pub const GASLIMIT: u8 = Opcode::Gaslimit.as_u8();
+pub const CHAINID: u8 = Opcode::Chainid.as_u8();
+pub const SELFBALANCE: u8 = Opcode::Selfbalance.as_u8();
+pub const BASEFEE: u8 = Opcode::Basefee.as_u8();
+pub const POP: u8 = Opcode::Pop.as_u8();
+pub const MLOAD: u8 = Opcode::Mload.as_u8();
+pub const MSTORE: u8 = Opcode::Mstore.as_u8();
+pub const SLOAD: u8 = Opcode::Sload.as_u8();
+pub const SSTORE: u8 = Opcode::Sstore.as_u8();
+pub const JUMP: u8 = Opcode::Jump.as_u8();
+pub const JUMPI: u8 = Opcode::Jumpi.as_u8();
+pub const PC: u8 = Opcode::Pc.as_u8();
+pub const MSIZE: u8 = Opcode::Msize.as_u8();
+pub const GAS: u8 = Opcode::Gas.as_u8();
+pub const JUMPDEST: u8 = Opcode::Jumpdest.as_u8();
+pub const TLOAD: u8 = Opcode::Tload.as_u8();
+pub const TSTORE: u8 = Opcode::Tstore.as_u8();
+pub const MCOPY: u8 = Opcode::Mcopy.as_u8();
+pub const LOG0: u8 = Opcode::Log0.as_u8();
+pub const LOG4: u8 = Opcode::Log4.as_u8();
+pub const PUSH1: u8 = Opcode::Push1.as_u8();
+pub const PUSH32: u8 = Opcode::Push32.as_u8();
+pub const CREATE: u8 = Opcode::Create.as_u8();
+pub const CALL: u8 = Opcode::Call.as_u8();
+pub const CALLCODE: u8 = Opcode::Callcode.as_u8();
+pub const RETURN: u8 = Opcode::Return.as_u8();
+pub const DELEGATECALL: u8 = Opcode::Delegatecall.as_u8();
+pub const CREATE2: u8 = Opcode::Create2.as_u8();
+pub const STATICCALL: u8 = Opcode::Staticcall.as_u8();
+pub const REVERT: u8 = Opcode::Revert.as_u8();
+pub const INVALID: u8 = Opcode::Invalid.as_u8();
+pub const SELFDESTRUCT: u8 = Opcode::Selfdestruct.as_u8();
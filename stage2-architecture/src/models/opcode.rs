@@ -0,0 +1,246 @@
+/// 定义操作码表，生成 `Opcode` 枚举及其元数据
+///
+/// 每一行：变体 = 编码, 助记符, 弹出数, 压入数。
+macro_rules! opcodes {
+    ($($name:ident = $value:literal, $mnemonic:literal, $pops:literal, $pushes:literal;)*) => {
+        /// EVM 操作码
+        ///
+        /// 操作码的编码、助记符和栈效果都以这张表为准。
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(u8)]
+        pub enum Opcode {
+            $($name = $value,)*
+        }
+
+        impl Opcode {
+            /// 所有已定义的操作码，按编码升序排列
+            pub const ALL: &'static [Opcode] = &[$(Opcode::$name,)*];
+
+            /// 已定义的操作码数量
+            pub const COUNT: usize = Self::ALL.len();
+
+            /// 解析操作码，未定义的字节返回 None
+            pub const fn from_u8(byte: u8) -> Option<Self> {
+                match byte {
+                    $($value => Some(Opcode::$name),)*
+                    _ => None,
+                }
+            }
+
+            /// 助记符，如 `PUSH1`、`SSTORE`
+            pub fn mnemonic(&self) -> &'static str {
+                match self {
+                    $(Opcode::$name => $mnemonic,)*
+                }
+            }
+
+            /// 栈效果：(弹出的元素数, 压入的元素数)
+            pub const fn stack_io(&self) -> (u8, u8) {
+                match self {
+                    $(Opcode::$name => ($pops, $pushes),)*
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    Stop = 0x00, "STOP", 0, 0;
+    Add = 0x01, "ADD", 2, 1;
+    Mul = 0x02, "MUL", 2, 1;
+    Sub = 0x03, "SUB", 2, 1;
+    Div = 0x04, "DIV", 2, 1;
+    Sdiv = 0x05, "SDIV", 2, 1;
+    Mod = 0x06, "MOD", 2, 1;
+    Smod = 0x07, "SMOD", 2, 1;
+    Addmod = 0x08, "ADDMOD", 3, 1;
+    Mulmod = 0x09, "MULMOD", 3, 1;
+    Exp = 0x0A, "EXP", 2, 1;
+    Signextend = 0x0B, "SIGNEXTEND", 2, 1;
+    Lt = 0x10, "LT", 2, 1;
+    Gt = 0x11, "GT", 2, 1;
+    Slt = 0x12, "SLT", 2, 1;
+    Sgt = 0x13, "SGT", 2, 1;
+    Eq = 0x14, "EQ", 2, 1;
+    Iszero = 0x15, "ISZERO", 1, 1;
+    And = 0x16, "AND", 2, 1;
+    Or = 0x17, "OR", 2, 1;
+    Xor = 0x18, "XOR", 2, 1;
+    Not = 0x19, "NOT", 1, 1;
+    Byte = 0x1A, "BYTE", 2, 1;
+    Shl = 0x1B, "SHL", 2, 1;
+    Shr = 0x1C, "SHR", 2, 1;
+    Sar = 0x1D, "SAR", 2, 1;
+    Keccak256 = 0x20, "KECCAK256", 2, 1;
+    Address = 0x30, "ADDRESS", 0, 1;
+    Balance = 0x31, "BALANCE", 1, 1;
+    Origin = 0x32, "ORIGIN", 0, 1;
+    Caller = 0x33, "CALLER", 0, 1;
+    Callvalue = 0x34, "CALLVALUE", 0, 1;
+    Calldataload = 0x35, "CALLDATALOAD", 1, 1;
+    Calldatasize = 0x36, "CALLDATASIZE", 0, 1;
+    Calldatacopy = 0x37, "CALLDATACOPY", 3, 0;
+    Codesize = 0x38, "CODESIZE", 0, 1;
+    Codecopy = 0x39, "CODECOPY", 3, 0;
+    Gasprice = 0x3A, "GASPRICE", 0, 1;
+    Extcodesize = 0x3B, "EXTCODESIZE", 1, 1;
+    Extcodecopy = 0x3C, "EXTCODECOPY", 4, 0;
+    Returndatasize = 0x3D, "RETURNDATASIZE", 0, 1;
+    Returndatacopy = 0x3E, "RETURNDATACOPY", 3, 0;
+    Extcodehash = 0x3F, "EXTCODEHASH", 1, 1;
+    Blockhash = 0x40, "BLOCKHASH", 1, 1;
+    Coinbase = 0x41, "COINBASE", 0, 1;
+    Timestamp = 0x42, "TIMESTAMP", 0, 1;
+    Number = 0x43, "NUMBER", 0, 1;
+    Prevrandao = 0x44, "PREVRANDAO", 0, 1;
+    Gaslimit = 0x45, "GASLIMIT", 0, 1;
+    Chainid = 0x46, "CHAINID", 0, 1;
+    Selfbalance = 0x47, "SELFBALANCE", 0, 1;
+    Basefee = 0x48, "BASEFEE", 0, 1;
+    Pop = 0x50, "POP", 1, 0;
+    Mload = 0x51, "MLOAD", 1, 1;
+    Mstore = 0x52, "MSTORE", 2, 0;
+    Mstore8 = 0x53, "MSTORE8", 2, 0;
+    Sload = 0x54, "SLOAD", 1, 1;
+    Sstore = 0x55, "SSTORE", 2, 0;
+    Jump = 0x56, "JUMP", 1, 0;
+    Jumpi = 0x57, "JUMPI", 2, 0;
+    Pc = 0x58, "PC", 0, 1;
+    Msize = 0x59, "MSIZE", 0, 1;
+    Gas = 0x5A, "GAS", 0, 1;
+    Jumpdest = 0x5B, "JUMPDEST", 0, 0;
+    Tload = 0x5C, "TLOAD", 1, 1;
+    Tstore = 0x5D, "TSTORE", 2, 0;
+    Mcopy = 0x5E, "MCOPY", 3, 0;
+    Push1 = 0x60, "PUSH1", 0, 1;
+    Push2 = 0x61, "PUSH2", 0, 1;
+    Push3 = 0x62, "PUSH3", 0, 1;
+    Push4 = 0x63, "PUSH4", 0, 1;
+    Push5 = 0x64, "PUSH5", 0, 1;
+    Push6 = 0x65, "PUSH6", 0, 1;
+    Push7 = 0x66, "PUSH7", 0, 1;
+    Push8 = 0x67, "PUSH8", 0, 1;
+    Push9 = 0x68, "PUSH9", 0, 1;
+    Push10 = 0x69, "PUSH10", 0, 1;
+    Push11 = 0x6A, "PUSH11", 0, 1;
+    Push12 = 0x6B, "PUSH12", 0, 1;
+    Push13 = 0x6C, "PUSH13", 0, 1;
+    Push14 = 0x6D, "PUSH14", 0, 1;
+    Push15 = 0x6E, "PUSH15", 0, 1;
+    Push16 = 0x6F, "PUSH16", 0, 1;
+    Push17 = 0x70, "PUSH17", 0, 1;
+    Push18 = 0x71, "PUSH18", 0, 1;
+    Push19 = 0x72, "PUSH19", 0, 1;
+    Push20 = 0x73, "PUSH20", 0, 1;
+    Push21 = 0x74, "PUSH21", 0, 1;
+    Push22 = 0x75, "PUSH22", 0, 1;
+    Push23 = 0x76, "PUSH23", 0, 1;
+    Push24 = 0x77, "PUSH24", 0, 1;
+    Push25 = 0x78, "PUSH25", 0, 1;
+    Push26 = 0x79, "PUSH26", 0, 1;
+    Push27 = 0x7A, "PUSH27", 0, 1;
+    Push28 = 0x7B, "PUSH28", 0, 1;
+    Push29 = 0x7C, "PUSH29", 0, 1;
+    Push30 = 0x7D, "PUSH30", 0, 1;
+    Push31 = 0x7E, "PUSH31", 0, 1;
+    Push32 = 0x7F, "PUSH32", 0, 1;
+    Dup1 = 0x80, "DUP1", 1, 2;
+    Dup2 = 0x81, "DUP2", 2, 3;
+    Dup3 = 0x82, "DUP3", 3, 4;
+    Dup4 = 0x83, "DUP4", 4, 5;
+    Dup5 = 0x84, "DUP5", 5, 6;
+    Dup6 = 0x85, "DUP6", 6, 7;
+    Dup7 = 0x86, "DUP7", 7, 8;
+    Dup8 = 0x87, "DUP8", 8, 9;
+    Dup9 = 0x88, "DUP9", 9, 10;
+    Dup10 = 0x89, "DUP10", 10, 11;
+    Dup11 = 0x8A, "DUP11", 11, 12;
+    Dup12 = 0x8B, "DUP12", 12, 13;
+    Dup13 = 0x8C, "DUP13", 13, 14;
+    Dup14 = 0x8D, "DUP14", 14, 15;
+    Dup15 = 0x8E, "DUP15", 15, 16;
+    Dup16 = 0x8F, "DUP16", 16, 17;
+    Swap1 = 0x90, "SWAP1", 2, 2;
+    Swap2 = 0x91, "SWAP2", 3, 3;
+    Swap3 = 0x92, "SWAP3", 4, 4;
+    Swap4 = 0x93, "SWAP4", 5, 5;
+    Swap5 = 0x94, "SWAP5", 6, 6;
+    Swap6 = 0x95, "SWAP6", 7, 7;
+    Swap7 = 0x96, "SWAP7", 8, 8;
+    Swap8 = 0x97, "SWAP8", 9, 9;
+    Swap9 = 0x98, "SWAP9", 10, 10;
+    Swap10 = 0x99, "SWAP10", 11, 11;
+    Swap11 = 0x9A, "SWAP11", 12, 12;
+    Swap12 = 0x9B, "SWAP12", 13, 13;
+    Swap13 = 0x9C, "SWAP13", 14, 14;
+    Swap14 = 0x9D, "SWAP14", 15, 15;
+    Swap15 = 0x9E, "SWAP15", 16, 16;
+    Swap16 = 0x9F, "SWAP16", 17, 17;
+    Log0 = 0xA0, "LOG0", 2, 0;
+    Log1 = 0xA1, "LOG1", 3, 0;
+    Log2 = 0xA2, "LOG2", 4, 0;
+    Log3 = 0xA3, "LOG3", 5, 0;
+    Log4 = 0xA4, "LOG4", 6, 0;
+    Create = 0xF0, "CREATE", 3, 1;
+    Call = 0xF1, "CALL", 7, 1;
+    Callcode = 0xF2, "CALLCODE", 7, 1;
+    Return = 0xF3, "RETURN", 2, 0;
+    Delegatecall = 0xF4, "DELEGATECALL", 6, 1;
+    Create2 = 0xF5, "CREATE2", 4, 1;
+    Staticcall = 0xFA, "STATICCALL", 6, 1;
+    Revert = 0xFD, "REVERT", 2, 0;
+    Invalid = 0xFE, "INVALID", 0, 0;
+    Selfdestruct = 0xFF, "SELFDESTRUCT", 1, 0;
+}
+
+impl Opcode {
+    /// 操作码的编码
+    pub const fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+
+    /// 指令后紧跟的立即数字节数，只有 PUSH1..PUSH32 不为 0
+    pub fn immediate_size(&self) -> usize {
+        match self.as_u8() {
+            byte @ 0x60..=0x7F => (byte - 0x5F) as usize,
+            _ => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.mnemonic())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_all_opcodes() {
+        for opcode in Opcode::ALL {
+            assert_eq!(Opcode::from_u8(opcode.as_u8()), Some(*opcode));
+        }
+
+        // 每个字节要么未定义，要么对应唯一的操作码
+        let defined = (0..=255u8).filter_map(Opcode::from_u8).count();
+        assert_eq!(defined, Opcode::ALL.len());
+        assert_eq!(Opcode::from_u8(0x0c), None);
+        assert_eq!(Opcode::from_u8(0xef), None);
+    }
+
+    #[test]
+    fn test_opcode_metadata() {
+        assert_eq!(Opcode::Push1.mnemonic(), "PUSH1");
+        assert_eq!(Opcode::Keccak256.to_string(), "KECCAK256");
+        assert_eq!(Opcode::Add.stack_io(), (2, 1));
+        assert_eq!(Opcode::Dup16.stack_io(), (16, 17));
+        assert_eq!(Opcode::Swap1.stack_io(), (2, 2));
+        assert_eq!(Opcode::Log4.stack_io(), (6, 0));
+        assert_eq!(Opcode::Call.stack_io(), (7, 1));
+        assert_eq!(Opcode::Push32.immediate_size(), 32);
+        assert_eq!(Opcode::Jumpdest.immediate_size(), 0);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::models::arithmetic::{addmod, mulmod};
+use ethereum_types::{Address, U256};
+
+/// 有限域的模数 p = 2^256 - 2^32 - 977
+const P: U256 = U256([
+    0xFFFF_FFFE_FFFF_FC2F,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFF,
+]);
+
+/// 曲线的阶 n
+const N: U256 = U256([
+    0xBFD2_5E8C_D036_4141,
+    0xBAAE_DCE6_AF48_A03B,
+    0xFFFF_FFFF_FFFF_FFFE,
+    0xFFFF_FFFF_FFFF_FFFF,
+]);
+
+/// 生成元 G 的坐标
+const GX: U256 = U256([
+    0x59F2_815B_16F8_1798,
+    0x029B_FCDB_2DCE_28D9,
+    0x55A0_6295_CE87_0B07,
+    0x79BE_667E_F9DC_BBAC,
+]);
+const GY: U256 = U256([
+    0x9C47_D08F_FB10_D4B8,
+    0xFD17_B448_A685_5419,
+    0x5DA4_FBFC_0E11_08A8,
+    0x483A_DA77_26A3_C465,
+]);
+
+/// 模 m 减法：(a - b) mod m，要求 a、b 都小于 m
+fn submod(a: U256, b: U256, m: U256) -> U256 {
+    addmod(a, m - b, m)
+}
+
+/// 模幂：base ** exponent mod m
+fn powmod(base: U256, exponent: U256, m: U256) -> U256 {
+    let mut result = U256::one();
+    let mut base = base % m;
+    let mut exponent = exponent;
+
+    while !exponent.is_zero() {
+        if exponent.bit(0) {
+            result = mulmod(result, base, m);
+        }
+        base = mulmod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// 模逆（费马小定理，m 为素数）
+fn invmod(value: U256, m: U256) -> U256 {
+    powmod(value, m - 2, m)
+}
+
+/// 雅可比坐标下的曲线点 (X / Z^2, Y / Z^3)，Z = 0 表示无穷远点
+#[derive(Clone, Copy)]
+struct Point {
+    x: U256,
+    y: U256,
+    z: U256,
+}
+
+impl Point {
+    const INFINITY: Point = Point {
+        x: U256([0; 4]),
+        y: U256([0; 4]),
+        z: U256([0; 4]),
+    };
+
+    fn affine(x: U256, y: U256) -> Self {
+        Self {
+            x,
+            y,
+            z: U256::one(),
+        }
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// 倍点（曲线参数 a = 0）
+    fn double(&self) -> Self {
+        if self.is_infinity() || self.y.is_zero() {
+            return Self::INFINITY;
+        }
+        let a = mulmod(self.x, self.x, P);
+        let b = mulmod(self.y, self.y, P);
+        let c = mulmod(b, b, P);
+        let x_plus_b = addmod(self.x, b, P);
+        let d = submod(submod(mulmod(x_plus_b, x_plus_b, P), a, P), c, P);
+        let d = addmod(d, d, P);
+        let e = mulmod(U256::from(3), a, P);
+        let f = mulmod(e, e, P);
+
+        let x = submod(f, addmod(d, d, P), P);
+        let y = submod(
+            mulmod(e, submod(d, x, P), P),
+            mulmod(U256::from(8), c, P),
+            P,
+        );
+        let z = mulmod(U256::from(2), mulmod(self.y, self.z, P), P);
+        Self { x, y, z }
+    }
+
+    /// 点加
+    fn add(&self, other: &Self) -> Self {
+        if self.is_infinity() {
+            return *other;
+        }
+        if other.is_infinity() {
+            return *self;
+        }
+
+        let z1z1 = mulmod(self.z, self.z, P);
+        let z2z2 = mulmod(other.z, other.z, P);
+        let u1 = mulmod(self.x, z2z2, P);
+        let u2 = mulmod(other.x, z1z1, P);
+        let s1 = mulmod(self.y, mulmod(other.z, z2z2, P), P);
+        let s2 = mulmod(other.y, mulmod(self.z, z1z1, P), P);
+
+        if u1 == u2 {
+            return if s1 == s2 {
+                self.double()
+            } else {
+                Self::INFINITY
+            };
+        }
+
+        let h = submod(u2, u1, P);
+        let r = submod(s2, s1, P);
+        let hh = mulmod(h, h, P);
+        let hhh = mulmod(h, hh, P);
+        let u1hh = mulmod(u1, hh, P);
+
+        let x = submod(submod(mulmod(r, r, P), hhh, P), addmod(u1hh, u1hh, P), P);
+        let y = submod(mulmod(r, submod(u1hh, x, P), P), mulmod(s1, hhh, P), P);
+        let z = mulmod(h, mulmod(self.z, other.z, P), P);
+        Self { x, y, z }
+    }
+
+    /// 标量乘法（从高位到低位的倍点-点加）
+    fn mul(&self, scalar: U256) -> Self {
+        let mut result = Self::INFINITY;
+        for i in (0..256).rev() {
+            result = result.double();
+            if scalar.bit(i) {
+                result = result.add(self);
+            }
+        }
+        result
+    }
+
+    /// 转换回仿射坐标
+    fn to_affine(self) -> (U256, U256) {
+        let z_inv = invmod(self.z, P);
+        let z_inv2 = mulmod(z_inv, z_inv, P);
+        let x = mulmod(self.x, z_inv2, P);
+        let y = mulmod(self.y, mulmod(z_inv2, z_inv, P), P);
+        (x, y)
+    }
+}
+
+/// 从签名中恢复签名者地址
+///
+/// - `hash`：被签名的 32 字节消息哈希
+/// - `recovery_id`：0 或 1（即 v - 27），表示 R 点 y 坐标的奇偶性
+/// - `r`、`s`：签名，必须位于 [1, n) 范围内
+///
+/// 签名无效时返回 None。
+pub fn recover(hash: &[u8; 32], recovery_id: u8, r: U256, s: U256) -> Option<Address> {
+    if recovery_id > 1 || r.is_zero() || r >= N || s.is_zero() || s >= N {
+        return None;
+    }
+
+    // 由 x = r 求出 R 点：y^2 = x^3 + 7，p ≡ 3 (mod 4) 时 y = (y^2)^((p + 1) / 4)
+    let y_squared = addmod(mulmod(mulmod(r, r, P), r, P), U256::from(7), P);
+    let mut y = powmod(y_squared, (P >> 2) + 1, P);
+    if mulmod(y, y, P) != y_squared {
+        return None;
+    }
+    if y.bit(0) != (recovery_id == 1) {
+        y = P - y;
+    }
+
+    // Q = r^-1 * (s * R - e * G)
+    let e = U256::from_big_endian(hash) % N;
+    let r_inv = invmod(r, N);
+    let u1 = mulmod(submod(U256::zero(), e, N), r_inv, N);
+    let u2 = mulmod(s, r_inv, N);
+
+    let q = Point::affine(GX, GY)
+        .mul(u1)
+        .add(&Point::affine(r, y).mul(u2));
+    if q.is_infinity() {
+        return None;
+    }
+
+    // 地址为公钥 (x || y) 的 keccak256 哈希的低 20 字节
+    let (x, y) = q.to_affine();
+    let mut public_key = [0u8; 64];
+    x.to_big_endian(&mut public_key[..32]);
+    y.to_big_endian(&mut public_key[32..]);
+    let hash = keccak_hash::keccak(public_key);
+    Some(Address::from_slice(&hash.as_bytes()[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(hex_str: &str) -> U256 {
+        U256::from_big_endian(&hex::decode(hex_str).unwrap())
+    }
+
+    #[test]
+    fn test_generator_is_on_curve() {
+        let lhs = mulmod(GY, GY, P);
+        let rhs = addmod(mulmod(mulmod(GX, GX, P), GX, P), U256::from(7), P);
+        assert_eq!(lhs, rhs);
+    }
+
+    #[test]
+    fn test_recover_known_signature() {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(
+            &hex::decode("18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c")
+                .unwrap(),
+        );
+        let r = word("73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f");
+        let s = word("eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549");
+
+        let signer = recover(&hash, 1, r, s).unwrap();
+        let expected: Address = "a94f5374fce5edbc8e2a8697c15331677e6ebf0b".parse().unwrap();
+        assert_eq!(signer, expected);
+
+        // 换成另一个 recovery id 会恢复出不同的地址
+        assert_ne!(recover(&hash, 0, r, s), Some(expected));
+    }
+
+    #[test]
+    fn test_recover_rejects_out_of_range() {
+        let hash = [1u8; 32];
+        assert_eq!(recover(&hash, 0, U256::zero(), U256::one()), None);
+        assert_eq!(recover(&hash, 0, U256::one(), N), None);
+        assert_eq!(recover(&hash, 2, U256::one(), U256::one()), None);
+    }
+}
@@ -1,7 +1,11 @@
-use ethereum_types::{Address, H256, U256};
+use crate::models::analysis::valid_jumpdests;
+use crate::spec::Spec;
+use ethereum_types::{Address, Bloom, BloomInput, H256, U256};
+use std::collections::HashSet;
 
 /// 基础账户信息
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccountInfo {
     pub balance: U256,
     pub nonce: u64,
@@ -21,16 +25,61 @@ impl Default for AccountInfo {
 }
 
 /// 字节码表示
+///
+/// 序列化时只保存 `0x` 开头的十六进制代码，哈希和跳转目标在反序列化时重新计算。
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
 pub struct Bytecode {
     pub bytes: Vec<u8>,
     pub hash: H256,
+    /// 合法的跳转目标，创建时分析一次并缓存
+    pub jumpdests: HashSet<usize>,
 }
 
 impl Bytecode {
     pub fn new(bytes: Vec<u8>) -> Self {
         let hash = keccak_hash::keccak(&bytes);
-        Self { bytes, hash }
+        let jumpdests = valid_jumpdests(&bytes);
+        Self {
+            bytes,
+            hash,
+            jumpdests,
+        }
+    }
+
+    /// 从十六进制字符串解析字节码，支持可选的 `0x` 前缀
+    pub fn from_hex(hex: &str) -> Result<Self, Error> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let bytes = hex::decode(hex).map_err(|_| Error::InvalidHex)?;
+        Ok(Self::new(bytes))
+    }
+
+    /// `0x` 开头的十六进制表示
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(&self.bytes))
+    }
+
+    /// 检查 JUMP/JUMPI 的目标是否为合法的 JUMPDEST
+    pub fn is_valid_jump(&self, dest: usize) -> bool {
+        self.jumpdests.contains(&dest)
+    }
+}
+
+impl TryFrom<String> for Bytecode {
+    type Error = Error;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        Self::from_hex(&hex)
+    }
+}
+
+impl From<Bytecode> for String {
+    fn from(code: Bytecode) -> Self {
+        code.to_hex()
     }
 }
 
@@ -63,7 +112,8 @@ pub enum CallScheme {
 }
 
 /// 交易信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transaction {
     pub caller: Address,
     pub to: Option<Address>,
@@ -71,16 +121,75 @@ pub struct Transaction {
     pub data: Vec<u8>,
     pub gas_limit: u64,
     pub gas_price: U256,
+    /// 期望的发送者 nonce，None 时不校验
+    pub nonce: Option<u64>,
+    /// 交易签名的链 ID (EIP-155)，None 表示不带链 ID 的旧式交易
+    pub chain_id: Option<u64>,
+    /// 访问列表 (EIP-2930)：预先声明的地址及其存储槽，执行前加入热集合；
+    /// 未启用访问列表的规范忽略该字段
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub access_list: Vec<(Address, Vec<U256>)>,
+}
+
+/// 每笔交易的基础 gas 成本
+pub const GAS_TX_BASE: u64 = 21000;
+
+/// 创建合约交易额外的 gas 成本
+pub const GAS_TX_CREATE: u64 = 32000;
+
+/// 交易调用数据中每个零字节的 gas 成本
+pub const GAS_TX_DATA_ZERO: u64 = 4;
+
+/// 初始化代码每 32 字节的 gas 成本 (EIP-3860)
+pub const GAS_INITCODE_WORD: u64 = 2;
+
+/// 访问列表中每个地址的 gas 成本 (EIP-2930)
+pub const GAS_ACCESS_LIST_ADDRESS: u64 = 2400;
+
+/// 访问列表中每个存储槽的 gas 成本 (EIP-2930)
+pub const GAS_ACCESS_LIST_STORAGE_KEY: u64 = 1900;
+
+impl Transaction {
+    /// 交易的固有 gas 成本，在执行任何指令之前扣除
+    ///
+    /// 包括基础成本、调用数据成本，创建交易另加创建成本，
+    /// 启用 EIP-3860 的规范还要按字收取初始化代码成本，
+    /// 启用访问列表的规范按访问列表中的地址和存储槽收费。
+    pub fn intrinsic_gas<SPEC: Spec>(&self) -> u64 {
+        let zero_bytes = self.data.iter().filter(|byte| **byte == 0).count() as u64;
+        let nonzero_bytes = self.data.len() as u64 - zero_bytes;
+
+        let mut gas =
+            GAS_TX_BASE + zero_bytes * GAS_TX_DATA_ZERO + nonzero_bytes * SPEC::GAS_TX_DATA_NONZERO;
+        if self.to.is_none() {
+            gas += GAS_TX_CREATE;
+            if SPEC::ENABLE_INITCODE_METERING {
+                gas += (self.data.len() as u64).div_ceil(32) * GAS_INITCODE_WORD;
+            }
+        }
+        if SPEC::ENABLE_ACCESS_LISTS {
+            for (_, keys) in &self.access_list {
+                gas += GAS_ACCESS_LIST_ADDRESS + keys.len() as u64 * GAS_ACCESS_LIST_STORAGE_KEY;
+            }
+        }
+        gas
+    }
 }
 
 /// 执行环境
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Environment {
     pub block_number: U256,
     pub block_timestamp: U256,
     pub block_difficulty: U256,
     pub block_gas_limit: u64,
     pub chain_id: U256,
+    /// EIP-1559 基础费用（London 之前不使用）
+    pub base_fee: U256,
+    /// 区块受益人，`execute_block` 把交易的优先费支付给它
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub coinbase: Address,
 }
 
 impl Default for Environment {
@@ -91,29 +200,141 @@ impl Default for Environment {
             block_difficulty: U256::from(1000),
             block_gas_limit: 30_000_000,
             chain_id: U256::from(1),
+            base_fee: U256::from(7),
+            coinbase: Address::zero(),
         }
     }
 }
 
+/// 区块头中与执行相关的字段
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockHeader {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    /// EIP-1559 基础费用（London 之前不使用）
+    pub base_fee: U256,
+    pub gas_limit: u64,
+}
+
+/// 区块的执行结果
+#[derive(Debug, Clone)]
+pub struct BlockResult {
+    /// 每笔交易的执行结果，顺序与区块中的交易一致
+    pub results: Vec<ExecutionResult>,
+    /// 区块内所有交易累计使用的 gas
+    pub gas_used: u64,
+}
+
 /// 执行结果
 #[derive(Debug, Clone)]
 pub struct ExecutionResult {
     pub success: bool,
+    /// 扣除退款后实际消耗的 gas
     pub gas_used: u64,
+    /// 按规范上限计算后的 gas 退款
+    pub gas_refunded: u64,
     pub return_data: Vec<u8>,
     pub logs: Vec<Log>,
 }
 
+/// 一个调用帧或创建帧的执行结果
+///
+/// 内部调用路径使用它区分正常结束、REVERT 和异常终止，只在交易层面转换为 `ExecutionResult`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallResult {
+    /// 正常结束
+    Success { output: Vec<u8>, gas_left: u64 },
+    /// REVERT：状态变更回滚，剩余 gas 退还给调用方
+    Revert { output: Vec<u8>, gas_left: u64 },
+    /// 异常终止：状态变更回滚，通常消耗掉全部 gas；
+    /// 在执行开始前就失败的调用（如超过调用深度）退还全部 gas
+    Halt { reason: Error, gas_left: u64 },
+}
+
+impl CallResult {
+    pub fn is_success(&self) -> bool {
+        matches!(self, CallResult::Success { .. })
+    }
+
+    /// 返回数据，异常终止时为空
+    pub fn output(&self) -> &[u8] {
+        match self {
+            CallResult::Success { output, .. } | CallResult::Revert { output, .. } => output,
+            CallResult::Halt { .. } => &[],
+        }
+    }
+
+    /// 退还给调用方的 gas
+    pub fn gas_left(&self) -> u64 {
+        match self {
+            CallResult::Success { gas_left, .. }
+            | CallResult::Revert { gas_left, .. }
+            | CallResult::Halt { gas_left, .. } => *gas_left,
+        }
+    }
+
+    /// 取出返回数据，异常终止时为空
+    pub fn into_output(self) -> Vec<u8> {
+        match self {
+            CallResult::Success { output, .. } | CallResult::Revert { output, .. } => output,
+            CallResult::Halt { .. } => Vec::new(),
+        }
+    }
+}
+
 /// 日志条目
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Log {
     pub address: Address,
     pub topics: Vec<H256>,
     pub data: Vec<u8>,
 }
 
+/// 交易收据
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Receipt {
+    /// 交易是否执行成功
+    pub status: bool,
+    /// 区块内截至本交易（含）累计使用的 gas
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+    /// 2048 位的日志布隆过滤器，由每条日志的地址和主题计算
+    pub logs_bloom: Bloom,
+}
+
+impl Receipt {
+    pub fn new(status: bool, cumulative_gas_used: u64, logs: Vec<Log>) -> Self {
+        let logs_bloom = logs_bloom(&logs);
+        Self {
+            status,
+            cumulative_gas_used,
+            logs,
+            logs_bloom,
+        }
+    }
+}
+
+/// 计算日志的布隆过滤器
+///
+/// 每个地址和主题取 keccak256 哈希的前三对字节，各自的低 11 位决定置位的位置。
+pub fn logs_bloom(logs: &[Log]) -> Bloom {
+    let mut bloom = Bloom::zero();
+    for log in logs {
+        bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+    bloom
+}
+
 /// 状态变更类型
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StateChange {
     /// 创建新账户
     CreateAccount { address: Address, info: AccountInfo },
@@ -144,7 +365,40 @@ pub enum Error {
     CallDepthExceeded,
     CreateCollision,
     OutOfMemory,
-    DatabaseError,
+    /// 数据库后端返回的错误，保留原始错误的调试输出
+    Database(String),
+    /// REVERT：携带返回给调用方的 revert 数据（通常是 revert reason）
+    Reverted(Vec<u8>),
+    InvalidHex,
+    ReturnDataOutOfBounds,
+    /// 交易的 gas_limit 低于固有成本
+    IntrinsicGasTooLow,
+    /// 交易的 nonce 与发送者账户的 nonce 不一致
+    NonceMismatch {
+        expected: u64,
+        got: u64,
+    },
+    /// 交易的链 ID 与执行环境的链 ID 不一致
+    ChainIdMismatch {
+        expected: U256,
+        got: u64,
+    },
+    /// 转账金额超过发送方余额
+    InsufficientBalance,
+    /// 发送者余额不足以预付 `value + gas_limit * gas_price`
+    InsufficientFunds,
+    /// 初始化代码或部署的代码超过规范的大小上限
+    CodeTooLarge,
+    /// 汇编源码有误：未知助记符、未定义的标签或非法立即数
+    Assembly(String),
+    /// 执行的指令数超过 `EVM::set_max_steps` 设置的上限
+    StepLimitExceeded,
+    /// 区块已使用的 gas 加上交易的 gas 上限超过 `Environment::block_gas_limit`
+    BlockGasLimitExceeded,
+    /// 交易的 gas 价格低于区块的基础费用 (EIP-1559)
+    GasPriceBelowBaseFee,
+    /// genesis 文件有误：JSON 格式、数值或代码无效
+    Genesis(String),
 }
 
 impl std::fmt::Display for Error {
@@ -158,9 +412,98 @@ impl std::fmt::Display for Error {
             Error::CallDepthExceeded => write!(f, "Call depth exceeded"),
             Error::CreateCollision => write!(f, "Create collision"),
             Error::OutOfMemory => write!(f, "Out of memory"),
-            Error::DatabaseError => write!(f, "Database error"),
+            Error::Database(message) => write!(f, "Database error: {}", message),
+            Error::Reverted(_) => write!(f, "Execution reverted"),
+            Error::InvalidHex => write!(f, "Invalid hex string"),
+            Error::ReturnDataOutOfBounds => write!(f, "Return data out of bounds"),
+            Error::IntrinsicGasTooLow => write!(f, "Intrinsic gas too low"),
+            Error::NonceMismatch { expected, got } => {
+                write!(f, "Nonce mismatch: expected {}, got {}", expected, got)
+            }
+            Error::ChainIdMismatch { expected, got } => {
+                write!(f, "Chain id mismatch: expected {}, got {}", expected, got)
+            }
+            Error::InsufficientBalance => write!(f, "Insufficient balance"),
+            Error::InsufficientFunds => write!(f, "Insufficient funds for gas * price + value"),
+            Error::CodeTooLarge => write!(f, "Code too large"),
+            Error::StepLimitExceeded => write!(f, "Step limit exceeded"),
+            Error::BlockGasLimitExceeded => write!(f, "Block gas limit exceeded"),
+            Error::GasPriceBelowBaseFee => write!(f, "Gas price below block base fee"),
+            Error::Genesis(message) => write!(f, "Invalid genesis: {}", message),
+            Error::Assembly(message) => write!(f, "Assembly error: {}", message),
         }
     }
 }
 
 impl std::error::Error for Error {}
+
+impl Error {
+    /// 把数据库后端的错误转换为 `Error::Database`
+    ///
+    /// `Database::Error` 只要求实现 `Debug`，无法为其实现 `From`，
+    /// 因此在调用处使用 `.map_err(Error::database)`。
+    pub fn database<E: std::fmt::Debug>(error: E) -> Self {
+        Error::Database(format!("{:?}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{Berlin, Frontier};
+    use crate::test_utils::addr;
+
+    fn tx(to: Option<Address>, data: Vec<u8>) -> Transaction {
+        Transaction {
+            caller: addr(1),
+            to,
+            data,
+            gas_limit: 1_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_intrinsic_gas_of_calldata_heavy_tx() {
+        // 100 个零字节 + 200 个非零字节
+        let mut data = vec![0u8; 100];
+        data.extend([0xab; 200]);
+        let call = tx(Some(addr(2)), data);
+
+        assert_eq!(call.intrinsic_gas::<Berlin>(), 21000 + 100 * 4 + 200 * 16);
+        // EIP-2028 之前非零字节为 68 gas
+        assert_eq!(call.intrinsic_gas::<Frontier>(), 21000 + 100 * 4 + 200 * 68);
+
+        let create = tx(None, vec![0x60, 0x00]);
+        assert_eq!(create.intrinsic_gas::<Berlin>(), 21000 + 32000 + 16 + 4);
+        assert_eq!(tx(None, Vec::new()).intrinsic_gas::<Berlin>(), 53000);
+    }
+
+    #[test]
+    fn test_logs_bloom_sets_address_bits() {
+        let log = Log {
+            address: addr(0xaa),
+            topics: vec![H256::repeat_byte(1)],
+            data: vec![1, 2, 3],
+        };
+        let receipt = Receipt::new(true, 21000, vec![log.clone()]);
+        assert!(!receipt.logs_bloom.is_zero());
+
+        // keccak256(地址) 的前三对字节各自置一位，位 0 是最后一个字节的最低位
+        let hash = keccak_hash::keccak(log.address.as_bytes());
+        let bloom = receipt.logs_bloom.as_bytes();
+        for i in [0, 2, 4] {
+            let bit = ((hash[i] as usize) << 8 | hash[i + 1] as usize) & 2047;
+            assert_ne!(bloom[255 - bit / 8] & (1 << (bit % 8)), 0);
+        }
+        assert!(receipt
+            .logs_bloom
+            .contains_input(BloomInput::Raw(log.topics[0].as_bytes())));
+        // 日志数据不参与布隆过滤器
+        assert!(!receipt
+            .logs_bloom
+            .contains_input(BloomInput::Raw(&log.data)));
+
+        assert!(Receipt::new(true, 21000, Vec::new()).logs_bloom.is_zero());
+    }
+}
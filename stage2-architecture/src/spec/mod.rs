@@ -1,3 +1,46 @@
+use crate::models::{op, Opcode};
+
+// === 固定 gas 成本（各规范相同）===
+
+/// SELFDESTRUCT 的基础成本（EIP-150）
+pub(crate) const GAS_SELFDESTRUCT: u64 = 5000;
+
+/// JUMPDEST 的成本
+pub(crate) const GAS_JUMPDEST: u64 = 1;
+
+/// 复制类指令（CALLDATACOPY 等）每个字的成本
+pub(crate) const GAS_COPY: u64 = 3;
+
+/// EIP-2929：首次访问存储槽的冷访问附加成本
+pub(crate) const GAS_COLD_SLOAD: u64 = 2100;
+
+/// EIP-2929：首次访问账户的冷访问成本
+pub(crate) const GAS_COLD_ACCOUNT_ACCESS: u64 = 2600;
+
+/// EIP-2929：再次访问账户（或存储槽）的热访问成本
+pub(crate) const GAS_WARM_ACCESS: u64 = 100;
+
+/// EIP-2929 之前 BALANCE / EXTCODESIZE / EXTCODECOPY 的固定成本（Frontier）
+pub(crate) const GAS_EXT_ACCOUNT_LEGACY: u64 = 20;
+
+/// EXP 指令的固定成本，另按指数字节数收取 SPEC::GAS_EXP_BYTE
+pub(crate) const GAS_EXP: u64 = 10;
+
+/// LOG 指令的固定成本
+pub(crate) const GAS_LOG: u64 = 375;
+
+/// LOG 指令每个主题的成本
+pub(crate) const GAS_LOG_TOPIC: u64 = 375;
+
+/// LOG 指令每字节数据的成本
+pub(crate) const GAS_LOG_DATA: u64 = 8;
+
+/// 携带非零 value 的 CALL / CALLCODE 的附加成本
+pub(crate) const GAS_CALL_VALUE: u64 = 9000;
+
+/// Keccak-256 每个字的哈希成本，CREATE2 按初始化代码的字数收取
+pub(crate) const GAS_KECCAK256_WORD: u64 = 6;
+
 /// EVM 规范 trait
 ///
 /// 这个 trait 定义了不同以太坊硬分叉的规范参数，
@@ -9,9 +52,13 @@ pub trait Spec: Clone + 'static {
     // === Gas 成本常量 ===
 
     /// CALL 系列指令的基础 gas 成本
+    ///
+    /// 只在 EIP-2929 之前生效，之后 CALL 系列指令按冷/热访问收费。
     const GAS_CALL: u64;
 
     /// SLOAD 指令的 gas 成本
+    ///
+    /// 只在 EIP-2929 之前生效，之后 SLOAD 按冷/热访问收费。
     const GAS_SLOAD: u64;
 
     /// SSTORE 设置新值的 gas 成本
@@ -23,29 +70,73 @@ pub trait Spec: Clone + 'static {
     /// SSTORE 清除值的 gas 退款
     const GAS_SSTORE_CLEAR_REFUND: i64;
 
+    /// SELFDESTRUCT 的 gas 退款
+    const GAS_SELFDESTRUCT_REFUND: i64;
+
+    /// 退款上限的除数：退款最多为 gas_used / MAX_REFUND_QUOTIENT
+    const MAX_REFUND_QUOTIENT: u64;
+
     /// CREATE 指令的基础 gas 成本
     const GAS_CREATE: u64;
 
     /// 每字节代码部署的 gas 成本
     const GAS_CODE_DEPOSIT: u64;
 
+    /// EXP 指令中指数每字节的 gas 成本
+    const GAS_EXP_BYTE: u64;
+
+    /// 交易调用数据中每个非零字节的 gas 成本
+    const GAS_TX_DATA_NONZERO: u64;
+
+    /// 携带非零 value 的调用额外转发给被调用方的 gas 补贴
+    const CALL_STIPEND: u64 = 2300;
+
+    // === Gas 等级（黄皮书中的 Wbase / Wverylow / Wlow / Wmid / Whigh）===
+
+    const GAS_BASE: u64 = 2;
+    const GAS_VERY_LOW: u64 = 3;
+    const GAS_LOW: u64 = 5;
+    const GAS_MID: u64 = 8;
+    const GAS_HIGH: u64 = 10;
+
     // === EIP 特性开关 ===
 
     /// 是否启用 CREATE2 指令 (EIP-1014)
     const ENABLE_CREATE2: bool;
 
+    /// 是否启用 SHL/SHR/SAR 移位指令 (EIP-145，Constantinople 起)
+    const ENABLE_BITWISE_SHIFTING: bool;
+
     /// 是否启用 CHAINID 指令 (EIP-1344)
     const ENABLE_CHAINID: bool;
 
     /// 是否启用 SELFBALANCE 指令 (EIP-1884)
     const ENABLE_SELFBALANCE: bool;
 
+    /// 是否启用 EXTCODEHASH 指令 (EIP-1052)
+    const ENABLE_EXTCODEHASH: bool;
+
     /// 是否启用访问列表 (EIP-2930)
     const ENABLE_ACCESS_LISTS: bool;
 
+    /// 是否区分冷/热存储访问 (EIP-2929)
+    const ENABLE_EIP2929: bool;
+
     /// 是否启用 EIP-1559 手续费机制
     const ENABLE_EIP1559: bool;
 
+    /// MODEXP 预编译合约是否使用 EIP-2565 的 gas 公式
+    const ENABLE_EIP2565: bool;
+
+    /// 是否对初始化代码按字收费 (EIP-3860，Shanghai 起)
+    const ENABLE_INITCODE_METERING: bool;
+
+    /// 是否启用 MCOPY 指令 (EIP-5656，Cancun 起)
+    const ENABLE_MCOPY: bool;
+
+    /// 是否启用瞬时存储 TLOAD/TSTORE (EIP-1153，Cancun 起)
+    const ENABLE_TRANSIENT_STORAGE: bool;
+
     // === 系统限制参数 ===
 
     /// 栈最大深度
@@ -57,9 +148,93 @@ pub trait Spec: Clone + 'static {
     /// 调用栈最大深度
     const CALL_DEPTH_LIMIT: usize;
 
-    /// 代码最大大小
+    /// 部署代码最大大小 (EIP-170)
     const MAX_CODE_SIZE: usize;
 
+    /// 初始化代码最大大小 (EIP-3860)
+    const MAX_INITCODE_SIZE: usize;
+
+    /// 指令的静态 gas 成本
+    ///
+    /// 解释器在分发每条内置指令之前按它扣除 gas，指令自身只收取动态部分
+    /// （内存扩展、EXP 指数字节、SSTORE 的 SET、冷访问附加费、转发给子调用的 gas 等）。
+    /// 默认按黄皮书的等级映射，当前规范不支持的指令返回 0。
+    /// 规范可以覆盖它来调整单条指令的成本。
+    fn opcode_gas(opcode: Opcode) -> u64 {
+        match opcode.as_u8() {
+            op::STOP | op::RETURN | op::REVERT => 0,
+            op::ADD
+            | op::SUB
+            | op::LT
+            | op::GT
+            | op::SLT
+            | op::SGT
+            | op::EQ
+            | op::ISZERO
+            | op::AND
+            | op::OR
+            | op::XOR
+            | op::NOT
+            | op::BYTE => Self::GAS_VERY_LOW,
+            op::SHL | op::SHR | op::SAR if Self::ENABLE_BITWISE_SHIFTING => Self::GAS_VERY_LOW,
+            op::MUL | op::DIV | op::SDIV | op::MOD | op::SMOD => Self::GAS_LOW,
+            op::ADDMOD | op::MULMOD => Self::GAS_MID,
+            op::EXP => GAS_EXP,
+            op::ADDRESS
+            | op::CALLER
+            | op::CALLVALUE
+            | op::CALLDATASIZE
+            | op::RETURNDATASIZE
+            | op::TIMESTAMP
+            | op::NUMBER
+            | op::PREVRANDAO
+            | op::GASLIMIT
+            | op::POP
+            | op::PC
+            | op::MSIZE
+            | op::GAS => Self::GAS_BASE,
+            op::CHAINID if Self::ENABLE_CHAINID => Self::GAS_BASE,
+            op::SELFBALANCE if Self::ENABLE_SELFBALANCE => Self::GAS_LOW,
+            op::BASEFEE if Self::ENABLE_EIP1559 => Self::GAS_BASE,
+            op::CALLDATALOAD
+            | op::CALLDATACOPY
+            | op::CODECOPY
+            | op::RETURNDATACOPY
+            | op::MLOAD
+            | op::MSTORE => Self::GAS_VERY_LOW,
+            op::MCOPY if Self::ENABLE_MCOPY => Self::GAS_VERY_LOW,
+            op::TLOAD | op::TSTORE if Self::ENABLE_TRANSIENT_STORAGE => GAS_WARM_ACCESS,
+            op::SLOAD if Self::ENABLE_EIP2929 => GAS_WARM_ACCESS,
+            op::SLOAD => Self::GAS_SLOAD,
+            op::SSTORE if Self::ENABLE_EIP2929 => GAS_WARM_ACCESS,
+            op::SSTORE => Self::GAS_SSTORE_RESET,
+            op::JUMP => Self::GAS_MID,
+            op::JUMPI => Self::GAS_HIGH,
+            op::JUMPDEST => GAS_JUMPDEST,
+            op::PUSH1..=op::PUSH32 => Self::GAS_VERY_LOW,
+            op::CREATE => Self::GAS_CREATE,
+            op::CREATE2 if Self::ENABLE_CREATE2 => Self::GAS_CREATE,
+            op::CALL | op::CALLCODE | op::DELEGATECALL | op::STATICCALL => {
+                if Self::ENABLE_EIP2929 {
+                    GAS_WARM_ACCESS
+                } else {
+                    Self::GAS_CALL
+                }
+            }
+            op::SELFDESTRUCT => GAS_SELFDESTRUCT,
+            byte @ op::LOG0..=op::LOG4 => GAS_LOG + GAS_LOG_TOPIC * (byte - op::LOG0) as u64,
+            op::EXTCODEHASH if !Self::ENABLE_EXTCODEHASH => 0,
+            op::BALANCE | op::EXTCODESIZE | op::EXTCODECOPY | op::EXTCODEHASH => {
+                if Self::ENABLE_EIP2929 {
+                    GAS_WARM_ACCESS
+                } else {
+                    GAS_EXT_ACCOUNT_LEGACY
+                }
+            }
+            _ => 0,
+        }
+    }
+
     // === 预编译合约支持 ===
 
     /// 获取支持的预编译合约地址列表
@@ -79,26 +254,38 @@ impl Spec for Berlin {
     const NAME: &'static str = "Berlin";
 
     // Berlin 的 Gas 成本（受 EIP-2929 影响）
-    const GAS_CALL: u64 = 700; // 冷访问成本更高
+    const GAS_CALL: u64 = 700;
     const GAS_SLOAD: u64 = 800; // 冷存储读取成本
     const GAS_SSTORE_SET: u64 = 20000;
     const GAS_SSTORE_RESET: u64 = 5000;
-    const GAS_SSTORE_CLEAR_REFUND: i64 = 4800;
+    const GAS_SSTORE_CLEAR_REFUND: i64 = 15000;
+    const GAS_SELFDESTRUCT_REFUND: i64 = 24000;
+    const MAX_REFUND_QUOTIENT: u64 = 2;
     const GAS_CREATE: u64 = 32000;
     const GAS_CODE_DEPOSIT: u64 = 200;
+    const GAS_EXP_BYTE: u64 = 50; // EIP-160
+    const GAS_TX_DATA_NONZERO: u64 = 16; // EIP-2028
 
     // Berlin 支持的 EIP 特性
     const ENABLE_CREATE2: bool = true;
+    const ENABLE_BITWISE_SHIFTING: bool = true;
     const ENABLE_CHAINID: bool = true;
     const ENABLE_SELFBALANCE: bool = true;
+    const ENABLE_EXTCODEHASH: bool = true;
     const ENABLE_ACCESS_LISTS: bool = true; // EIP-2930
+    const ENABLE_EIP2929: bool = true;
     const ENABLE_EIP1559: bool = false; // London 才有
+    const ENABLE_EIP2565: bool = true;
+    const ENABLE_INITCODE_METERING: bool = false;
+    const ENABLE_MCOPY: bool = false;
+    const ENABLE_TRANSIENT_STORAGE: bool = false;
 
     // 系统限制
     const STACK_LIMIT: usize = 1024;
     const MEMORY_LIMIT: usize = 0x1FFFFFFE0;
     const CALL_DEPTH_LIMIT: usize = 1024;
     const MAX_CODE_SIZE: usize = 0x6000; // EIP-170
+    const MAX_INITCODE_SIZE: usize = usize::MAX; // Shanghai 之前无限制
 
     fn precompiles() -> &'static [u8] {
         // Berlin 支持 1-9 号预编译合约
@@ -123,22 +310,34 @@ impl Spec for London {
     const GAS_SLOAD: u64 = 800;
     const GAS_SSTORE_SET: u64 = 20000;
     const GAS_SSTORE_RESET: u64 = 5000;
-    const GAS_SSTORE_CLEAR_REFUND: i64 = 0; // EIP-3529: 取消清除退款
+    const GAS_SSTORE_CLEAR_REFUND: i64 = 4800; // EIP-3529: 降低清除退款
+    const GAS_SELFDESTRUCT_REFUND: i64 = 0; // EIP-3529: 取消销毁退款
+    const MAX_REFUND_QUOTIENT: u64 = 5; // EIP-3529: 退款上限从 1/2 降为 1/5
     const GAS_CREATE: u64 = 32000;
     const GAS_CODE_DEPOSIT: u64 = 200;
+    const GAS_EXP_BYTE: u64 = 50;
+    const GAS_TX_DATA_NONZERO: u64 = 16;
 
     // London 的 EIP 特性
     const ENABLE_CREATE2: bool = true;
+    const ENABLE_BITWISE_SHIFTING: bool = true;
     const ENABLE_CHAINID: bool = true;
     const ENABLE_SELFBALANCE: bool = true;
+    const ENABLE_EXTCODEHASH: bool = true;
     const ENABLE_ACCESS_LISTS: bool = true;
+    const ENABLE_EIP2929: bool = true;
     const ENABLE_EIP1559: bool = true; // 新增 EIP-1559
+    const ENABLE_EIP2565: bool = true;
+    const ENABLE_INITCODE_METERING: bool = false; // Shanghai 才有
+    const ENABLE_MCOPY: bool = false; // Cancun 才有
+    const ENABLE_TRANSIENT_STORAGE: bool = false;
 
     // 系统限制与 Berlin 相同
     const STACK_LIMIT: usize = 1024;
     const MEMORY_LIMIT: usize = 0x1FFFFFFE0;
     const CALL_DEPTH_LIMIT: usize = 1024;
     const MAX_CODE_SIZE: usize = 0x6000;
+    const MAX_INITCODE_SIZE: usize = usize::MAX;
 
     fn precompiles() -> &'static [u8] {
         // London 支持 1-9 号预编译合约
@@ -146,6 +345,58 @@ impl Spec for London {
     }
 }
 
+/// Cancun 硬分叉规范 (2024年3月)
+///
+/// 在 London 的基础上包含 Shanghai 与 Cancun 的执行层变更：
+/// - EIP-3860: 限制并按字收费初始化代码
+/// - EIP-1153: 瞬时存储 TLOAD/TSTORE
+/// - EIP-5656: MCOPY 操作码
+#[derive(Clone, Debug)]
+pub struct Cancun;
+
+impl Spec for Cancun {
+    const NAME: &'static str = "Cancun";
+
+    // Gas 成本与 London 相同
+    const GAS_CALL: u64 = 700;
+    const GAS_SLOAD: u64 = 800;
+    const GAS_SSTORE_SET: u64 = 20000;
+    const GAS_SSTORE_RESET: u64 = 5000;
+    const GAS_SSTORE_CLEAR_REFUND: i64 = 4800;
+    const GAS_SELFDESTRUCT_REFUND: i64 = 0;
+    const MAX_REFUND_QUOTIENT: u64 = 5;
+    const GAS_CREATE: u64 = 32000;
+    const GAS_CODE_DEPOSIT: u64 = 200;
+    const GAS_EXP_BYTE: u64 = 50;
+    const GAS_TX_DATA_NONZERO: u64 = 16;
+
+    // Cancun 的 EIP 特性
+    const ENABLE_CREATE2: bool = true;
+    const ENABLE_BITWISE_SHIFTING: bool = true;
+    const ENABLE_CHAINID: bool = true;
+    const ENABLE_SELFBALANCE: bool = true;
+    const ENABLE_EXTCODEHASH: bool = true;
+    const ENABLE_ACCESS_LISTS: bool = true;
+    const ENABLE_EIP2929: bool = true;
+    const ENABLE_EIP1559: bool = true;
+    const ENABLE_EIP2565: bool = true;
+    const ENABLE_INITCODE_METERING: bool = true; // EIP-3860
+    const ENABLE_MCOPY: bool = true; // EIP-5656
+    const ENABLE_TRANSIENT_STORAGE: bool = true; // EIP-1153
+
+    // 系统限制
+    const STACK_LIMIT: usize = 1024;
+    const MEMORY_LIMIT: usize = 0x1FFFFFFE0;
+    const CALL_DEPTH_LIMIT: usize = 1024;
+    const MAX_CODE_SIZE: usize = 0x6000;
+    const MAX_INITCODE_SIZE: usize = 2 * 0x6000; // EIP-3860
+
+    fn precompiles() -> &'static [u8] {
+        // 0x0a（KZG 点评估）尚未实现
+        &[1, 2, 3, 4, 5, 6, 7, 8, 9]
+    }
+}
+
 /// 旧版规范（用于对比）
 #[derive(Clone, Debug)]
 pub struct Frontier;
@@ -159,21 +410,33 @@ impl Spec for Frontier {
     const GAS_SSTORE_SET: u64 = 20000;
     const GAS_SSTORE_RESET: u64 = 5000;
     const GAS_SSTORE_CLEAR_REFUND: i64 = 15000; // 高退款
+    const GAS_SELFDESTRUCT_REFUND: i64 = 24000;
+    const MAX_REFUND_QUOTIENT: u64 = 2;
     const GAS_CREATE: u64 = 32000;
     const GAS_CODE_DEPOSIT: u64 = 200;
+    const GAS_EXP_BYTE: u64 = 10; // EIP-160 之前
+    const GAS_TX_DATA_NONZERO: u64 = 68; // EIP-2028 之前
 
     // Frontier 不支持现代 EIP 特性
     const ENABLE_CREATE2: bool = false;
+    const ENABLE_BITWISE_SHIFTING: bool = false;
     const ENABLE_CHAINID: bool = false;
     const ENABLE_SELFBALANCE: bool = false;
+    const ENABLE_EXTCODEHASH: bool = false;
     const ENABLE_ACCESS_LISTS: bool = false;
+    const ENABLE_EIP2929: bool = false;
     const ENABLE_EIP1559: bool = false;
+    const ENABLE_EIP2565: bool = false;
+    const ENABLE_INITCODE_METERING: bool = false;
+    const ENABLE_MCOPY: bool = false;
+    const ENABLE_TRANSIENT_STORAGE: bool = false;
 
     // 系统限制
     const STACK_LIMIT: usize = 1024;
     const MEMORY_LIMIT: usize = 0x1FFFFFFE0;
     const CALL_DEPTH_LIMIT: usize = 1024;
     const MAX_CODE_SIZE: usize = usize::MAX; // 无限制
+    const MAX_INITCODE_SIZE: usize = usize::MAX;
 
     fn precompiles() -> &'static [u8] {
         // Frontier 仅支持 1-4 号预编译合约
@@ -181,6 +444,154 @@ impl Spec for Frontier {
     }
 }
 
+/// 导出规范下全部已定义操作码的 gas 表
+///
+/// 每一项为 (操作码, 助记符, 静态 gas)，按操作码升序排列，gas 取自 `Spec::opcode_gas`。
+pub fn gas_table<S: Spec>() -> [(u8, &'static str, u64); Opcode::COUNT] {
+    std::array::from_fn(|i| {
+        let opcode = Opcode::ALL[i];
+        (opcode.as_u8(), opcode.mnemonic(), S::opcode_gas(opcode))
+    })
+}
+
+/// 主网 Berlin 硬分叉的激活区块
+pub const BERLIN_BLOCK: u64 = 12_244_000;
+
+/// 主网 London 硬分叉的激活区块
+pub const LONDON_BLOCK: u64 = 12_965_000;
+
+/// 主网 Cancun 硬分叉的激活时间戳
+pub const CANCUN_TIMESTAMP: u64 = 1_710_338_135;
+
+/// 运行时的规范标识
+///
+/// `Spec` 是编译期类型参数，需要在运行时选择规范时用它分发到对应的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecId {
+    Frontier,
+    Berlin,
+    London,
+    Cancun,
+}
+
+impl SpecId {
+    /// 按主网的分叉时间表选出某个区块生效的规范
+    ///
+    /// 本 crate 只实现了 Frontier、Berlin、London 和 Cancun，Berlin 之前的区块
+    /// 一律按 Frontier 处理。合并之后的分叉按时间戳激活，
+    /// Cancun 之前的 Paris、Shanghai 按 London 处理。
+    pub fn for_block(block_number: u64, timestamp: u64) -> Self {
+        if timestamp >= CANCUN_TIMESTAMP {
+            SpecId::Cancun
+        } else if block_number >= LONDON_BLOCK {
+            SpecId::London
+        } else if block_number >= BERLIN_BLOCK {
+            SpecId::Berlin
+        } else {
+            SpecId::Frontier
+        }
+    }
+
+    /// 按名称查找规范（不区分大小写），用于命令行等运行时输入
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            SpecId::Frontier,
+            SpecId::Berlin,
+            SpecId::London,
+            SpecId::Cancun,
+        ]
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name))
+    }
+
+    /// 规范名称，与 `Spec::NAME` 一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpecId::Frontier => Frontier::NAME,
+            SpecId::Berlin => Berlin::NAME,
+            SpecId::London => London::NAME,
+            SpecId::Cancun => Cancun::NAME,
+        }
+    }
+}
+
+/// 单项参数在两个规范之间的取值
+///
+/// Gas 成本、退款和系统限制的类型各不相同，统一用 `i128` 保存以便求差。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecDiffEntry {
+    pub name: &'static str,
+    pub from: i128,
+    pub to: i128,
+}
+
+impl SpecDiffEntry {
+    fn new(name: &'static str, from: impl Into<i128>, to: impl Into<i128>) -> Self {
+        Self {
+            name,
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// 从旧规范到新规范的变化量
+    pub fn delta(&self) -> i128 {
+        self.to - self.from
+    }
+
+    pub fn changed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// 两个规范之间全部 Gas 常量和系统限制的对比报告
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecDiff {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub gas: Vec<SpecDiffEntry>,
+    pub limits: Vec<SpecDiffEntry>,
+}
+
+impl SpecDiff {
+    /// 按名称查找某一项
+    pub fn get(&self, name: &str) -> Option<&SpecDiffEntry> {
+        self.entries().find(|entry| entry.name == name)
+    }
+
+    /// 所有取值发生变化的项
+    pub fn changed(&self) -> impl Iterator<Item = &SpecDiffEntry> {
+        self.entries().filter(|entry| entry.changed())
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &SpecDiffEntry> {
+        self.gas.iter().chain(&self.limits)
+    }
+
+    /// 格式化为对齐的文本表格，变化的项以 `*` 标出
+    pub fn format_table(&self) -> String {
+        let mut table = format!(
+            "  {:<22} {:>14} {:>14} {:>10}\n",
+            "参数", self.from, self.to, "变化"
+        );
+        for (title, entries) in [("Gas 成本", &self.gas), ("系统限制", &self.limits)] {
+            table.push_str(&format!("  -- {} --\n", title));
+            for entry in entries {
+                let marker = if entry.changed() { "*" } else { " " };
+                table.push_str(&format!(
+                    "{} {:<22} {:>14} {:>14} {:>+10}\n",
+                    marker,
+                    entry.name,
+                    entry.from,
+                    entry.to,
+                    entry.delta()
+                ));
+            }
+        }
+        table
+    }
+}
+
 /// 规范比较工具
 pub struct SpecComparison;
 
@@ -209,6 +620,60 @@ impl SpecComparison {
         ]
     }
 
+    /// 比较两个规范的全部 Gas 常量和系统限制
+    pub fn compare_all<S1: Spec, S2: Spec>() -> SpecDiff {
+        let usize_entry =
+            |name, from: usize, to: usize| SpecDiffEntry::new(name, from as u64, to as u64);
+        SpecDiff {
+            from: S1::NAME,
+            to: S2::NAME,
+            gas: vec![
+                SpecDiffEntry::new("CALL", S1::GAS_CALL, S2::GAS_CALL),
+                SpecDiffEntry::new("SLOAD", S1::GAS_SLOAD, S2::GAS_SLOAD),
+                SpecDiffEntry::new("SSTORE_SET", S1::GAS_SSTORE_SET, S2::GAS_SSTORE_SET),
+                SpecDiffEntry::new("SSTORE_RESET", S1::GAS_SSTORE_RESET, S2::GAS_SSTORE_RESET),
+                SpecDiffEntry::new(
+                    "SSTORE_CLEAR_REFUND",
+                    S1::GAS_SSTORE_CLEAR_REFUND,
+                    S2::GAS_SSTORE_CLEAR_REFUND,
+                ),
+                SpecDiffEntry::new(
+                    "SELFDESTRUCT_REFUND",
+                    S1::GAS_SELFDESTRUCT_REFUND,
+                    S2::GAS_SELFDESTRUCT_REFUND,
+                ),
+                SpecDiffEntry::new(
+                    "MAX_REFUND_QUOTIENT",
+                    S1::MAX_REFUND_QUOTIENT,
+                    S2::MAX_REFUND_QUOTIENT,
+                ),
+                SpecDiffEntry::new("CREATE", S1::GAS_CREATE, S2::GAS_CREATE),
+                SpecDiffEntry::new("CODE_DEPOSIT", S1::GAS_CODE_DEPOSIT, S2::GAS_CODE_DEPOSIT),
+                SpecDiffEntry::new("EXP_BYTE", S1::GAS_EXP_BYTE, S2::GAS_EXP_BYTE),
+                SpecDiffEntry::new(
+                    "TX_DATA_NONZERO",
+                    S1::GAS_TX_DATA_NONZERO,
+                    S2::GAS_TX_DATA_NONZERO,
+                ),
+            ],
+            limits: vec![
+                usize_entry("STACK_LIMIT", S1::STACK_LIMIT, S2::STACK_LIMIT),
+                usize_entry("MEMORY_LIMIT", S1::MEMORY_LIMIT, S2::MEMORY_LIMIT),
+                usize_entry(
+                    "CALL_DEPTH_LIMIT",
+                    S1::CALL_DEPTH_LIMIT,
+                    S2::CALL_DEPTH_LIMIT,
+                ),
+                usize_entry("MAX_CODE_SIZE", S1::MAX_CODE_SIZE, S2::MAX_CODE_SIZE),
+                usize_entry(
+                    "MAX_INITCODE_SIZE",
+                    S1::MAX_INITCODE_SIZE,
+                    S2::MAX_INITCODE_SIZE,
+                ),
+            ],
+        }
+    }
+
     /// 比较两个规范的特性支持
     pub fn compare_features<S1: Spec, S2: Spec>() -> Vec<(String, bool, bool)> {
         vec![
@@ -240,3 +705,93 @@ impl SpecComparison {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opcode_gas_tiers() {
+        // 等级成本在各规范之间相同
+        assert_eq!(Frontier::opcode_gas(Opcode::Add), Frontier::GAS_VERY_LOW);
+        assert_eq!(
+            Frontier::opcode_gas(Opcode::Add),
+            Berlin::opcode_gas(Opcode::Add)
+        );
+        assert_eq!(Berlin::opcode_gas(Opcode::Mul), 5);
+        assert_eq!(Berlin::opcode_gas(Opcode::Jumpi), 10);
+
+        // SLOAD 在 EIP-2929 之前使用规范自己的成本，之后与 BALANCE 一样为热访问成本
+        assert_eq!(Frontier::opcode_gas(Opcode::Sload), 200);
+        assert_eq!(Berlin::opcode_gas(Opcode::Sload), 100);
+        assert_eq!(
+            Berlin::opcode_gas(Opcode::Sload),
+            Berlin::opcode_gas(Opcode::Balance)
+        );
+
+        // 未启用的指令没有成本
+        assert_eq!(Frontier::opcode_gas(Opcode::Chainid), 0);
+        assert_eq!(Berlin::opcode_gas(Opcode::Chainid), 2);
+    }
+
+    #[test]
+    fn test_gas_table() {
+        let table = gas_table::<London>();
+        let cost = |mnemonic| {
+            table
+                .iter()
+                .find(|(_, name, _)| *name == mnemonic)
+                .map(|(_, _, gas)| *gas)
+                .unwrap()
+        };
+        assert_eq!(cost("ADD"), 3);
+        assert_eq!(cost("MUL"), 5);
+        assert_eq!(cost("SLOAD"), 100);
+        assert_eq!(cost("BALANCE"), 100);
+
+        // 表覆盖所有操作码，成本与 opcode_gas 一致
+        assert_eq!(table.len(), Opcode::COUNT);
+        for (opcode, (byte, _, gas)) in Opcode::ALL.iter().zip(table) {
+            assert_eq!((opcode.as_u8(), London::opcode_gas(*opcode)), (byte, gas));
+        }
+    }
+
+    #[test]
+    fn test_spec_id_from_name() {
+        assert_eq!(SpecId::from_name("Berlin"), Some(SpecId::Berlin));
+        assert_eq!(SpecId::from_name("london"), Some(SpecId::London));
+        assert_eq!(SpecId::from_name("FRONTIER"), Some(SpecId::Frontier));
+        assert_eq!(SpecId::from_name("cancun"), Some(SpecId::Cancun));
+        assert_eq!(SpecId::from_name("shanghai"), None);
+    }
+
+    #[test]
+    fn test_compare_all_frontier_to_london() {
+        let diff = SpecComparison::compare_all::<Frontier, London>();
+        assert_eq!((diff.from, diff.to), ("Frontier", "London"));
+
+        // EIP-3529 降低了清除退款并取消了销毁退款
+        let clear = diff.get("SSTORE_CLEAR_REFUND").unwrap();
+        assert_eq!((clear.from, clear.to), (15000, 4800));
+        assert_eq!(clear.delta(), -10200);
+        assert_eq!(diff.get("SELFDESTRUCT_REFUND").unwrap().to, 0);
+
+        let changed: Vec<_> = diff.changed().map(|entry| entry.name).collect();
+        assert!(changed.contains(&"SSTORE_CLEAR_REFUND"));
+        assert!(changed.contains(&"MAX_CODE_SIZE"));
+        assert!(!changed.contains(&"SSTORE_SET"));
+        assert!(!changed.contains(&"STACK_LIMIT"));
+
+        let table = diff.format_table();
+        assert!(table.contains("* SSTORE_CLEAR_REFUND"));
+        assert!(table.contains("  CREATE"));
+
+        // 同一规范之间没有差异
+        assert_eq!(
+            SpecComparison::compare_all::<London, London>()
+                .changed()
+                .count(),
+            0
+        );
+    }
+}
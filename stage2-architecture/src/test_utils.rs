@@ -0,0 +1,54 @@
+//! 测试辅助函数
+
+use crate::database::InMemoryDB;
+use crate::models::{AccountInfo, Transaction};
+use ethereum_types::{Address, U256};
+
+/// 每个字节都为 `n` 的地址，`addr(1)` 即 `Address::from([1u8; 20])`
+pub fn addr(n: u8) -> Address {
+    Address::repeat_byte(n)
+}
+
+/// 由 `addr(1)` 发往 `to` 的调用交易，gas limit 为 1_000_000，其余字段取默认值
+pub fn call_tx(to: Address) -> Transaction {
+    Transaction {
+        caller: addr(1),
+        to: Some(to),
+        gas_limit: 1_000_000,
+        ..Default::default()
+    }
+}
+
+/// 在数据库中部署合约代码，账户 nonce 为 1、余额为 0
+pub fn deploy(db: &mut InMemoryDB, address: Address, code: Vec<u8>) {
+    db.insert_account(
+        address,
+        AccountInfo {
+            balance: U256::zero(),
+            nonce: 1,
+            code_hash: keccak_hash::keccak(&code),
+            code: Some(code),
+        },
+    );
+}
+
+/// 由种子确定性生成的地址：取 keccak256(seed) 的后 20 字节
+pub fn random_addr(seed: u64) -> Address {
+    Address::from_slice(&keccak_hash::keccak(seed.to_be_bytes())[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addr_helpers() {
+        assert_eq!(addr(1), Address::from([1u8; 20]));
+        assert_eq!(addr(0xaa), Address::from([0xaa; 20]));
+
+        // 相同种子得到相同地址，不同种子得到不同地址
+        assert_eq!(random_addr(7), random_addr(7));
+        assert_ne!(random_addr(7), random_addr(8));
+        assert_ne!(random_addr(0), Address::zero());
+    }
+}